@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use handlebars::template::{Parameter, TemplateElement};
+use handlebars::Template;
+use serde_json::Value;
+
+/// Identifies a single `{{#switch}}` block.
+pub(crate) type ArmsKey = (Option<String>, (usize, usize));
+
+/// The order to check the `{{#case}}` arms of a block in.
+#[derive(Debug)]
+struct ArmOrder {
+    /// The arms by template index, most recently matched first.
+    order: Vec<usize>,
+    /// Whether no value can match two of the arms, which makes the order
+    /// the arms are checked in irrelevant.
+    disjoint: bool,
+}
+
+/// Adaptive Order
+///
+/// Tracks which `{{#case}}` arm of every `{{#switch ... adaptive=true}}`
+/// block matched last and checks it first on the next render, moving each
+/// matched arm to the front. For heavily skewed values, such as a status
+/// that is `"active"` in most rows, the switch then usually compares its
+/// value against a single arm instead of every arm listed before it.
+///
+/// Only blocks whose arms are all `{{#case}}` and `{{#default}}` arms
+/// listing nothing but literals, no two of which match each other, are
+/// reordered, since only then exactly the arm template order picks can
+/// match. Other blocks render as usual. The handle is cheap to clone and
+/// every clone shares the same orders.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{AdaptiveOrder, SwitchHelper};
+///
+/// let order = AdaptiveOrder::new();
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(SwitchHelper::new().with_adaptive_order(order.clone())),
+/// );
+/// handlebars
+///     .register_template_string(
+///         "row",
+///         "{{#switch status adaptive=true}}\
+///             {{#case \"deleted\"}}D{{/case}}\
+///             {{#case \"active\"}}A{{/case}}\
+///         {{/switch}}",
+///     )
+///     .unwrap();
+///
+/// for _ in 0..3 {
+///     assert_eq!(handlebars.render("row", &json!({"status": "active"})).unwrap(), "A");
+/// }
+/// assert_eq!(order.len(), 1);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AdaptiveOrder {
+    orders: Arc<Mutex<HashMap<ArmsKey, ArmOrder>>>,
+}
+
+impl AdaptiveOrder {
+    /// Creates a handle without any tracked blocks.
+    pub fn new() -> AdaptiveOrder {
+        AdaptiveOrder::default()
+    }
+
+    /// Returns the number of tracked blocks.
+    pub fn len(&self) -> usize {
+        self.orders().len()
+    }
+
+    /// Returns `true` if no block is tracked.
+    pub fn is_empty(&self) -> bool {
+        self.orders().is_empty()
+    }
+
+    /// Forgets the order of every block, for example after templates change.
+    pub fn clear(&self) {
+        self.orders().clear();
+    }
+
+    /// Picks the index of the `{{#case}}` of the block `arms` that `matches`,
+    /// checking the most recently matched arms first, or the number of arms
+    /// when none matches. `eq` tells whether two literals match each other
+    /// and is asked once per block. Returns `None` when the order of the
+    /// arms matters.
+    pub(crate) fn select(
+        &self,
+        key: ArmsKey,
+        arms: &[Vec<Value>],
+        eq: impl Fn(&Value, &Value) -> bool,
+        matches: impl Fn(&Value) -> bool,
+    ) -> Option<usize> {
+        let mut orders = self.orders();
+        let order = orders.entry(key).or_insert_with(|| ArmOrder {
+            order: (0..arms.len()).collect(),
+            disjoint: is_disjoint(arms, &eq),
+        });
+        if !order.disjoint || order.order.len() != arms.len() {
+            return None;
+        }
+        let Some(position) = (order.order.iter()).position(|arm| arms[*arm].iter().any(&matches))
+        else {
+            return Some(arms.len());
+        };
+        let arm = order.order.remove(position);
+        order.order.insert(0, arm);
+        Some(arm)
+    }
+
+    fn orders(&self) -> MutexGuard<'_, HashMap<ArmsKey, ArmOrder>> {
+        // a panic while holding the lock cannot leave a half-written order
+        self.orders
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The hash parameters of an arm that do not change what it matches
+const INERT_HASH: &[&str] = &["label", "context", "max_len", "truncate"];
+
+/// Lists the literals of every `{{#case}}` directly inside the block, or
+/// `None` unless the block consists of literal `{{#case}}` arms and
+/// `{{#default}}` arms alone
+pub(crate) fn literal_arms(template: &Template) -> Option<Vec<Vec<Value>>> {
+    let mut arms = Vec::new();
+    for element in &template.elements {
+        let ht = match element {
+            TemplateElement::RawString(_) | TemplateElement::Comment(_) => continue,
+            TemplateElement::HelperBlock(ht) => ht,
+            _ => return None,
+        };
+        let inert = ht.hash.keys().all(|key| INERT_HASH.contains(&key.as_str()));
+        match ht.name.as_name() {
+            Some("default") if inert => {}
+            Some("case") if inert && !ht.params.is_empty() => {
+                let literals = ht.params.iter().map(|param| match param {
+                    Parameter::Literal(value) => Some(value.clone()),
+                    _ => None,
+                });
+                arms.push(literals.collect::<Option<Vec<_>>>()?);
+            }
+            _ => return None,
+        }
+    }
+    Some(arms)
+}
+
+fn is_disjoint(arms: &[Vec<Value>], eq: impl Fn(&Value, &Value) -> bool) -> bool {
+    arms.iter().enumerate().all(|(i, a)| {
+        arms[i + 1..]
+            .iter()
+            .all(|b| a.iter().all(|x| b.iter().all(|y| !eq(x, y))))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptiveOrder;
+
+    #[test]
+    fn test_select_moves_to_front() {
+        let order = AdaptiveOrder::new();
+        let arms = [vec![json!("a")], vec![json!("b"), json!("c")]];
+        let select = |value: serde_json::Value| {
+            order.select((None, (1, 1)), &arms, |x, y| x == y, |x| *x == value)
+        };
+        assert_eq!(select(json!("c")), Some(1));
+        assert_eq!(select(json!("a")), Some(0));
+        assert_eq!(select(json!("z")), Some(2));
+        assert_eq!(order.len(), 1);
+
+        let overlapping = [vec![json!("a")], vec![json!("a")]];
+        let key = (None, (2, 1));
+        assert_eq!(
+            order.select(key, &overlapping, |x, y| x == y, |_| true),
+            None
+        );
+    }
+}
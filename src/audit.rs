@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde_json::Value;
+
+use crate::trace::ArmKind;
+
+/// Identifies a distinct observation by template, switch name, and value.
+type AuditKey = (Option<String>, Option<String>, String);
+
+/// Switch Observation
+///
+/// A value a `{{#switch}}` was rendered with, as reported to an
+/// [`AuditSink`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchObservation {
+    /// The name of the template being rendered, if any.
+    pub template: Option<String>,
+    /// The name given to the switch by `name="..."`, if any.
+    pub name: Option<String>,
+    /// The value switched on, after normalization.
+    pub value: Value,
+    /// The kind of arm that rendered, or `None` when no arm did, such as
+    /// when the render failed.
+    pub arm: Option<ArmKind>,
+}
+
+/// Audit Sink
+///
+/// Receives every value switched on by a
+/// [`SwitchHelper`](crate::SwitchHelper) set up with
+/// [`with_audit_sink`](crate::SwitchHelper::with_audit_sink), once per
+/// render of a `{{#switch}}`, so the values real data brings can be
+/// collected from production and the ones falling into `{{#default}}` arms
+/// given cases of their own. Switches served from a
+/// [`MemoCache`](crate::MemoCache) are reported as well, with the kind of
+/// arm that rendered the cached output.
+///
+/// Recording runs while rendering, so sinks that ship observations
+/// elsewhere should deduplicate and batch them rather than block. See
+/// [`AuditLog`] for one that keeps the distinct observations in memory.
+pub trait AuditSink: Send + Sync {
+    /// Records a single observation.
+    fn record(&self, observation: SwitchObservation);
+}
+
+/// Audit Log
+///
+/// An [`AuditSink`] keeping every distinct `(template, name, value)`
+/// observed, along with the kind of arm it rendered last. The log is cheap
+/// to clone and every clone shares the same observations, so keep a handle
+/// around and [`take`](AuditLog::take) them periodically.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{ArmKind, AuditLog, SwitchHelper};
+///
+/// let log = AuditLog::new();
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_audit_sink(log.clone())));
+///
+/// let tpl = "\
+///     {{#switch plan name=\"plan\"}}\
+///         {{#case \"pro\"}}Pro{{/case}}\
+///         {{#default}}Free{{/default}}\
+///     {{/switch}}\
+/// ";
+/// for plan in ["pro", "team", "pro", "team"] {
+///     handlebars.render_template(tpl, &json!({ "plan": plan })).unwrap();
+/// }
+///
+/// let defaulted: Vec<_> = log
+///     .take()
+///     .into_iter()
+///     .filter(|x| x.arm == Some(ArmKind::Default))
+///     .map(|x| x.value)
+///     .collect();
+/// assert_eq!(defaulted, [json!("team")]);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AuditLog {
+    seen: Arc<Mutex<BTreeMap<AuditKey, SwitchObservation>>>,
+}
+
+impl AuditLog {
+    /// Creates an empty log.
+    pub fn new() -> AuditLog {
+        AuditLog::default()
+    }
+
+    /// Returns the number of distinct observations.
+    pub fn len(&self) -> usize {
+        self.seen().len()
+    }
+
+    /// Returns `true` if nothing was observed.
+    pub fn is_empty(&self) -> bool {
+        self.seen().is_empty()
+    }
+
+    /// Returns the distinct observations so far, ordered by template, switch
+    /// name, and value, leaving the log empty.
+    pub fn take(&self) -> Vec<SwitchObservation> {
+        std::mem::take(&mut *self.seen()).into_values().collect()
+    }
+
+    fn seen(&self) -> MutexGuard<'_, BTreeMap<AuditKey, SwitchObservation>> {
+        self.seen
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl AuditSink for AuditLog {
+    fn record(&self, observation: SwitchObservation) {
+        let key = (
+            observation.template.clone(),
+            observation.name.clone(),
+            observation.value.to_string(),
+        );
+        self.seen().insert(key, observation);
+    }
+}
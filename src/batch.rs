@@ -0,0 +1,208 @@
+use handlebars::{Context, Handlebars, RenderError, RenderErrorReason};
+
+use serde_json::Value;
+
+/// Render Batch
+///
+/// Renders a registered template against each of `contexts` in turn, like
+/// calling `Handlebars::render` once per context, for reports over many rows.
+/// Every context is rendered from its value as is, without serializing it
+/// again. The template is compiled once when it is registered, and the state
+/// the switch helper keeps between renders, such as its compiled `regex=`
+/// patterns, a [`MemoCache`] or an [`AdaptiveOrder`], carries over from one
+/// row to the next, so a batch over similar rows mostly reuses earlier work.
+///
+/// Each row goes through the render path of the registry, so in dev mode
+/// templates registered from files are reloaded for every row, just as they
+/// would be by `Handlebars::render`, and a template edited during a batch
+/// renders the remaining rows from the new source.
+///
+/// Stops at the first context that fails to render and returns its error.
+///
+/// [`MemoCache`]: crate::MemoCache
+/// [`AdaptiveOrder`]: crate::AdaptiveOrder
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{render_batch, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars
+///     .register_template_string(
+///         "row",
+///         "{{#switch paid}}{{#case true}}Paid{{/case}}{{#default}}Due{{/default}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// let rows = [json!({"paid": true}), json!({"paid": false})];
+/// assert_eq!(render_batch(&handlebars, "row", &rows).unwrap(), ["Paid", "Due"]);
+/// # }
+/// ```
+pub fn render_batch(
+    handlebars: &Handlebars,
+    name: &str,
+    contexts: &[Value],
+) -> Result<Vec<String>, RenderError> {
+    ensure_template(handlebars, name)?;
+    contexts
+        .iter()
+        .map(|data| render_row(handlebars, name, data))
+        .collect()
+}
+
+/// Render Batch in Parallel
+///
+/// Renders like [`render_batch`] while spreading `contexts` across the
+/// threads of the global `rayon` pool, for batches large enough that
+/// rendering dominates. The outputs keep the order of `contexts`, and the error of the
+/// first failing context in that order is returned. The switch helper shares
+/// its state between threads, so a [`MemoCache`](crate::MemoCache) or
+/// [`RenderTracer`](crate::RenderTracer) collects from every row, although
+/// traces of rows rendered at the same time interleave.
+///
+/// Requires the `rayon` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{render_batch_parallel, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars
+///     .register_template_string(
+///         "row",
+///         "{{#switch paid}}{{#case true}}Paid{{/case}}{{#default}}Due{{/default}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// let rows: Vec<_> = (0..100).map(|i| json!({"paid": i % 2 == 0})).collect();
+/// let rendered = render_batch_parallel(&handlebars, "row", &rows).unwrap();
+/// assert_eq!(rendered[..3], ["Paid", "Due", "Paid"]);
+/// # }
+/// ```
+#[cfg(feature = "rayon")]
+pub fn render_batch_parallel(
+    handlebars: &Handlebars,
+    name: &str,
+    contexts: &[Value],
+) -> Result<Vec<String>, RenderError> {
+    use rayon::prelude::*;
+
+    ensure_template(handlebars, name)?;
+    let rendered: Vec<_> = contexts
+        .par_iter()
+        .map(|data| render_row(handlebars, name, data))
+        .collect();
+    rendered.into_iter().collect()
+}
+
+/// Fails unless a template is registered as `name`, so even an empty batch
+/// reports a missing template
+fn ensure_template(handlebars: &Handlebars, name: &str) -> Result<(), RenderError> {
+    if handlebars.has_template(name) {
+        Ok(())
+    } else {
+        Err(RenderErrorReason::TemplateNotFound(name.to_owned()).into())
+    }
+}
+
+/// Renders the template registered as `name` against a single context
+fn render_row(handlebars: &Handlebars, name: &str, data: &Value) -> Result<String, RenderError> {
+    handlebars.render_with_context(name, &Context::from(data.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_batch;
+    use crate::{MemoCache, SwitchHelper};
+    use handlebars::Handlebars;
+    use std::fs;
+
+    #[test]
+    fn test_render_batch() {
+        let memo = MemoCache::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_memo(memo.clone())),
+        );
+        let tpl = "{{#switch kind memo=true}}{{#case \"a\"}}A{{/case}}{{#default}}-{{/default}}{{/switch}}";
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let rows: Vec<_> = ["a", "b", "a", "a"]
+            .iter()
+            .map(|kind| json!({ "kind": kind }))
+            .collect();
+        assert_eq!(
+            render_batch(&handlebars, "tpl", &rows).unwrap(),
+            ["A", "-", "A", "A"]
+        );
+        assert_eq!(memo.len(), 2);
+        assert_eq!(
+            render_batch(&handlebars, "tpl", &[]).unwrap(),
+            Vec::<String>::new()
+        );
+        assert!(render_batch(&handlebars, "missing", &rows).is_err());
+        assert!(render_batch(&handlebars, "missing", &[]).is_err());
+    }
+
+    #[test]
+    fn test_render_batch_dev_mode() {
+        let dir =
+            std::env::temp_dir().join(format!("handlebars_switch_batch_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("row.hbs");
+        fs::write(&path, "{{#switch x}}{{#case 1}}one{{/case}}{{/switch}}").unwrap();
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.set_dev_mode(true);
+        handlebars.register_template_file("row", &path).unwrap();
+
+        let rows = [json!({"x": 1})];
+        assert_eq!(render_batch(&handlebars, "row", &rows).unwrap(), ["one"]);
+        fs::write(&path, "{{#switch x}}{{#case 1}}uno{{/case}}{{/switch}}").unwrap();
+        assert_eq!(render_batch(&handlebars, "row", &rows).unwrap(), ["uno"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_render_batch_parallel() {
+        use super::render_batch_parallel;
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let tpl = "{{#switch (lookup this \"n\")}}{{#case 0}}zero{{/case}}{{#default}}{{n}}{{/default}}{{/switch}}";
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let rows: Vec<_> = (0..1000).map(|n| json!({ "n": n % 7 })).collect();
+        assert_eq!(
+            render_batch_parallel(&handlebars, "tpl", &rows).unwrap(),
+            render_batch(&handlebars, "tpl", &rows).unwrap()
+        );
+        assert_eq!(
+            render_batch_parallel(&handlebars, "tpl", &[]).unwrap(),
+            Vec::<String>::new()
+        );
+
+        handlebars.set_strict_mode(true);
+        let mut rows = rows;
+        rows[600] = json!({});
+        assert!(render_batch_parallel(&handlebars, "tpl", &rows).is_err());
+    }
+}
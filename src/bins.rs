@@ -0,0 +1,268 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason,
+};
+
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::config::SwitchFamily;
+use crate::switch::SwitchHelper;
+use crate::units::as_canonical_number;
+
+/// Bins Helper
+///
+/// Provides the `{{#bins}}` helper to a Handlebars template. It places a
+/// number into one of the bins separated by the ascending `edges` and then
+/// behaves like `{{#switch}}` on the index of that bin. Bin `0` holds values
+/// below the first edge and every edge belongs to the bin above it, so
+/// `edges` of `[50, 70, 90]` give the bins `< 50`, `50..70`, `70..90`, and
+/// `>= 90`.
+///
+/// Passing `labels` with one entry per bin switches on the label instead of
+/// the index. Values that are not numbers fall in no bin and render the
+/// `{{#default}}`. With `units=true`, values and edges may also be strings
+/// with a unit like `"5MB"` or `"1500ms"`, which are read in bytes or
+/// milliseconds. With the `currency` feature, `currency=true` reads
+/// currency-formatted strings like `"1.234,56 €"` by their amount instead.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::BinsHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("bins", Box::new(BinsHelper));
+///
+/// let tpl = "\
+///     {{#bins score edges=grading.edges labels=grading.labels}}\
+///         {{#case \"A\"}}Excellent{{/case}}\
+///         {{#case \"B\" \"C\"}}Passed{{/case}}\
+///         {{#default}}Failed{{/default}}\
+///     {{/bins}}\
+/// ";
+/// let grading = json!({"edges": [50, 70, 90], "labels": ["F", "C", "B", "A"]});
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"score": 90, "grading": grading})).unwrap(),
+///     "Excellent"
+/// );
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"score": 69.5, "grading": grading})).unwrap(),
+///     "Passed"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct BinsHelper;
+
+impl SwitchFamily for BinsHelper {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        // Read in the number to place in a bin
+        let param = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("bins", 0))?;
+
+        let edges = h
+            .hash_get("edges")
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForName("bins", "edges".to_owned()))?;
+        let is_enabled = |name| {
+            h.hash_get(name)
+                .and_then(|x| x.value().as_bool())
+                .unwrap_or_default()
+        };
+        let units = is_enabled("units");
+        #[cfg(feature = "currency")]
+        let currency = is_enabled("currency");
+        let as_number = |x: &Value| {
+            #[cfg(feature = "currency")]
+            if currency {
+                return crate::currency::as_amount(x);
+            }
+            if units {
+                as_canonical_number(x)
+            } else {
+                x.as_f64()
+            }
+        };
+        let edges = parse_edges(edges.value(), as_number)?;
+
+        let labels = match h.hash_get("labels").map(|x| x.value()) {
+            Some(Value::Array(labels)) if labels.len() == edges.len() + 1 => Some(labels),
+            Some(_) => {
+                return Err(RenderErrorReason::HashTypeMismatchForName(
+                    "bins",
+                    "labels".to_owned(),
+                    "array with one more entry than edges".to_owned(),
+                )
+                .into())
+            }
+            None => None,
+        };
+
+        let bin =
+            as_number(param.value()).map(|x| edges.iter().take_while(|edge| **edge <= x).count());
+        let value = match (bin, labels) {
+            (Some(bin), Some(labels)) => labels.get(bin).cloned().unwrap_or_default(),
+            (Some(bin), None) => json!(bin),
+            (None, _) => Value::Null,
+        };
+
+        switch.render_switch(Cow::Owned(value), h, r, ctx, rc, out)
+    }
+}
+
+impl HelperDef for BinsHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_with(&SwitchHelper::new(), h, r, ctx, rc, out)
+    }
+}
+
+fn parse_edges(
+    edges: &Value,
+    as_number: impl Fn(&Value) -> Option<f64>,
+) -> Result<Vec<f64>, RenderError> {
+    let edges = edges
+        .as_array()
+        .and_then(|edges| edges.iter().map(as_number).collect::<Option<Vec<_>>>())
+        .filter(|edges| edges.windows(2).all(|pair| pair[0] < pair[1]))
+        .ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "bins",
+                "edges".to_owned(),
+                "ascending array of numbers".to_owned(),
+            )
+        })?;
+    Ok(edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BinsHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_bins() {
+        let tpl = "\
+            {{#bins status edges=classes}}\
+                {{#case 0}}info{{/case}}\
+                {{#case 1}}success{{/case}}\
+                {{#case 2}}redirect{{/case}}\
+                {{#case 3 4}}error{{/case}}\
+                {{#default}}unknown{{/default}}\
+            {{/bins}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("bins", Box::new(BinsHelper));
+        let data = |status| json!({"status": status, "classes": [200, 300, 400, 500]});
+
+        let cases = [
+            (100, "info"),
+            (199, "info"),
+            (200, "success"),
+            (301, "redirect"),
+        ];
+        for (status, expected) in cases {
+            let rendered = handlebars.render_template(tpl, &data(json!(status)));
+            assert_eq!(rendered.unwrap(), expected);
+        }
+        let r0 = handlebars.render_template(tpl, &data(json!(503)));
+        assert_eq!(r0.unwrap(), "error");
+        let r1 = handlebars.render_template(tpl, &data(json!("503")));
+        assert_eq!(r1.unwrap(), "unknown");
+    }
+
+    #[test]
+    fn test_bins_with_units() {
+        let tpl = "\
+            {{#bins latency edges=edges labels=labels units=true}}\
+                {{#case \"fast\"}}fast{{/case}}\
+                {{#case \"slow\"}}slow{{/case}}\
+                {{#default}}unknown{{/default}}\
+            {{/bins}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("bins", Box::new(BinsHelper));
+        let data =
+            |latency| json!({"latency": latency, "edges": ["1s"], "labels": ["fast", "slow"]});
+
+        let cases = [
+            (json!("250ms"), "fast"),
+            (json!("1.5s"), "slow"),
+            (json!(999), "fast"),
+            (json!("1s"), "slow"),
+            (json!("1"), "unknown"),
+        ];
+        for (latency, expected) in cases {
+            let rendered = handlebars.render_template(tpl, &data(latency));
+            assert_eq!(rendered.unwrap(), expected);
+        }
+        let r0 = handlebars.render_template(&tpl.replace(" units=true", ""), &data(json!(1)));
+        assert!(r0.is_err());
+    }
+
+    #[cfg(feature = "currency")]
+    #[test]
+    fn test_bins_with_currency() {
+        let tpl = "\
+            {{#bins total edges=edges currency=true}}\
+                {{#case 0}}small{{/case}}\
+                {{#case 1}}large{{/case}}\
+            {{/bins}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("bins", Box::new(BinsHelper));
+        let data = |total| json!({"total": total, "edges": ["1.000,00 €"]});
+
+        let r0 = handlebars.render_template(tpl, &data("999,99 €"));
+        assert_eq!(r0.unwrap(), "small");
+        let r1 = handlebars.render_template(tpl, &data("$1,500"));
+        assert_eq!(r1.unwrap(), "large");
+    }
+
+    #[test]
+    fn test_invalid_edges() {
+        let tpl = "{{#bins 1 edges=edges}}{{/bins}}";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("bins", Box::new(BinsHelper));
+
+        assert!(handlebars.render_template(tpl, &json!({})).is_err());
+        assert!(handlebars
+            .render_template(tpl, &json!({"edges": [2, 1]}))
+            .is_err());
+        assert!(handlebars
+            .render_template(tpl, &json!({"edges": ["a"]}))
+            .is_err());
+        assert!(handlebars
+            .render_template(
+                "{{#bins 1 edges=edges labels=edges}}{{/bins}}",
+                &json!({"edges": [1]})
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use handlebars::{
+    BlockContext, BlockParams, Context, Handlebars, Helper, HelperDef, HelperResult, Output,
+    RenderContext, Renderable,
+};
+
+use serde_json::Value;
+
+use crate::switch::SWITCH_LOCALS;
+use crate::vars::{restore_vars, saved_vars};
+
+/// Creates a block that resolves paths and user `@` variables like the
+/// enclosing block, so helpers can push their own state without changing the
+/// context seen by their bodies. The state of an enclosing switch stays
+/// behind, so a switch nested in the body starts out without it.
+pub(crate) fn child_block<'rc>(rc: &RenderContext<'_, 'rc>) -> BlockContext<'rc> {
+    let mut block = arm_block(rc);
+    for name in SWITCH_LOCALS {
+        if block.get_local_var(name).is_some() {
+            block.set_local_var(name, Value::Null);
+        }
+    }
+    block
+}
+
+/// Creates a block like [`child_block`] that keeps the state of the
+/// enclosing switch as well, for the body of one of its own arms.
+pub(crate) fn arm_block<'rc>(rc: &RenderContext<'_, 'rc>) -> BlockContext<'rc> {
+    let mut block = BlockContext::new();
+    if let Some(parent) = rc.block() {
+        block.base_path_mut().clone_from(parent.base_path());
+        if let Some(value) = parent.base_value() {
+            block.set_base_value(value.clone());
+        }
+        block
+            .local_variables_mut()
+            .clone_from(parent.local_variables());
+    }
+    block
+}
+
+/// An arm that renders only when it was given a payload, binding the payload
+/// to `as |v|` if requested.
+#[derive(Clone)]
+pub(crate) struct PayloadHelper {
+    pub(crate) payload: Option<Value>,
+}
+
+impl HelperDef for PayloadHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let (Some(payload), Some(t)) = (&self.payload, h.template()) else {
+            return Ok(());
+        };
+
+        let mut block = child_block(rc);
+        if let Some(name) = h.block_param() {
+            let mut params = BlockParams::new();
+            params.add_value(name, payload.clone())?;
+            block.set_block_params(params);
+        }
+
+        rc.push_block(block);
+        let result = t.render(r, ctx, rc, out);
+        let vars = saved_vars(rc);
+        rc.pop_block();
+        restore_vars(rc, vars);
+
+        result
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+use handlebars::{Helper, RenderError, RenderErrorReason};
+
+/// The time a `{{#switch}}` may take to evaluate and render its arms, as set
+/// by its `budget_ms=` hash parameter. Rendering cannot be interrupted, so
+/// the budget is checked before and after every arm.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Budget {
+    limit: Duration,
+    start: Instant,
+}
+
+impl Budget {
+    /// Starts the budget of a `{{#switch}}`, if it asks for one.
+    pub(crate) fn from_hash(h: &Helper) -> Result<Option<Budget>, RenderError> {
+        let Some(budget) = h.hash_get("budget_ms") else {
+            return Ok(None);
+        };
+        let ms = budget.value().as_u64().ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "switch",
+                "budget_ms".to_owned(),
+                "non-negative integer".to_owned(),
+            )
+        })?;
+        Ok(Some(Budget {
+            limit: Duration::from_millis(ms),
+            start: Instant::now(),
+        }))
+    }
+
+    /// Fails once more time has passed than the budget allows.
+    pub(crate) fn check(&self) -> Result<(), RenderError> {
+        let elapsed = self.start.elapsed();
+        if elapsed <= self.limit {
+            return Ok(());
+        }
+        Err(RenderErrorReason::Other(format!(
+            "`{{{{#switch}}}}` took {}ms, exceeding its budget of {}ms",
+            elapsed.as_millis(),
+            self.limit.as_millis()
+        ))
+        .into())
+    }
+}
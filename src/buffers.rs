@@ -0,0 +1,57 @@
+use std::sync::{Mutex, MutexGuard};
+
+/// A pool of string buffers scoped to the render of a single switch, so the
+/// strings built while comparing its value against every arm reuse the same
+/// few allocations rather than each taking and freeing its own. This is not
+/// an pool: every buffer is a separate heap allocation, only kept alive for
+/// reuse. Cloning gives an empty pool, as every render starts from a clone of
+/// the comparison holding it.
+#[derive(Debug, Default)]
+pub(crate) struct BufferPool {
+    strings: Mutex<Vec<String>>,
+}
+
+impl Clone for BufferPool {
+    fn clone(&self) -> BufferPool {
+        BufferPool::default()
+    }
+}
+
+impl BufferPool {
+    /// Lends an empty string to `f`, keeping its capacity for the next use
+    pub(crate) fn with_string<R>(&self, f: impl FnOnce(&mut String) -> R) -> R {
+        let mut s = self.strings().pop().unwrap_or_default();
+        s.clear();
+        let result = f(&mut s);
+        self.strings().push(s);
+        result
+    }
+
+    fn strings(&self) -> MutexGuard<'_, Vec<String>> {
+        // a panic while lending a string leaves the pool intact
+        self.strings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufferPool;
+
+    #[test]
+    fn test_with_string_reuses_buffers() {
+        let pool = BufferPool::default();
+        let capacity = pool.with_string(|a| {
+            a.push_str("a string long enough to allocate");
+            pool.with_string(|b| assert!(b.is_empty()));
+            a.capacity()
+        });
+        assert_eq!(pool.strings().len(), 2);
+        pool.with_string(|s| {
+            assert!(s.is_empty());
+            assert_eq!(s.capacity(), capacity);
+        });
+        assert!(pool.clone().strings().is_empty());
+    }
+}
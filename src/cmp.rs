@@ -0,0 +1,216 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use crate::config::SwitchFamily;
+use crate::switch::SwitchHelper;
+
+/// Compare Helper
+///
+/// Provides the `{{#cmp}}` helper to a Handlebars template. It compares two
+/// values and then behaves like `{{#switch}}` on `"lt"`, `"eq"`, or `"gt"`,
+/// as the first is less than, equal to, or greater than the second. Numbers
+/// compare by value and strings by their Unicode code points, while any
+/// other pair, such as a number and a string, switches on `null` and renders
+/// the `{{#default}}`.
+///
+/// Passing `natural=true` compares strings in natural order instead, where
+/// runs of digits compare by their numeric value, so `"v10"` is greater than
+/// `"v9"` and `"build-2.tar"` less than `"build-12.tar"`. This suits
+/// versioned names like release tags and artifacts.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::CmpHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("cmp", Box::new(CmpHelper));
+///
+/// let tpl = "\
+///     {{#cmp price previous}}\
+///         {{#case \"lt\"}}down{{/case}}\
+///         {{#case \"gt\"}}up{{/case}}\
+///         {{#default}}unchanged{{/default}}\
+///     {{/cmp}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"price": 9.5, "previous": 10})).unwrap(),
+///     "down"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct CmpHelper;
+
+impl SwitchFamily for CmpHelper {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let a = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("cmp", 0))?;
+        let b = h
+            .param(1)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("cmp", 1))?;
+
+        let natural = match h.hash_get("natural").map(|x| x.value()) {
+            Some(Value::Bool(natural)) => *natural,
+            Some(_) => {
+                return Err(RenderErrorReason::HashTypeMismatchForName(
+                    "cmp",
+                    "natural".to_owned(),
+                    "boolean".to_owned(),
+                )
+                .into())
+            }
+            None => false,
+        };
+
+        let value = match compare(a.value(), b.value(), natural) {
+            Some(Ordering::Less) => json!("lt"),
+            Some(Ordering::Equal) => json!("eq"),
+            Some(Ordering::Greater) => json!("gt"),
+            None => Value::Null,
+        };
+
+        switch.render_switch(Cow::Owned(value), h, r, ctx, rc, out)
+    }
+}
+
+impl HelperDef for CmpHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_with(&SwitchHelper::new(), h, r, ctx, rc, out)
+    }
+}
+
+/// Orders two numbers or two strings, exactly for integers
+fn compare(a: &Value, b: &Value, natural: bool) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => Some(x.cmp(&y)),
+            _ => match (x.as_u64(), y.as_u64()) {
+                (Some(x), Some(y)) => Some(x.cmp(&y)),
+                _ => x.as_f64()?.partial_cmp(&y.as_f64()?),
+            },
+        },
+        (Value::String(x), Value::String(y)) if natural => Some(natural_cmp(x, y)),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+/// Orders strings with runs of digits compared by value, falling back to
+/// plain order for strings that differ only in leading zeros
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a, b);
+    loop {
+        let (Some(c), Some(d)) = (x.chars().next(), y.chars().next()) else {
+            return x.len().cmp(&y.len()).then_with(|| a.cmp(b));
+        };
+        let ordering = if c.is_ascii_digit() && d.is_ascii_digit() {
+            let (m, rest_x) = split_digits(x);
+            let (n, rest_y) = split_digits(y);
+            (x, y) = (rest_x, rest_y);
+            // compare without parsing, so long runs cannot overflow
+            let (m, n) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+            m.len().cmp(&n.len()).then_with(|| m.cmp(n))
+        } else {
+            (x, y) = (&x[c.len_utf8()..], &y[d.len_utf8()..]);
+            c.cmp(&d)
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{natural_cmp, CmpHelper};
+    use handlebars::Handlebars;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_cmp() {
+        let tpl = "\
+            {{#cmp a b}}\
+                {{#case \"lt\"}}<{{/case}}\
+                {{#case \"eq\"}}={{/case}}\
+                {{#case \"gt\"}}>{{/case}}\
+                {{#default}}?{{/default}}\
+            {{/cmp}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cmp", Box::new(CmpHelper));
+
+        let render = |a, b| {
+            handlebars
+                .render_template(tpl, &json!({"a": a, "b": b}))
+                .unwrap()
+        };
+        assert_eq!(render(json!(1), json!(2)), "<");
+        assert_eq!(render(json!(2.0), json!(2)), "=");
+        assert_eq!(render(json!(u64::MAX), json!(u64::MAX - 1)), ">");
+        assert_eq!(render(json!(-1), json!(u64::MAX)), "<");
+        assert_eq!(render(json!("b"), json!("ab")), ">");
+        assert_eq!(render(json!("10"), json!(9)), "?");
+        assert_eq!(render(json!(null), json!(null)), "?");
+
+        assert!(handlebars
+            .render_template("{{#cmp 1}}{{/cmp}}", &json!({}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("v10", "v9"), Ordering::Greater);
+        assert_eq!(natural_cmp("build-2.tar", "build-12.tar"), Ordering::Less);
+        assert_eq!(natural_cmp("1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(natural_cmp("v1", "v1"), Ordering::Equal);
+        assert_eq!(natural_cmp("v01", "v1"), Ordering::Less);
+        assert_eq!(natural_cmp("v1", "v1a"), Ordering::Less);
+        assert_eq!(
+            natural_cmp("a99999999999999999999999", "a1"),
+            Ordering::Greater
+        );
+        assert_eq!(natural_cmp("é2", "é10"), Ordering::Less);
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cmp", Box::new(CmpHelper));
+        let tpl =
+            "{{#cmp a b natural=n}}{{#case \"gt\"}}>{{/case}}{{#default}}<{{/default}}{{/cmp}}";
+        let render = |n| handlebars.render_template(tpl, &json!({"a": "v10", "b": "v9", "n": n}));
+        assert_eq!(render(json!(true)).unwrap(), ">");
+        assert_eq!(render(json!(false)).unwrap(), "<");
+        assert!(render(json!("yes")).is_err());
+    }
+}
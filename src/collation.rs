@@ -0,0 +1,112 @@
+#[cfg(feature = "collation")]
+use std::cmp::Ordering;
+#[cfg(feature = "collation")]
+use std::fmt;
+#[cfg(feature = "collation")]
+use std::sync::Arc;
+
+#[cfg(feature = "collation")]
+use icu_collator::options::{CollatorOptions, Strength};
+#[cfg(feature = "collation")]
+use icu_collator::{Collator, CollatorBorrowed};
+#[cfg(feature = "collation")]
+use icu_locale_core::Locale;
+
+/// Folds the case of a string onto `folded` by the rules shared by most
+/// locales, expanding letters that fold to several like `ß`, so strings
+/// differing only in case fold to the same.
+pub(crate) fn case_fold(s: &str, folded: &mut String) {
+    for c in s.chars() {
+        fold_char(c, folded);
+    }
+}
+
+fn fold_char(c: char, folded: &mut String) {
+    match c {
+        'ß' | 'ẞ' => folded.push_str("ss"),
+        'ſ' => folded.push('s'),
+        'ς' => folded.push('σ'),
+        'ﬀ' => folded.push_str("ff"),
+        'ﬁ' => folded.push_str("fi"),
+        'ﬂ' => folded.push_str("fl"),
+        // `İ` lowercases to `i` and a combining dot outside Turkic
+        c => folded.extend(c.to_lowercase()),
+    }
+}
+
+/// Collation
+///
+/// An ICU4X collator for a locale at secondary strength, for comparing
+/// strings the way the locale sorts them: case does not matter, while
+/// accents still do.
+#[cfg(feature = "collation")]
+#[derive(Clone)]
+pub(crate) struct Collation {
+    locale: String,
+    collator: Arc<CollatorBorrowed<'static>>,
+}
+
+#[cfg(feature = "collation")]
+impl Collation {
+    /// Builds the collator of a locale like `"de-CH"` or `"tr_TR"`, falling
+    /// back to the root collation for locales without data of their own, or
+    /// `None` when `locale` is not a language tag
+    pub(crate) fn for_locale(locale: &str) -> Option<Collation> {
+        let tag = Locale::try_from_str(&locale.replace('_', "-")).ok()?;
+        let mut options = CollatorOptions::default();
+        options.strength = Some(Strength::Secondary);
+        let collator = Collator::try_new((&tag).into(), options).ok()?;
+        Some(Collation {
+            locale: tag.to_string(),
+            collator: Arc::new(collator),
+        })
+    }
+
+    /// Checks whether the collator finds two strings equal
+    pub(crate) fn eq(&self, a: &str, b: &str) -> bool {
+        self.collator.compare(a, b) == Ordering::Equal
+    }
+}
+
+#[cfg(feature = "collation")]
+impl fmt::Debug for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Collation").field(&self.locale).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::case_fold;
+    #[cfg(feature = "collation")]
+    use super::Collation;
+
+    #[test]
+    fn test_case_fold() {
+        let fold = |s| {
+            let mut folded = String::new();
+            case_fold(s, &mut folded);
+            folded
+        };
+        assert_eq!(fold("Straße"), "strasse");
+        assert_eq!(fold("ΟΔΟΣ"), fold("οδος"));
+        assert_eq!(fold("ǅemal"), fold("ǆEMAL"));
+        assert_ne!(fold("Müller"), fold("Muller"));
+    }
+
+    #[cfg(feature = "collation")]
+    #[test]
+    fn test_collation() {
+        let root = Collation::for_locale("de-DE").unwrap();
+        assert!(root.eq("Müller", "MÜLLER"));
+        assert!(root.eq("ΟΔΟΣ", "οδος"));
+        assert!(!root.eq("Müller", "Muller"));
+        assert!(!root.eq("IŞIK", "ışık"));
+
+        let turkic = Collation::for_locale("tr_TR").unwrap();
+        assert!(turkic.eq("IŞIK", "ışık"));
+        assert!(turkic.eq("İstanbul", "istanbul"));
+        assert!(!turkic.eq("Istanbul", "istanbul"));
+        assert!(Collation::for_locale("not a locale").is_none());
+    }
+}
@@ -0,0 +1,87 @@
+/// Parses a CSS color written as `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`,
+/// `rgb(r, g, b)`, or `rgba(r, g, b, a)` into its red, green, blue, and
+/// alpha channels. The functional forms accept space separated channels,
+/// percentages, and a `/` before the alpha.
+fn parse_color(s: &str) -> Option<[u8; 4]> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok();
+        let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return match hex.len() {
+            3 | 4 => {
+                let mut rgba = [255; 4];
+                for (i, channel) in rgba.iter_mut().enumerate().take(hex.len()) {
+                    *channel = digit(i)? * 17;
+                }
+                Some(rgba)
+            }
+            6 | 8 => {
+                let mut rgba = [255; 4];
+                for (i, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+                    *channel = pair(2 * i)?;
+                }
+                Some(rgba)
+            }
+            _ => None,
+        };
+    }
+
+    let lower = s.to_ascii_lowercase();
+    let args = lower
+        .strip_prefix("rgba(")
+        .or_else(|| lower.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let args: Vec<&str> = args
+        .split([',', ' ', '/'])
+        .filter(|x| !x.is_empty())
+        .collect();
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    let mut rgba = [255; 4];
+    for (i, arg) in args.iter().enumerate() {
+        let (n, divisor) = match arg.strip_suffix('%') {
+            Some(percent) => (percent.parse::<f64>().ok()? * 255.0, 100.0),
+            None if i == 3 => (arg.parse::<f64>().ok()? * 255.0, 1.0),
+            None => (arg.parse::<f64>().ok()?, 1.0),
+        };
+        rgba[i] = (n / divisor).round().clamp(0.0, 255.0) as u8;
+    }
+    Some(rgba)
+}
+
+/// Compares two strings as CSS colors when both are colors, returning
+/// `None` otherwise.
+pub(crate) fn same_color(a: &str, b: &str) -> Option<bool> {
+    Some(parse_color(a)? == parse_color(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_color, same_color};
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#fff"), Some([255, 255, 255, 255]));
+        assert_eq!(parse_color("#FF000080"), Some([255, 0, 0, 128]));
+        assert_eq!(parse_color("#0f08"), Some([0, 255, 0, 136]));
+        assert_eq!(parse_color("rgb(255,255,255)"), Some([255, 255, 255, 255]));
+        assert_eq!(parse_color("RGBA(0, 0, 255, 0.5)"), Some([0, 0, 255, 128]));
+        assert_eq!(parse_color("rgb(100% 0% 0% / 50%)"), Some([255, 0, 0, 128]));
+        assert_eq!(parse_color("#ffff0"), None);
+        assert_eq!(parse_color("#ééé"), None);
+        assert_eq!(parse_color("rgb(1, 2)"), None);
+        assert_eq!(parse_color("white"), None);
+    }
+
+    #[test]
+    fn test_same_color() {
+        assert_eq!(same_color("#fff", "#FFFFFF"), Some(true));
+        assert_eq!(same_color("#ffffff", "rgb(255, 255, 255)"), Some(true));
+        assert_eq!(same_color("#fff", "#fffe"), Some(false));
+        assert_eq!(same_color("#fff", "fff"), None);
+    }
+}
@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use handlebars::{Helper, JsonRender, RenderError, RenderErrorReason};
+use serde_json::Value;
+
+use crate::buffers::BufferPool;
+use crate::fields::FieldComparator;
+use crate::units::same_quantity;
+
+/// Array Equality
+///
+/// Decides when an array switch value is equal to an array `{{#case}}` value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayEquality {
+    /// Arrays are equal when they have equal elements in the same order.
+    #[default]
+    Positional,
+    /// Arrays are equal when they contain the same elements, ignoring order
+    /// and duplicates.
+    Set,
+}
+
+impl ArrayEquality {
+    fn from_name(name: &str) -> Option<ArrayEquality> {
+        match name {
+            "positional" => Some(ArrayEquality::Positional),
+            "set" => Some(ArrayEquality::Set),
+            _ => None,
+        }
+    }
+}
+
+/// Compare Limits
+///
+/// Bounds how deep and how large a switch value may be before it is compared
+/// against any `{{#case}}`, so switching on a huge object by mistake does not
+/// walk the whole object once per arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompareLimits {
+    /// The deepest nesting of arrays and objects allowed, where a scalar has
+    /// a depth of zero.
+    pub max_depth: usize,
+    /// The most values allowed, counting every array, object, and scalar.
+    pub max_nodes: usize,
+    /// What to do with a switch value beyond either limit.
+    pub on_exceeded: LimitExceeded,
+}
+
+impl Default for CompareLimits {
+    fn default() -> CompareLimits {
+        CompareLimits {
+            max_depth: 16,
+            max_nodes: 1024,
+            on_exceeded: LimitExceeded::Default,
+        }
+    }
+}
+
+impl CompareLimits {
+    fn admits(&self, value: &Value, depth: usize, nodes: &mut usize) -> bool {
+        if depth > self.max_depth || *nodes >= self.max_nodes {
+            return false;
+        }
+        *nodes += 1;
+        match value {
+            Value::Array(items) => items.iter().all(|x| self.admits(x, depth + 1, nodes)),
+            Value::Object(map) => map.values().all(|x| self.admits(x, depth + 1, nodes)),
+            _ => true,
+        }
+    }
+}
+
+/// Limit Exceeded
+///
+/// What a `{{#switch}}` does when its value is beyond its [`CompareLimits`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LimitExceeded {
+    /// Match no `{{#case}}`, so the `{{#default}}` renders.
+    #[default]
+    Default,
+    /// Fail the render.
+    Error,
+}
+
+/// The deepest value ever compared, matching the nesting serde_json accepts
+/// when parsing, so comparisons cannot overflow the stack.
+const MAX_NESTING: usize = 128;
+
+/// The rules used to compare a switch value against `{{#case}}` values.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Comparison {
+    pub(crate) arrays: ArrayEquality,
+    pub(crate) limits: Option<CompareLimits>,
+    pub(crate) ignore_case_style: bool,
+    pub(crate) insensitive: bool,
+    #[cfg(feature = "unicode")]
+    pub(crate) ignore_diacritics: bool,
+    #[cfg(feature = "collation")]
+    pub(crate) collation: Option<crate::collation::Collation>,
+    pub(crate) units: bool,
+    pub(crate) uuids: bool,
+    pub(crate) colors: bool,
+    pub(crate) yaml_bools: bool,
+    pub(crate) stringify: bool,
+    #[cfg(feature = "currency")]
+    pub(crate) currency: bool,
+    #[cfg(feature = "instants")]
+    pub(crate) instants: bool,
+    #[cfg(feature = "phones")]
+    pub(crate) phones: bool,
+    /// The comparators of fields, by the JSON Pointer of the field
+    pub(crate) fields: Arc<HashMap<String, FieldComparator>>,
+    /// The buffers strings are folded into, fresh for every render
+    buffers: BufferPool,
+}
+
+impl Comparison {
+    /// Applies the per-switch overrides found in the `{{#switch}}` hash.
+    pub(crate) fn with_hash(mut self, h: &Helper) -> Result<Comparison, RenderError> {
+        if let Some(arrays) = h.hash_get("arrays") {
+            self.arrays = arrays
+                .value()
+                .as_str()
+                .and_then(ArrayEquality::from_name)
+                .ok_or_else(|| {
+                    RenderErrorReason::HashTypeMismatchForName(
+                        "switch",
+                        "arrays".to_owned(),
+                        "\"positional\" or \"set\"".to_owned(),
+                    )
+                })?;
+        }
+        if let Some(ignore) = bool_hash(h, "ignore_case_style")? {
+            self.ignore_case_style = ignore;
+        }
+        if let Some(insensitive) = bool_hash(h, "insensitive")? {
+            self.insensitive = insensitive;
+        }
+        #[cfg(feature = "unicode")]
+        if let Some(ignore) = bool_hash(h, "ignore_diacritics")? {
+            self.ignore_diacritics = ignore;
+        }
+        #[cfg(feature = "collation")]
+        if let Some(locale) = h.hash_get("collation") {
+            let collation = (locale.value().as_str())
+                .and_then(crate::collation::Collation::for_locale)
+                .ok_or_else(|| {
+                    RenderErrorReason::HashTypeMismatchForName(
+                        "switch",
+                        "collation".to_owned(),
+                        "locale string".to_owned(),
+                    )
+                })?;
+            self.collation = Some(collation);
+        }
+        if let Some(units) = bool_hash(h, "units")? {
+            self.units = units;
+        }
+        if let Some(uuids) = bool_hash(h, "uuids")? {
+            self.uuids = uuids;
+        }
+        if let Some(colors) = bool_hash(h, "colors")? {
+            self.colors = colors;
+        }
+        if let Some(yaml_bools) = bool_hash(h, "yaml_bools")? {
+            self.yaml_bools = yaml_bools;
+        }
+        if let Some(stringify) = bool_hash(h, "stringify")? {
+            self.stringify = stringify;
+        }
+        #[cfg(feature = "currency")]
+        if let Some(currency) = bool_hash(h, "currency")? {
+            self.currency = currency;
+        }
+        #[cfg(feature = "instants")]
+        if let Some(instants) = bool_hash(h, "instants")? {
+            self.instants = instants;
+        }
+        #[cfg(feature = "phones")]
+        if let Some(phones) = bool_hash(h, "phones")? {
+            self.phones = phones;
+        }
+        Ok(self)
+    }
+
+    /// Checks the switch value against the limits, returning `false` when it
+    /// should match no `{{#case}}`.
+    pub(crate) fn admits(&self, value: &Value) -> Result<bool, RenderError> {
+        let nesting = CompareLimits {
+            max_depth: MAX_NESTING,
+            max_nodes: usize::MAX,
+            on_exceeded: LimitExceeded::Error,
+        };
+        if !nesting.admits(value, 0, &mut 0) {
+            return Err(RenderErrorReason::Other(format!(
+                "`{{{{#switch}}}}` value is nested deeper than {MAX_NESTING} levels"
+            ))
+            .into());
+        }
+
+        let Some(limits) = self.limits else {
+            return Ok(true);
+        };
+        if limits.admits(value, 0, &mut 0) {
+            return Ok(true);
+        }
+        match limits.on_exceeded {
+            LimitExceeded::Default => Ok(false),
+            LimitExceeded::Error => Err(RenderErrorReason::Other(format!(
+                "`{{{{#switch}}}}` value exceeds the comparison limits of depth {} and {} values",
+                limits.max_depth, limits.max_nodes
+            ))
+            .into()),
+        }
+    }
+
+    pub(crate) fn eq(&self, a: &Value, b: &Value) -> bool {
+        self.eq_at(a, b, &mut String::new())
+    }
+
+    /// Compares the values at the JSON Pointer `path` of the `{{#case}}`
+    /// value and the switch value
+    fn eq_at(&self, a: &Value, b: &Value, path: &mut String) -> bool {
+        if let Some(field) = self.fields.get(path.as_str()) {
+            return field.eq(a, b);
+        }
+        if self.units {
+            if let Some(same) = same_quantity(a, b) {
+                return same;
+            }
+        }
+        #[cfg(feature = "currency")]
+        if self.currency {
+            if let Some(same) = crate::currency::same_money(a, b) {
+                return same;
+            }
+        }
+        #[cfg(feature = "instants")]
+        if let (true, Value::String(a), Value::String(b)) = (self.instants, a, b) {
+            if let Some(same) = crate::instant::same_instant(a, b) {
+                return same;
+            }
+        }
+        #[cfg(feature = "phones")]
+        if let (true, Value::String(a), Value::String(b)) = (self.phones, a, b) {
+            if let Some(same) = crate::phone::same_phone(a, b) {
+                return same;
+            }
+        }
+        if let (true, Value::String(a), Value::String(b)) = (self.uuids, a, b) {
+            if let (Some(a), Some(b)) = (uuid_digits(a), uuid_digits(b)) {
+                return a == b;
+            }
+        }
+        if let (true, Value::String(a), Value::String(b)) = (self.colors, a, b) {
+            if let Some(same) = crate::color::same_color(a, b) {
+                return same;
+            }
+        }
+        if let (true, Value::Bool(x), Value::String(s)) | (true, Value::String(s), Value::Bool(x)) =
+            (self.yaml_bools, a, b)
+        {
+            return yaml_bool(s) == Some(*x);
+        }
+        if self.stringify && !is_container(a) && !is_container(b) {
+            return self.str_eq(&a.render(), &b.render());
+        }
+        match (a, b) {
+            // the elements of arrays are found at the index of the element
+            // of the `{{#case}}` value
+            (Value::Array(a), Value::Array(b)) => match self.arrays {
+                ArrayEquality::Positional => {
+                    a.len() == b.len()
+                        && (a.iter().zip(b).enumerate())
+                            .all(|(i, (x, y))| self.eq_in(x, y, path, &i.to_string()))
+                }
+                ArrayEquality::Set => {
+                    (a.iter().enumerate())
+                        .all(|(i, x)| b.iter().any(|y| self.eq_in(x, y, path, &i.to_string())))
+                        && b.iter().all(|y| {
+                            (a.iter().enumerate())
+                                .any(|(i, x)| self.eq_in(x, y, path, &i.to_string()))
+                        })
+                }
+            },
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, x)| b.get(k).is_some_and(|y| self.eq_in(x, y, path, k)))
+            }
+            (Value::String(a), Value::String(b)) => self.str_eq(a, b),
+            (Value::Number(n), Value::String(s)) | (Value::String(s), Value::Number(n)) => {
+                let n = n.as_i64().map(i128::from).or(n.as_u64().map(i128::from));
+                n.is_some_and(|n| parse_radix_literal(s) == Some(n))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Compares the values at the `segment` below the JSON Pointer `path`
+    fn eq_in(&self, a: &Value, b: &Value, path: &mut String, segment: &str) -> bool {
+        if self.fields.is_empty() {
+            return self.eq_at(a, b, path);
+        }
+        let len = path.len();
+        path.push('/');
+        path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        let eq = self.eq_at(a, b, path);
+        path.truncate(len);
+        eq
+    }
+
+    /// Compares two strings after folding them by every enabled string
+    /// normalization, into pooled buffers, and then by the collation
+    /// if there is one
+    fn str_eq(&self, a: &str, b: &str) -> bool {
+        #[allow(unused_mut)]
+        let mut folds = self.ignore_case_style || self.insensitive;
+        #[cfg(feature = "unicode")]
+        {
+            folds |= self.ignore_diacritics;
+        }
+        #[cfg(feature = "collation")]
+        {
+            folds |= self.collation.is_some();
+        }
+        if !folds {
+            return a == b;
+        }
+        self.buffers.with_string(|x| {
+            self.buffers.with_string(|y| {
+                self.buffers.with_string(|tmp| {
+                    self.fold(a, x, tmp);
+                    self.fold(b, y, tmp);
+                    #[cfg(feature = "collation")]
+                    if let Some(collation) = &self.collation {
+                        return collation.eq(x, y);
+                    }
+                    x == y
+                })
+            })
+        })
+    }
+
+    /// Folds a string into `out` by every enabled string normalization,
+    /// passing it through `tmp` between them
+    fn fold(&self, s: &str, out: &mut String, tmp: &mut String) {
+        out.push_str(s);
+        #[cfg(feature = "unicode")]
+        if self.ignore_diacritics {
+            tmp.clear();
+            tmp.extend(crate::diacritics::strip_diacritics(out));
+            std::mem::swap(out, tmp);
+        }
+        if self.ignore_case_style {
+            tmp.clear();
+            tmp.extend(identifier_words(out));
+            std::mem::swap(out, tmp);
+        }
+        if self.insensitive {
+            tmp.clear();
+            crate::collation::case_fold(out, tmp);
+            std::mem::swap(out, tmp);
+        }
+    }
+}
+
+/// Parses integer literals like `0xFF`, `0o755`, or `-0b1010`, allowing `_`
+/// between digits. Decimal strings are not parsed, so `"42"` stays a string.
+fn parse_radix_literal(s: &str) -> Option<i128> {
+    let (negative, literal) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let radix = match literal.get(..2)? {
+        "0x" | "0X" => 16,
+        "0o" | "0O" => 8,
+        "0b" | "0B" => 2,
+        _ => return None,
+    };
+    let digits = &literal[2..];
+    if digits.starts_with('_') {
+        return None;
+    }
+    let n = i128::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+    Some(if negative { -n } else { n })
+}
+
+fn is_container(value: &Value) -> bool {
+    matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+/// Reads the booleans YAML 1.1 spells as words, in any case
+fn yaml_bool(s: &str) -> Option<bool> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "yes" | "on" | "true" => Some(true),
+        "no" | "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads the 32 hex digits of a UUID in lower case, accepting upper case,
+/// missing hyphens, braces, and a `urn:uuid:` prefix
+fn uuid_digits(s: &str) -> Option<String> {
+    let s = s.trim();
+    let s = s
+        .strip_prefix("urn:uuid:")
+        .or_else(|| s.strip_prefix('{').and_then(|x| x.strip_suffix('}')))
+        .unwrap_or(s);
+    let hyphens = [8, 13, 18, 23];
+    let hyphenated = s.len() == 36
+        && s.bytes()
+            .enumerate()
+            .all(|(i, b)| (b == b'-') == hyphens.contains(&i));
+    if !(hyphenated || s.len() == 32) {
+        return None;
+    }
+    let digits: String = s.chars().filter(|c| *c != '-').collect();
+    (digits.len() == 32 && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| digits.to_ascii_lowercase())
+}
+
+/// Reads an optional boolean `{{#switch}}` hash parameter
+fn bool_hash(h: &Helper, name: &str) -> Result<Option<bool>, RenderError> {
+    h.hash_get(name)
+        .map(|x| {
+            x.value().as_bool().ok_or_else(|| {
+                RenderErrorReason::HashTypeMismatchForName(
+                    "switch",
+                    name.to_owned(),
+                    "boolean".to_owned(),
+                )
+                .into()
+            })
+        })
+        .transpose()
+}
+
+/// Folds an identifier in any naming convention, so `orderCreated`,
+/// `order_created`, and `OrderCreated` all yield the same characters
+fn identifier_words(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars()
+        .filter(|c| !matches!(c, '_' | '-' | '.' | ' '))
+        .flat_map(char::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ArrayEquality, CompareLimits, Comparison, LimitExceeded};
+    use crate::fields::FieldComparator;
+
+    #[test]
+    fn test_array_equality() {
+        let positional = Comparison::default();
+        let set = Comparison {
+            arrays: ArrayEquality::Set,
+            ..Comparison::default()
+        };
+
+        let a = json!(["read", "write", "read"]);
+        let b = json!(["write", "read"]);
+        assert!(!positional.eq(&a, &b));
+        assert!(set.eq(&a, &b));
+        assert!(set.eq(&json!({"roles": a}), &json!({"roles": b})));
+        assert!(!set.eq(&json!(["read"]), &json!(["read", "admin"])));
+        assert!(positional.eq(&json!([1, [2, 3]]), &json!([1, [2, 3]])));
+    }
+
+    #[test]
+    fn test_field_comparators() {
+        let fields = Comparison {
+            fields: Arc::new(
+                [
+                    ("/a~1b".to_owned(), FieldComparator::ignore_case()),
+                    ("/list/1".to_owned(), FieldComparator::new(|_, _| true)),
+                    ("/nested/v".to_owned(), FieldComparator::semver()),
+                ]
+                .into(),
+            ),
+            ..Comparison::default()
+        };
+
+        assert!(fields.eq(&json!({"a/b": "X"}), &json!({"a/b": "x"})));
+        assert!(!fields.eq(&json!({"ab": "X"}), &json!({"ab": "x"})));
+        assert!(fields.eq(&json!({"list": [1, 2]}), &json!({"list": [1, 3]})));
+        assert!(!fields.eq(&json!({"list": [0, 2]}), &json!({"list": [1, 2]})));
+        assert!(fields.eq(
+            &json!({"nested": {"v": "1.2"}}),
+            &json!({"nested": {"v": "v1.2.0"}})
+        ));
+        assert!(!Comparison::default().eq(&json!({"a/b": "X"}), &json!({"a/b": "x"})));
+    }
+
+    #[test]
+    fn test_ignore_case_style() {
+        let styled = Comparison {
+            ignore_case_style: true,
+            ..Comparison::default()
+        };
+
+        let names = [
+            "orderCreated",
+            "order_created",
+            "OrderCreated",
+            "order-created",
+        ];
+        for a in names {
+            for b in names {
+                assert!(styled.eq(&json!(a), &json!(b)));
+            }
+        }
+        assert!(!Comparison::default().eq(&json!("orderCreated"), &json!("order_created")));
+        assert!(!styled.eq(&json!("order_created"), &json!("order_create")));
+        assert!(styled.eq(&json!(["Ü_x"]), &json!(["üX"])));
+    }
+
+    #[test]
+    fn test_insensitive() {
+        let insensitive = Comparison {
+            insensitive: true,
+            ..Comparison::default()
+        };
+        assert!(insensitive.eq(&json!("ADMIN"), &json!("admin")));
+        assert!(insensitive.eq(&json!("Straße"), &json!("STRASSE")));
+        assert!(insensitive.eq(&json!("ὈΔΥΣΣΕΎΣ"), &json!("ὀδυσσεύς")));
+        assert!(insensitive.eq(&json!({"Role": ["Ärztin"]}), &json!({"Role": ["ÄRZTIN"]})));
+        assert!(!insensitive.eq(&json!({"Role": 1}), &json!({"role": 1})));
+        assert!(!insensitive.eq(&json!("order_created"), &json!("OrderCreated")));
+        assert!(!Comparison::default().eq(&json!("ADMIN"), &json!("admin")));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn test_ignore_diacritics() {
+        let folded = Comparison {
+            ignore_diacritics: true,
+            ..Comparison::default()
+        };
+        assert!(folded.eq(&json!("José"), &json!("Jose")));
+        assert!(folded.eq(&json!({"city": "Malmö"}), &json!({"city": "Malmo"})));
+        assert!(!folded.eq(&json!("José"), &json!("jose")));
+        assert!(!Comparison::default().eq(&json!("José"), &json!("Jose")));
+
+        let both = Comparison {
+            ignore_case_style: true,
+            ..folded
+        };
+        assert!(both.eq(&json!("José"), &json!("jose")));
+    }
+
+    #[test]
+    fn test_units() {
+        let units = Comparison {
+            units: true,
+            ..Comparison::default()
+        };
+        assert!(units.eq(&json!("1500ms"), &json!("1.5s")));
+        assert!(units.eq(&json!(["5MB"]), &json!(["5000KB"])));
+        assert!(!units.eq(&json!("1s"), &json!("1000B")));
+        assert!(!Comparison::default().eq(&json!("1500ms"), &json!("1.5s")));
+    }
+
+    #[cfg(feature = "currency")]
+    #[test]
+    fn test_currency() {
+        let currency = Comparison {
+            currency: true,
+            ..Comparison::default()
+        };
+        assert!(currency.eq(&json!("1.234,56 €"), &json!("€1,234.56")));
+        assert!(currency.eq(&json!("$12"), &json!("USD")));
+        assert!(!currency.eq(&json!("$12"), &json!("EUR")));
+        assert!(!Comparison::default().eq(&json!("$12"), &json!("USD 12")));
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        let comparison = Comparison::default();
+        assert!(comparison.eq(&json!(255), &json!("0xFF")));
+        assert!(comparison.eq(&json!("0o755"), &json!(493)));
+        assert!(comparison.eq(&json!(10), &json!("0b1010")));
+        assert!(comparison.eq(&json!(-16), &json!("-0x10")));
+        assert!(comparison.eq(&json!(u64::MAX), &json!("0xffff_ffff_ffff_ffff")));
+        assert!(comparison.eq(&json!([493]), &json!(["0o755"])));
+        assert!(!comparison.eq(&json!(42), &json!("42")));
+        assert!(!comparison.eq(&json!(1.0), &json!("0x1")));
+        assert!(!comparison.eq(&json!(0), &json!("0x")));
+        assert!(!comparison.eq(&json!(0), &json!("0x_0")));
+        assert!(!comparison.eq(&json!("0xFF"), &json!("255")));
+    }
+
+    #[cfg(feature = "instants")]
+    #[test]
+    fn test_instants() {
+        let instants = Comparison {
+            instants: true,
+            ..Comparison::default()
+        };
+        let (a, b) = (
+            json!("2024-01-01T00:00:00Z"),
+            json!("2024-01-01T01:00:00+01:00"),
+        );
+        assert!(instants.eq(&a, &b));
+        assert!(!Comparison::default().eq(&a, &b));
+        assert!(!instants.eq(&a, &json!("2024-01-01T00:00:00+01:00")));
+    }
+
+    #[test]
+    fn test_uuids() {
+        let uuids = Comparison {
+            uuids: true,
+            ..Comparison::default()
+        };
+        let id = json!("6ba7b810-9dad-11d1-80b4-00c04fd430c8");
+        let forms = [
+            "6BA7B810-9DAD-11D1-80B4-00C04FD430C8",
+            "6ba7b8109dad11d180b400c04fd430c8",
+            "{6ba7b810-9dad-11d1-80b4-00c04fd430c8}",
+            "urn:uuid:6ba7b810-9dad-11d1-80b4-00c04fd430c8",
+        ];
+        for form in forms {
+            assert!(uuids.eq(&id, &json!(form)));
+            assert!(!Comparison::default().eq(&id, &json!(form)));
+        }
+        assert!(!uuids.eq(&id, &json!("6ba7b811-9dad-11d1-80b4-00c04fd430c8")));
+        assert!(!uuids.eq(&json!("6ba7-b810"), &json!("6BA7-B810")));
+        assert!(!uuids.eq(&id, &json!("6ba7b8109-dad-11d1-80b4-00c04fd430c8")));
+    }
+
+    #[test]
+    fn test_limits() {
+        let limited = |on_exceeded| Comparison {
+            limits: Some(CompareLimits {
+                max_depth: 1,
+                max_nodes: 3,
+                on_exceeded,
+            }),
+            ..Comparison::default()
+        };
+
+        let default = limited(LimitExceeded::Default);
+        assert!(default.admits(&json!("scalar")).unwrap());
+        assert!(default.admits(&json!([1, 2])).unwrap());
+        assert!(!default.admits(&json!([1, 2, 3])).unwrap());
+        assert!(!default.admits(&json!([[1]])).unwrap());
+        assert!(limited(LimitExceeded::Error).admits(&json!([[1]])).is_err());
+        assert!(Comparison::default().admits(&json!([[[[1]]]])).unwrap());
+
+        let deep = (0..200).fold(json!(1), |x, _| json!([x]));
+        assert!(Comparison::default().admits(&deep).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use serde_json::Value;
+
+/// Compat Mode
+///
+/// Semantics presets for `{{#switch}}` that mimic switch helpers commonly
+/// found in JavaScript projects, so templates copied from them render the
+/// same without edits. In every mode `@switch_break` is `true` inside the
+/// block once a `{{#case}}` has rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompatMode {
+    /// The semantics of this crate: only the first matching arm renders.
+    #[default]
+    Native,
+    /// The helper widely copied between Node projects: every matching
+    /// `{{#case}}` renders, values compare with the loose `==` of
+    /// JavaScript, and `{{#default}}` renders unless a `{{#case}}` before
+    /// it rendered.
+    Loose,
+    /// The semantics of a C `switch`: once an arm renders, every arm after
+    /// it renders as well, `{{#default}}` included, until one whose body
+    /// holds a `{{break}}`. The rest of that arm still renders. The
+    /// `{{break}}` must sit directly in the arm body rather than inside
+    /// another block helper like `{{#each}}`.
+    Fallthrough,
+}
+
+/// Compares two values like the loose `==` of JavaScript, treating `null`
+/// as both `null` and `undefined`. Arrays and objects, which JavaScript
+/// compares by reference, are equal when their contents are.
+pub(crate) fn loose_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Null, Value::Null) => true,
+        (Value::Null, _) | (_, Value::Null) => false,
+        (Value::Bool(x), y) | (y, Value::Bool(x)) if !y.is_boolean() => {
+            loose_eq(&json!(u8::from(*x)), y)
+        }
+        (Value::Number(_), Value::String(_)) | (Value::String(_), Value::Number(_)) => {
+            to_number(a) == to_number(b)
+        }
+        (Value::Array(_) | Value::Object(_), Value::String(_) | Value::Number(_)) => {
+            loose_eq(&Value::String(to_string(a)), b)
+        }
+        (Value::String(_) | Value::Number(_), Value::Array(_) | Value::Object(_)) => {
+            loose_eq(a, &Value::String(to_string(b)))
+        }
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (a, b) => a == b,
+    }
+}
+
+/// Converts a scalar to a number like `Number(x)` in JavaScript, with `NaN`
+/// for anything that is not a number
+fn to_number(value: &Value) -> f64 {
+    let s = match value {
+        Value::Number(n) => return n.as_f64().unwrap_or(f64::NAN),
+        Value::String(s) => s.trim(),
+        _ => return f64::NAN,
+    };
+    if s.is_empty() {
+        return 0.0;
+    }
+    let (radix, digits) = match s.get(..2) {
+        Some("0x" | "0X") => (16, &s[2..]),
+        Some("0o" | "0O") => (8, &s[2..]),
+        Some("0b" | "0B") => (2, &s[2..]),
+        _ => (10, s),
+    };
+    if radix != 10 {
+        return u128::from_str_radix(digits, radix).map_or(f64::NAN, |n| n as f64);
+    }
+    match s.strip_prefix(['+', '-']).unwrap_or(s) {
+        "Infinity" if s.starts_with('-') => f64::NEG_INFINITY,
+        "Infinity" => f64::INFINITY,
+        // Rust also reads words like `inf` and `nan`, which JavaScript does not
+        _ if s.contains(|c: char| !matches!(c, '0'..='9' | '+' | '-' | '.' | 'e' | 'E')) => {
+            f64::NAN
+        }
+        _ => s.parse().unwrap_or(f64::NAN),
+    }
+}
+
+/// Converts an array or object to a string like `String(x)` in JavaScript
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(to_string).collect::<Vec<_>>().join(","),
+        Value::Object(_) => "[object Object]".to_owned(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{loose_eq, to_number};
+
+    #[test]
+    fn test_loose_eq() {
+        let equal = [
+            (json!(1), json!("1")),
+            (json!(16), json!(" 0x10 ")),
+            (json!(0), json!("")),
+            (json!(true), json!("1")),
+            (json!(false), json!(0)),
+            (json!(1), json!(1.0)),
+            (json!([1, [2, null]]), json!("1,2,")),
+            (json!({"a": 1}), json!("[object Object]")),
+            (json!(null), json!(null)),
+            (json!("1e3"), json!(1000)),
+        ];
+        for (a, b) in equal {
+            assert!(loose_eq(&a, &b), "{a} == {b}");
+            assert!(loose_eq(&b, &a), "{b} == {a}");
+        }
+
+        let unequal = [
+            (json!(null), json!(0)),
+            (json!(null), json!("")),
+            (json!("true"), json!(true)),
+            (json!("a"), json!("A")),
+            (json!("inf"), json!(f64::MAX)),
+            (json!("1_000"), json!(1000)),
+            (json!("-0x10"), json!(-16)),
+        ];
+        for (a, b) in unequal {
+            assert!(!loose_eq(&a, &b), "{a} != {b}");
+        }
+
+        assert_eq!(to_number(&json!("-Infinity")), f64::NEG_INFINITY);
+        assert!(to_number(&json!("Infinityx")).is_nan());
+    }
+}
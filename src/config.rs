@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use handlebars::{Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext};
+
+use crate::{
+    ArrHelper, BinsHelper, CaptureHelper, CmpHelper, FsmHelper, ObjHelper, SetHelper, SlotHelper,
+    SwitchEntriesHelper, SwitchFlagsHelper, SwitchHelper, SwitchOrdinalHelper, SwitchResultHelper,
+    SwitchSomeHelper,
+};
+
+/// A helper of the switch family that renders its block through a
+/// `{{#switch}}` configured elsewhere.
+pub(crate) trait SwitchFamily {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult;
+}
+
+/// A helper of the switch family along with the switch it renders through
+struct Configured<H> {
+    helper: H,
+    switch: SwitchHelper,
+}
+
+impl<H: SwitchFamily> HelperDef for Configured<H> {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.helper.render_with(&self.switch, h, r, ctx, rc, out)
+    }
+}
+
+/// Switch Config
+///
+/// Options shared by every helper of the switch family registered with
+/// [`SwitchConfig::register`], so an application sets up comparison,
+/// normalization, strictness, and diagnostics such as tracing in one place.
+/// The defaults are given as a [`SwitchHelper`] built as usual, and the
+/// helpers built on it, like `{{#bins}}` and `{{#cmp}}`, match their arms
+/// with the same options. Options for a single helper are layered on top
+/// with [`SwitchConfig::with_helper`], and the hash parameters of a single
+/// block, like `ignore_case_style=false`, still apply above both.
+///
+/// [`FsmHelper`] needs its transitions, so it is registered on its own with
+/// [`SwitchConfig::register_fsm`].
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{Lowercase, SwitchConfig, SwitchHelper};
+///
+/// let config = SwitchConfig::new(SwitchHelper::new().with_normalizer(Lowercase))
+///     .with_helper("switch", |switch| switch.with_redacted_values(true));
+/// let mut handlebars = Handlebars::new();
+/// config.register(&mut handlebars);
+///
+/// let tpl = "\
+///     {{#bins score edges=(arr 50) labels=(arr \"FAIL\" \"PASS\")}}\
+///         {{#case \"pass\"}}Passed{{/case}}\
+///     {{/bins}}\
+///     {{#switch role}}{{#case \"admin\"}} as admin{{/case}}{{/switch}}\
+/// ";
+/// let data = json!({"score": 70, "role": "ADMIN"});
+/// assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "Passed as admin");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct SwitchConfig {
+    base: SwitchHelper,
+    helpers: HashMap<String, SwitchHelper>,
+}
+
+impl SwitchConfig {
+    /// Creates a config whose helpers share the options of `base`.
+    pub fn new(base: SwitchHelper) -> SwitchConfig {
+        SwitchConfig {
+            base,
+            helpers: HashMap::new(),
+        }
+    }
+
+    /// Layers the options `f` adds to the shared ones onto the helper
+    /// registered as `name` alone, such as `"switch"` or `"bins"`.
+    pub fn with_helper(
+        mut self,
+        name: &str,
+        f: impl FnOnce(SwitchHelper) -> SwitchHelper,
+    ) -> SwitchConfig {
+        let switch = f(self.switch_helper(name));
+        self.helpers.insert(name.to_owned(), switch);
+        self
+    }
+
+    /// Returns the switch the helper registered as `name` uses, to register
+    /// a further `{{#switch}}` under another name with the same options.
+    pub fn switch_helper(&self, name: &str) -> SwitchHelper {
+        self.helpers.get(name).unwrap_or(&self.base).clone()
+    }
+
+    /// Registers every helper of this crate under its documented name, like
+    /// [`register`](crate::register), with the switch family configured by
+    /// this config.
+    pub fn register(&self, handlebars: &mut Handlebars) {
+        handlebars.register_helper("switch", Box::new(self.switch_helper("switch")));
+        handlebars.register_helper("switch_some", Box::new(SwitchSomeHelper));
+        handlebars.register_helper("switch_result", Box::new(SwitchResultHelper::new()));
+        self.register_family(handlebars, "switch_flags", SwitchFlagsHelper);
+        self.register_family(handlebars, "switch_entries", SwitchEntriesHelper);
+        self.register_family(handlebars, "switch_ordinal", SwitchOrdinalHelper);
+        self.register_family(handlebars, "bins", BinsHelper);
+        self.register_family(handlebars, "cmp", CmpHelper);
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+        handlebars.register_helper("obj", Box::new(ObjHelper));
+        handlebars.register_helper("set", Box::new(SetHelper));
+        handlebars.register_helper("capture", Box::new(CaptureHelper));
+        handlebars.register_helper("slot", Box::new(SlotHelper::new()));
+    }
+
+    /// Registers `fsm` as the `{{#fsm}}` helper, switching on the `to` state
+    /// with the options this config gives the helper named `"fsm"`.
+    pub fn register_fsm(&self, handlebars: &mut Handlebars, fsm: FsmHelper) {
+        self.register_family(handlebars, "fsm", fsm);
+    }
+
+    fn register_family<H>(&self, handlebars: &mut Handlebars, name: &str, helper: H)
+    where
+        H: SwitchFamily + Send + Sync + 'static,
+    {
+        let switch = self.switch_helper(name);
+        handlebars.register_helper(name, Box::new(Configured { helper, switch }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchConfig;
+    use crate::{FsmHelper, RenderTracer, SwitchHelper};
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_switch_config() {
+        let tracer = RenderTracer::new();
+        let config = SwitchConfig::new(
+            SwitchHelper::new()
+                .with_ignore_case_style(true)
+                .with_tracer(tracer.clone()),
+        )
+        .with_helper("cmp", |switch| switch.with_ignore_case_style(false));
+        let mut handlebars = Handlebars::new();
+        config.register(&mut handlebars);
+
+        let render = |tpl| handlebars.render_template(tpl, &json!({"kind": "order_created"}));
+        let tpl = "{{#switch kind}}{{#case \"OrderCreated\"}}a{{/case}}{{/switch}}";
+        assert_eq!(render(tpl).unwrap(), "a");
+        let tpl = "{{#switch kind ignore_case_style=false}}{{#case \"OrderCreated\"}}a{{/case}}{{/switch}}";
+        assert_eq!(render(tpl).unwrap(), "");
+        let tpl = "{{#switch_entries (obj a=kind)}}{{#case \"orderCreated\"}}b{{/case}}{{/switch_entries}}";
+        assert_eq!(render(tpl).unwrap(), "b");
+        let tpl =
+            "{{#bins 2 edges=(arr 1) labels=(arr \"x\" \"Y\")}}{{#case \"y\"}}c{{/case}}{{/bins}}";
+        assert_eq!(render(tpl).unwrap(), "c");
+        assert_eq!(tracer.take().len(), 4);
+
+        let tpl = "{{#cmp 1 2}}{{#case \"LT\"}}d{{/case}}{{#default}}e{{/default}}{{/cmp}}";
+        assert_eq!(render(tpl).unwrap(), "e");
+        assert_eq!(tracer.take().len(), 1);
+
+        let fsm = FsmHelper::new().with_transitions([("new", "order_created")]);
+        config.register_fsm(&mut handlebars, fsm);
+        let render = |tpl| handlebars.render_template(tpl, &json!({"kind": "order_created"}));
+        let tpl = "{{#fsm from=\"new\" to=kind}}{{#case \"OrderCreated\"}}f{{/case}}{{/fsm}}";
+        assert_eq!(render(tpl).unwrap(), "f");
+        assert_eq!(tracer.take().len(), 1);
+        assert!(render("{{#fsm from=kind to=kind}}{{/fsm}}").is_err());
+    }
+}
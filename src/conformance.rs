@@ -0,0 +1,334 @@
+use handlebars::{Handlebars, RenderError};
+use serde_json::{Map, Value};
+
+use crate::SwitchHelper;
+
+/// Arm Spec
+///
+/// How a `{{#case}}` arm is written: the values it lists, and its hash
+/// parameters such as `glob=true` or `regex="^a"`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArmSpec {
+    /// The values listed by the `{{#case}}`.
+    pub values: Vec<Value>,
+    /// The hash parameters of the `{{#case}}`, by name.
+    pub hash: Map<String, Value>,
+}
+
+impl ArmSpec {
+    /// Creates the spec of a `{{#case}}` listing `values`.
+    pub fn new<I, V>(values: I) -> ArmSpec
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        ArmSpec {
+            values: values.into_iter().map(Into::into).collect(),
+            hash: Map::new(),
+        }
+    }
+
+    /// Adds the hash parameter `name=value`.
+    pub fn with_hash(mut self, name: &str, value: impl Into<Value>) -> ArmSpec {
+        self.hash.insert(name.to_owned(), value.into());
+        self
+    }
+
+    /// Whether the arm lists a single value and nothing else, so it can be
+    /// checked with the value and the switch value swapped
+    fn is_plain(&self) -> bool {
+        self.values.len() == 1 && self.hash.is_empty()
+    }
+}
+
+/// Case Matcher
+///
+/// Decides whether a `{{#case}}` arm matches a switch value, so the
+/// semantics of `{{#case}}` can be checked by [`check_conformance`] for any
+/// implementation of them. [`SwitchHelper`] implements it by rendering the
+/// arm, so every option it was configured with takes part.
+pub trait CaseMatcher {
+    /// Returns whether the `{{#case}}` written as `arm` matches `value`, or
+    /// why it fails to render.
+    fn case_matches(&self, value: &Value, arm: &ArmSpec) -> Result<bool, RenderError>;
+}
+
+impl CaseMatcher for SwitchHelper {
+    fn case_matches(&self, value: &Value, arm: &ArmSpec) -> Result<bool, RenderError> {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(self.clone()));
+        let mut params: Vec<_> = (0..arm.values.len())
+            .map(|i| format!("values.[{i}]"))
+            .collect();
+        params.extend(arm.hash.keys().map(|name| format!("{name}=hash.[{name}]")));
+        let tpl = format!(
+            "{{{{#switch value}}}}{{{{#case {}}}}}1{{{{/case}}}}{{{{#default}}}}0{{{{/default}}}}{{{{/switch}}}}",
+            params.join(" ")
+        );
+        let data = json!({"value": value, "values": arm.values, "hash": arm.hash});
+        Ok(handlebars.render_template(&tpl, &data)? == "1")
+    }
+}
+
+/// Conformance Fixture
+///
+/// A switch value and a `{{#case}}` arm along with whether every
+/// [`CaseMatcher`] must match them, whatever comparison it was configured
+/// with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConformanceFixture {
+    /// The invariant the fixture checks.
+    pub rule: &'static str,
+    /// The value switched on.
+    pub value: Value,
+    /// The `{{#case}}` arm.
+    pub arm: ArmSpec,
+    /// Whether the `{{#case}}` must match.
+    pub expected: bool,
+}
+
+/// Conformance Failure
+///
+/// A [`ConformanceFixture`] that a [`CaseMatcher`] did not respect, as found
+/// by [`check_conformance`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConformanceFailure {
+    /// The fixture, with the value and the one the arm lists swapped when
+    /// only the reverse comparison failed.
+    pub fixture: ConformanceFixture,
+    /// Whether the `{{#case}}` matched, or `None` when the render failed.
+    pub matched: Option<bool>,
+}
+
+/// Conformance Fixtures
+///
+/// Returns the fixtures every comparison of the crate respects: a value
+/// matches itself, `null` matches nothing but `null`, and booleans, numbers,
+/// and strings are not coerced into one another, in both directions. The
+/// arm specs `glob=true`, `not=true` and `if=`, and with the `regex` feature
+/// `regex=`, keep to their documented semantics on top. Custom comparators,
+/// normalizers and matchers are expected to keep to them too, see
+/// [`check_conformance`].
+pub fn conformance_fixtures() -> Vec<ConformanceFixture> {
+    let fixture = |rule, value, arm, expected| ConformanceFixture {
+        rule,
+        value,
+        arm,
+        expected,
+    };
+    let mut fixtures: Vec<_> = [
+        json!(null),
+        json!(true),
+        json!(0),
+        json!(-1.5),
+        json!(""),
+        json!("admin"),
+        json!([1, "a"]),
+        json!({"a": [null]}),
+    ]
+    .into_iter()
+    .map(|x| fixture("a value matches itself", x.clone(), ArmSpec::new([x]), true))
+    .collect();
+    for other in [
+        json!(""),
+        json!(0),
+        json!(false),
+        json!("null"),
+        json!([]),
+        json!({}),
+    ] {
+        fixtures.push(fixture(
+            "`null` matches only `null`",
+            json!(null),
+            ArmSpec::new([other]),
+            false,
+        ));
+    }
+    for (value, case) in [
+        (json!(true), json!(1)),
+        (json!(false), json!(0)),
+        (json!(true), json!("1")),
+        (json!(""), json!(0)),
+        (json!([1]), json!(1)),
+    ] {
+        fixtures.push(fixture(
+            "values of other types do not match",
+            value,
+            ArmSpec::new([case]),
+            false,
+        ));
+    }
+    let glob = |pattern: &str| ArmSpec::new([pattern]).with_hash("glob", true);
+    for (value, arm, expected) in [
+        (json!("admin-1"), glob("admin-*"), true),
+        (json!("abc"), glob("a?c"), true),
+        (json!("admin"), glob("admin-*"), false),
+        (json!("xadmin-1"), glob("admin-*"), false),
+        (json!(1), glob("1"), false),
+        (json!(null), glob("*"), false),
+        (json!(1), ArmSpec::new([1]).with_hash("glob", true), true),
+    ] {
+        fixtures.push(fixture(
+            "`glob=true` matches strings against the patterns listed",
+            value,
+            arm,
+            expected,
+        ));
+    }
+    #[cfg(feature = "regex")]
+    {
+        let regex = |pattern: &str| ArmSpec::default().with_hash("regex", pattern);
+        for (value, arm, expected) in [
+            (json!("admin-12"), regex("^admin-\\d+$"), true),
+            (json!("xadmin"), regex("admin"), true),
+            (json!("admin"), regex("^admin-"), false),
+            (json!(12), regex("12"), false),
+            (json!(null), regex(""), false),
+            (json!("a"), regex("^a").with_hash("glob", true), true),
+        ] {
+            fixtures.push(fixture(
+                "`regex=` matches strings against the pattern",
+                value,
+                arm,
+                expected,
+            ));
+        }
+    }
+    let not = |case: Value| ArmSpec::new([case]).with_hash("not", true);
+    for (value, arm, expected) in [
+        (json!("b"), not(json!("a")), true),
+        (json!("a"), not(json!("a")), false),
+        (json!(null), not(json!(null)), false),
+        (json!(null), not(json!("a")), true),
+        (json!(1), not(json!("1")), true),
+    ] {
+        fixtures.push(fixture(
+            "`not=true` matches what the arm does not list",
+            value,
+            arm,
+            expected,
+        ));
+    }
+    let guarded = |case: &str, guard: Value| ArmSpec::new([case]).with_hash("if", guard);
+    for (value, arm, expected) in [
+        (json!("a"), guarded("a", json!(true)), true),
+        (json!("a"), guarded("a", json!("yes")), true),
+        (json!("a"), guarded("a", json!(false)), false),
+        (json!("a"), guarded("a", json!(0)), false),
+        (json!("a"), guarded("a", json!("")), false),
+        (json!("a"), guarded("a", json!(null)), false),
+        (json!("a"), guarded("b", json!(true)), false),
+        (
+            json!("a"),
+            guarded("b", json!(false)).with_hash("not", true),
+            false,
+        ),
+    ] {
+        fixtures.push(fixture("a falsy `if=` skips the arm", value, arm, expected));
+    }
+    fixtures
+}
+
+/// Check Conformance
+///
+/// Checks every [`conformance_fixtures`] fixture with `matcher`, plain ones
+/// both ways round, and returns the ones it gets wrong, so authors of a
+/// custom [`CaseMatcher`], [`FieldComparator`](crate::FieldComparator) or
+/// [`Normalize`](crate::Normalize) can check it keeps the semantics
+/// templates rely on. Options that coerce on purpose, such as
+/// [`CompatMode::Loose`](crate::CompatMode::Loose), do not conform.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars_switch::{check_conformance, FieldComparator, SwitchHelper};
+///
+/// let semver = SwitchHelper::new().with_field_comparator("", FieldComparator::semver());
+/// assert!(check_conformance(semver).is_empty());
+///
+/// let truthy = FieldComparator::new(|a, b| a.is_null() == b.is_null());
+/// let sloppy = SwitchHelper::new().with_field_comparator("", truthy);
+/// assert!(!check_conformance(sloppy).is_empty());
+/// ```
+pub fn check_conformance(matcher: impl CaseMatcher) -> Vec<ConformanceFailure> {
+    let mut failures = Vec::new();
+    for fixture in conformance_fixtures() {
+        let reversed = fixture.arm.is_plain().then(|| ConformanceFixture {
+            value: fixture.arm.values[0].clone(),
+            arm: ArmSpec::new([fixture.value.clone()]),
+            ..fixture.clone()
+        });
+        for fixture in [Some(fixture), reversed].into_iter().flatten() {
+            let matched = matcher.case_matches(&fixture.value, &fixture.arm).ok();
+            if matched != Some(fixture.expected) {
+                failures.push(ConformanceFailure { fixture, matched });
+                break;
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_conformance, ArmSpec, CaseMatcher};
+    use crate::{ArrayEquality, CompatMode, FieldComparator, Lowercase, SwitchHelper};
+    use handlebars::RenderError;
+    use serde_json::Value;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_crate_options_conform() {
+        let helpers = [
+            SwitchHelper::new(),
+            SwitchHelper::new().with_array_equality(ArrayEquality::Set),
+            SwitchHelper::new().with_ignore_case_style(true),
+            SwitchHelper::new().with_units(true),
+            SwitchHelper::new().with_uuids(true),
+            SwitchHelper::new().with_colors(true),
+            SwitchHelper::new().with_normalizer(Lowercase),
+            SwitchHelper::new().with_field_comparator("", FieldComparator::ignore_case()),
+        ];
+        for helper in helpers {
+            assert_eq!(check_conformance(helper), []);
+        }
+    }
+
+    #[test]
+    fn test_coercing_options_do_not_conform() {
+        let loose = SwitchHelper::new().with_compat(CompatMode::Loose);
+        assert!(!check_conformance(loose).is_empty());
+
+        let failures = check_conformance(SwitchHelper::new().with_stringify(true));
+        assert!(failures
+            .iter()
+            .any(|x| x.fixture.rule == "`null` matches only `null`"));
+    }
+
+    /// Matches the values an arm lists, ignoring its hash parameters
+    struct ListedOnly;
+
+    impl CaseMatcher for ListedOnly {
+        fn case_matches(&self, value: &Value, arm: &ArmSpec) -> Result<bool, RenderError> {
+            Ok(arm.values.contains(value))
+        }
+    }
+
+    #[test]
+    fn test_custom_matcher() {
+        let failures = check_conformance(ListedOnly);
+        let rules: HashSet<_> = failures.iter().map(|x| x.fixture.rule).collect();
+        let mut expected = vec![
+            "`glob=true` matches strings against the patterns listed",
+            "`not=true` matches what the arm does not list",
+            "a falsy `if=` skips the arm",
+        ];
+        if cfg!(feature = "regex") {
+            expected.push("`regex=` matches strings against the pattern");
+        }
+        assert_eq!(rules, expected.into_iter().collect());
+        assert!(failures
+            .iter()
+            .all(|x| x.matched == Some(!x.fixture.expected)));
+    }
+}
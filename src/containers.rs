@@ -0,0 +1,48 @@
+use handlebars::{RenderError, RenderErrorReason};
+use serde_json::Value;
+
+/// Container Policy
+///
+/// What a `{{#switch}}` does when its value is an array or an object, which
+/// usually means the template switches on the wrong path, such as `order`
+/// where `order.status` was meant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContainerPolicy {
+    /// Compare the array or object with the `{{#case}}` values as a whole.
+    #[default]
+    Compare,
+    /// Fail the render.
+    Error,
+    /// Switch on the compact JSON of the array or object, like `"[1,2]"`.
+    Stringify,
+}
+
+impl ContainerPolicy {
+    /// Applies the policy to the switch value, showing it as `shown` in an
+    /// error
+    pub(crate) fn apply(
+        self,
+        value: Value,
+        shown: impl Fn(&Value) -> Value,
+    ) -> Result<Value, RenderError> {
+        if !matches!(value, Value::Array(_) | Value::Object(_)) {
+            return Ok(value);
+        }
+        match self {
+            ContainerPolicy::Compare => Ok(value),
+            ContainerPolicy::Error => {
+                let kind = if value.is_array() {
+                    "an array"
+                } else {
+                    "an object"
+                };
+                Err(RenderErrorReason::Other(format!(
+                    "`{{{{#switch}}}}` value {} is {kind}, not a scalar",
+                    shown(&value)
+                ))
+                .into())
+            }
+            ContainerPolicy::Stringify => Ok(Value::String(value.to_string())),
+        }
+    }
+}
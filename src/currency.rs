@@ -0,0 +1,172 @@
+use serde_json::Value;
+
+/// Currency symbols and the ISO 4217 code they stand for
+const SYMBOLS: &[(&str, &str)] = &[
+    ("€", "EUR"),
+    ("$", "USD"),
+    ("US$", "USD"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+    ("CHF", "CHF"),
+    ("kr", "SEK"),
+    ("zł", "PLN"),
+    ("R$", "BRL"),
+];
+
+/// An amount of money, with its currency when one was given
+#[derive(Clone, Debug, PartialEq)]
+struct Money {
+    amount: f64,
+    currency: Option<String>,
+}
+
+/// Reads a currency symbol or an upper case ISO 4217 code
+fn currency_code(s: &str) -> Option<String> {
+    let s = s.trim();
+    SYMBOLS
+        .iter()
+        .find(|(symbol, _)| *symbol == s)
+        .map(|(_, code)| (*code).to_owned())
+        .or_else(|| {
+            (s.len() == 3 && s.bytes().all(|b| b.is_ascii_uppercase())).then(|| s.to_owned())
+        })
+}
+
+/// Reads an amount like `1,234.56`, `1.234,56`, or `1 234`. The last `,` or
+/// `.` is the decimal separator when another separator comes before it, or
+/// when it is the only one and not followed by exactly three digits.
+fn parse_amount(s: &str) -> Option<f64> {
+    let is_separator = |c: char| matches!(c, ',' | '.' | ' ' | '\'' | '\u{a0}' | '\u{202f}');
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if !digits.starts_with(|c: char| c.is_ascii_digit())
+        || !digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || is_separator(c))
+    {
+        return None;
+    }
+
+    let decimal = digits.rfind([',', '.']).filter(|i| {
+        let separator = &digits[*i..*i + 1];
+        let grouped_otherwise = digits[..*i]
+            .chars()
+            .any(|c| is_separator(c) && !separator.starts_with(c));
+        let single = digits.matches(separator).count() == 1;
+        grouped_otherwise || single && digits.len() - i - 1 != 3
+    });
+    let (int, frac) = decimal.map_or((digits, ""), |i| (&digits[..i], &digits[i + 1..]));
+    let int: String = int.chars().filter(char::is_ascii_digit).collect();
+    let sign = if digits.len() < s.len() { "-" } else { "" };
+    format!("{sign}{int}.{frac}").parse().ok()
+}
+
+/// Parses strings like `"1.234,56 €"`, `"$1,234.56"`, or `"EUR 12"`
+fn parse_money(s: &str) -> Option<Money> {
+    let s = s.trim();
+    let start = s.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let end = s
+        .rfind(|c: char| c.is_ascii_digit())
+        .map_or(s.len(), |i| i + 1);
+    if end <= start {
+        return None;
+    }
+    let (prefix, amount, suffix) = (&s[..start], &s[start..end], &s[end..]);
+    let currency = match (prefix.trim(), suffix.trim()) {
+        ("", "") => None,
+        (code, "") | ("", code) => Some(currency_code(code)?),
+        _ => return None,
+    };
+    Some(Money {
+        amount: parse_amount(amount)?,
+        currency,
+    })
+}
+
+/// Compares two values as money when at least one is a currency-formatted
+/// string, returning `None` when they are not both money. A bare currency
+/// like `"EUR"` or `"€"` matches any amount in that currency, and a plain
+/// number matches the amount regardless of currency.
+pub(crate) fn same_money(a: &Value, b: &Value) -> Option<bool> {
+    let (a, b) = match (a, b) {
+        (Value::String(a), Value::String(b)) => (a.as_str(), b.as_str()),
+        (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s)) => {
+            let money = parse_money(s).filter(|m| m.currency.is_some())?;
+            return Some(n.as_f64() == Some(money.amount));
+        }
+        _ => return None,
+    };
+    match (parse_money(a), parse_money(b)) {
+        (Some(x), Some(y)) if x.currency.is_some() || y.currency.is_some() => {
+            let same_currency =
+                x.currency.is_none() || y.currency.is_none() || x.currency == y.currency;
+            Some(same_currency && x.amount == y.amount)
+        }
+        (Some(money), None) => {
+            let currency = currency_code(b)?;
+            Some(money.currency == Some(currency))
+        }
+        (None, Some(money)) => {
+            let currency = currency_code(a)?;
+            Some(money.currency == Some(currency))
+        }
+        _ => None,
+    }
+}
+
+/// Reads a number or a currency-formatted string as its amount.
+pub(crate) fn as_amount(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => parse_money(s).map(|m| m.amount),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{as_amount, parse_amount, same_money};
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("1,234.56"), Some(1234.56));
+        assert_eq!(parse_amount("1.234,56"), Some(1234.56));
+        assert_eq!(parse_amount("1 234 567"), Some(1234567.0));
+        assert_eq!(parse_amount("1'234.5"), Some(1234.5));
+        assert_eq!(parse_amount("1.234"), Some(1234.0));
+        assert_eq!(parse_amount("1.234,567"), Some(1234.567));
+        assert_eq!(parse_amount("12,5"), Some(12.5));
+        assert_eq!(parse_amount("-3"), Some(-3.0));
+        assert_eq!(parse_amount("1.2a"), None);
+        assert_eq!(parse_amount(".5"), None);
+    }
+
+    #[test]
+    fn test_same_money() {
+        assert_eq!(
+            same_money(&json!("1.234,56 €"), &json!("EUR 1,234.56")),
+            Some(true)
+        );
+        assert_eq!(
+            same_money(&json!("$1,234.56"), &json!("1234.56 USD")),
+            Some(true)
+        );
+        assert_eq!(same_money(&json!("$5"), &json!("5 €")), Some(false));
+        assert_eq!(same_money(&json!("$5"), &json!(5)), Some(true));
+        assert_eq!(same_money(&json!("£12"), &json!("GBP")), Some(true));
+        assert_eq!(same_money(&json!("€"), &json!("£12")), Some(false));
+
+        assert_eq!(same_money(&json!("12"), &json!("12")), None);
+        assert_eq!(same_money(&json!("12 apples"), &json!("12")), None);
+        assert_eq!(same_money(&json!("EUR"), &json!("EUR")), None);
+    }
+
+    #[test]
+    fn test_as_amount() {
+        assert_eq!(as_amount(&json!("1.234,56 €")), Some(1234.56));
+        assert_eq!(as_amount(&json!(3)), Some(3.0));
+        assert_eq!(as_amount(&json!("free")), None);
+    }
+}
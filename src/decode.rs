@@ -0,0 +1,146 @@
+use handlebars::{Helper, RenderError, RenderErrorReason};
+use serde_json::Value;
+
+/// Decodes the switch value as asked by the `decode=` hash parameter of the
+/// `{{#switch}}`. Strings that fail to decode, or decode to anything but
+/// UTF-8, become `null` and match no `{{#case}}`, while other values are
+/// left alone.
+pub(crate) fn decode_value(value: Value, h: &Helper) -> Result<Value, RenderError> {
+    let Some(decode) = h.hash_get("decode") else {
+        return Ok(value);
+    };
+    let decoder = match decode.value().as_str() {
+        Some("url") => url_decode,
+        Some("base64") => base64_decode,
+        _ => {
+            return Err(RenderErrorReason::HashTypeMismatchForName(
+                "switch",
+                "decode".to_owned(),
+                "\"url\" or \"base64\"".to_owned(),
+            )
+            .into())
+        }
+    };
+    Ok(match value {
+        Value::String(s) => decoder(&s)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .map_or(Value::Null, Value::String),
+        other => other,
+    })
+}
+
+/// Decodes `%XX` escapes, and `+` as a space like in query strings
+fn url_decode(s: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        decoded.push(match b {
+            b'%' => {
+                let high = hex_digit(bytes.next()?)?;
+                let low = hex_digit(bytes.next()?)?;
+                high << 4 | low
+            }
+            b'+' => b' ',
+            b => b,
+        });
+    }
+    Some(decoded)
+}
+
+fn hex_digit(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|x| x as u8)
+}
+
+/// Decodes a string of hex digit pairs like `"DEADbeef"`, optionally
+/// prefixed with `0x`
+pub(crate) fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    s.chunks(2)
+        .map(|pair| Some(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+/// Reads binary data serialized as an array of bytes or as a base64 string
+pub(crate) fn value_bytes(value: &Value) -> Option<Vec<u8>> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|x| x.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect(),
+        Value::String(s) => base64_decode(s),
+        _ => None,
+    }
+}
+
+/// Decodes standard or URL-safe base64, with or without padding
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_end_matches('=');
+    let mut decoded = Vec::with_capacity(s.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for b in s.bytes() {
+        let sextet = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // a single leftover sextet cannot encode a byte
+    (bits < 6).then_some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64_decode, hex_decode, url_decode, value_bytes};
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode("a%20b+c%2Fd").unwrap(), b"a b c/d");
+        assert_eq!(url_decode("caf%C3%A9").unwrap(), "café".as_bytes());
+        assert!(url_decode("%2").is_none());
+        assert!(url_decode("%zz").is_none());
+        assert!(url_decode("%+1").is_none());
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(base64_decode("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(base64_decode("aGVsbG8").unwrap(), b"hello");
+        assert_eq!(base64_decode("-_8").unwrap(), [0xfb, 0xff]);
+        assert_eq!(base64_decode("+/8=").unwrap(), [0xfb, 0xff]);
+        assert_eq!(base64_decode("").unwrap(), b"");
+        assert!(base64_decode("a").is_none());
+        assert!(base64_decode("a*b=").is_none());
+    }
+
+    #[test]
+    fn test_hex_decode() {
+        assert_eq!(hex_decode("DEADbeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(hex_decode("0x00ff").unwrap(), [0x00, 0xff]);
+        assert_eq!(hex_decode("").unwrap(), b"");
+        assert!(hex_decode("abc").is_none());
+        assert!(hex_decode("zz").is_none());
+    }
+
+    #[test]
+    fn test_value_bytes() {
+        assert_eq!(value_bytes(&json!([222, 173])).unwrap(), [0xde, 0xad]);
+        assert_eq!(value_bytes(&json!("3q0=")).unwrap(), [0xde, 0xad]);
+        assert!(value_bytes(&json!([256])).is_none());
+        assert!(value_bytes(&json!([-1])).is_none());
+        assert!(value_bytes(&json!(1)).is_none());
+    }
+}
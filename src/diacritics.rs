@@ -0,0 +1,76 @@
+/// Precomposed letters and the base letter they decompose to, covering the
+/// Latin-1 Supplement and Latin Extended-A blocks
+const LETTERS: &[(&str, char)] = &[
+    ("ÀÁÂÃÄÅĀĂĄ", 'A'),
+    ("àáâãäåāăą", 'a'),
+    ("ÇĆĈĊČ", 'C'),
+    ("çćĉċč", 'c'),
+    ("ĎĐ", 'D'),
+    ("ďđ", 'd'),
+    ("ÈÉÊËĒĔĖĘĚ", 'E'),
+    ("èéêëēĕėęě", 'e'),
+    ("ĜĞĠĢ", 'G'),
+    ("ĝğġģ", 'g'),
+    ("ĤĦ", 'H'),
+    ("ĥħ", 'h'),
+    ("ÌÍÎÏĨĪĬĮİ", 'I'),
+    ("ìíîïĩīĭįı", 'i'),
+    ("Ĵ", 'J'),
+    ("ĵ", 'j'),
+    ("Ķ", 'K'),
+    ("ķ", 'k'),
+    ("ĹĻĽĿŁ", 'L'),
+    ("ĺļľŀł", 'l'),
+    ("ÑŃŅŇ", 'N'),
+    ("ñńņňŉ", 'n'),
+    ("ÒÓÔÕÖØŌŎŐ", 'O'),
+    ("òóôõöøōŏő", 'o'),
+    ("ŔŖŘ", 'R'),
+    ("ŕŗř", 'r'),
+    ("ŚŜŞŠ", 'S'),
+    ("śŝşš", 's'),
+    ("ŢŤŦ", 'T'),
+    ("ţťŧ", 't'),
+    ("ÙÚÛÜŨŪŬŮŰŲ", 'U'),
+    ("ùúûüũūŭůűų", 'u'),
+    ("Ŵ", 'W'),
+    ("ŵ", 'w'),
+    ("ÝŶŸ", 'Y'),
+    ("ýÿŷ", 'y'),
+    ("ŹŻŽ", 'Z'),
+    ("źżž", 'z'),
+];
+
+/// Strips the diacritics from Latin letters, so `"José"` becomes `"Jose"`.
+/// Combining marks are dropped and precomposed letters are replaced by their
+/// base letter, while every other character is kept as is.
+pub(crate) fn strip_diacritics(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().filter(|c| !is_combining_mark(*c)).map(|c| {
+        if c.is_ascii() {
+            return c;
+        }
+        LETTERS
+            .iter()
+            .find(|(letters, _)| letters.contains(c))
+            .map_or(c, |(_, base)| *base)
+    })
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}' | '\u{FE20}'..='\u{FE2F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_diacritics;
+
+    #[test]
+    fn test_strip_diacritics() {
+        let strip_diacritics = |s| strip_diacritics(s).collect::<String>();
+        assert_eq!(strip_diacritics("José"), "Jose");
+        assert_eq!(strip_diacritics("Jose\u{301}"), "Jose");
+        assert_eq!(strip_diacritics("Łódź, Ærøskøbing"), "Lodz, Æroskobing");
+        assert_eq!(strip_diacritics("São Paulo ÿ"), "Sao Paulo y");
+        assert_eq!(strip_diacritics("東京"), "東京");
+    }
+}
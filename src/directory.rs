@@ -0,0 +1,281 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use handlebars::{Handlebars, TemplateError};
+
+use crate::config::SwitchConfig;
+use crate::validate::{validate_template, SwitchIssue};
+
+/// Template Problem
+///
+/// One of the problems [`TemplateDirectory::register`] found.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TemplateProblem {
+    /// A file or directory could not be read.
+    Io {
+        /// The path that could not be read.
+        path: PathBuf,
+        /// Why it could not be read.
+        error: io::Error,
+    },
+    /// A template is not valid UTF-8.
+    Utf8 {
+        /// The name of the template.
+        name: String,
+    },
+    /// A template failed to compile.
+    Template(TemplateError),
+    /// A template compiled but misuses the switch helper family.
+    Switch(SwitchIssue),
+}
+
+impl fmt::Display for TemplateProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateProblem::Io { path, error } => write!(f, "cannot read {path:?}: {error}"),
+            TemplateProblem::Utf8 { name } => write!(f, "template {name:?} is not UTF-8"),
+            TemplateProblem::Template(e) => e.fmt(f),
+            TemplateProblem::Switch(issue) => issue.fmt(f),
+        }
+    }
+}
+
+/// Directory Error
+///
+/// Every problem [`TemplateDirectory::register`] found: those loading the
+/// templates first, then the switch issues of the templates it loaded.
+#[derive(Debug)]
+pub struct DirectoryError {
+    /// The problems found.
+    pub problems: Vec<TemplateProblem>,
+}
+
+impl fmt::Display for DirectoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "found {} template problem(s)", self.problems.len())?;
+        (self.problems)
+            .iter()
+            .try_for_each(|problem| write!(f, "\n- {problem}"))
+    }
+}
+
+impl Error for DirectoryError {}
+
+/// Template Directory
+///
+/// Loads every template of a directory, like the `register_templates_directory`
+/// method of `Handlebars`, along with the switch helper family configured by
+/// a [`SwitchConfig`], and validates the switch structure of every template
+/// it loaded. Unlike registering the files one by one, it keeps going past
+/// unreadable files and templates that fail to compile, so a startup check
+/// reports every problem of the directory at once rather than at the first
+/// render that reaches it.
+///
+/// Templates are named after their path relative to the directory, with `/`
+/// between directories and the extension removed, so `mail/welcome.hbs` is
+/// registered as `mail/welcome`. Files and directories whose name starts with
+/// `.` or `#`, as editors leave behind, are skipped unless enabled with
+/// [`TemplateDirectory::with_hidden`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{SwitchConfig, TemplateDirectory};
+///
+/// let mut handlebars = Handlebars::new();
+/// let config = SwitchConfig::default();
+/// if let Err(e) = TemplateDirectory::new("templates").register(&mut handlebars, &config) {
+///     panic!("{e}");
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TemplateDirectory {
+    dir: PathBuf,
+    extension: String,
+    hidden: bool,
+}
+
+impl TemplateDirectory {
+    /// Creates a loader for the `.hbs` files of `dir` and its subdirectories.
+    pub fn new(dir: impl Into<PathBuf>) -> TemplateDirectory {
+        TemplateDirectory {
+            dir: dir.into(),
+            extension: ".hbs".to_owned(),
+            hidden: false,
+        }
+    }
+
+    /// Loads the files ending with `extension`, such as `".html.hbs"`,
+    /// instead of `.hbs` files.
+    pub fn with_extension(self, extension: &str) -> Self {
+        TemplateDirectory {
+            extension: extension.to_owned(),
+            ..self
+        }
+    }
+
+    /// Also loads files and directories whose name starts with `.` or `#`.
+    pub fn with_hidden(self, hidden: bool) -> Self {
+        TemplateDirectory { hidden, ..self }
+    }
+
+    /// Registers the switch helper family configured by `config` and every
+    /// template of the directory with `handlebars`, returning the names of
+    /// the templates registered, or every problem found. Nothing is
+    /// registered unless the whole directory passes, so a failed startup
+    /// leaves `handlebars` as it was.
+    pub fn register(
+        &self,
+        handlebars: &mut Handlebars,
+        config: &SwitchConfig,
+    ) -> Result<Vec<String>, DirectoryError> {
+        // Register into a copy that only replaces `handlebars` once
+        // everything passed
+        let mut staged = handlebars.clone();
+        config.register(&mut staged);
+
+        let mut files = Vec::new();
+        let mut problems = Vec::new();
+        self.collect(&self.dir, "", &mut files, &mut problems);
+        files.sort();
+
+        let mut names = Vec::new();
+        for (name, path) in files {
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(error) => {
+                    problems.push(TemplateProblem::Io { path, error });
+                    continue;
+                }
+            };
+            let Ok(source) = String::from_utf8(contents) else {
+                problems.push(TemplateProblem::Utf8 { name });
+                continue;
+            };
+            match staged.register_template_string(&name, source) {
+                Ok(()) => names.push(name),
+                Err(e) => problems.push(TemplateProblem::Template(e)),
+            }
+        }
+
+        let issues = names
+            .iter()
+            .filter_map(|name| staged.get_template(name))
+            .flat_map(validate_template);
+        problems.extend(issues.map(TemplateProblem::Switch));
+        if !problems.is_empty() {
+            return Err(DirectoryError { problems });
+        }
+        *handlebars = staged;
+        Ok(names)
+    }
+
+    /// Collects the `(name, path)` of the templates below `dir`
+    fn collect(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        files: &mut Vec<(String, PathBuf)>,
+        problems: &mut Vec<TemplateProblem>,
+    ) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                let path = dir.to_owned();
+                return problems.push(TemplateProblem::Io { path, error });
+            }
+        };
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let path = dir.to_owned();
+                    problems.push(TemplateProblem::Io { path, error });
+                    continue;
+                }
+            };
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if !self.hidden && (file_name.starts_with('.') || file_name.starts_with('#')) {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                self.collect(&path, &format!("{prefix}{file_name}/"), files, problems);
+            } else if let Some(stem) = file_name.strip_suffix(&self.extension) {
+                files.push((format!("{prefix}{stem}"), path));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TemplateDirectory, TemplateProblem};
+    use crate::{SwitchConfig, SwitchHelper};
+    use handlebars::Handlebars;
+    use std::fs;
+
+    #[test]
+    fn test_template_directory() {
+        let dir = std::env::temp_dir().join(format!("handlebars_switch_{}", std::process::id()));
+        let files: [(&str, &[u8]); 7] = [
+            ("a.hbs", b"{{#switch x}}{{#case 1}}one{{/case}}{{/switch}}"),
+            ("mail/b.hbs", b"{{> a}}!"),
+            ("mail/.c.hbs", b"{{#case 1}}{{/case}}"),
+            ("notes.txt", b"{{#if}}"),
+            ("broken.hbs", b"{{#if}}"),
+            ("stray.hbs", b"{{#default}}{{/default}}"),
+            ("bin.hbs", &[0xff]),
+        ];
+        for (name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let mut handlebars = Handlebars::new();
+        let config = SwitchConfig::new(SwitchHelper::new().with_case_insensitive(true));
+        let e = TemplateDirectory::new(&dir)
+            .register(&mut handlebars, &config)
+            .unwrap_err();
+        assert_eq!(e.problems.len(), 3);
+        assert!(matches!(&e.problems[0], TemplateProblem::Utf8 { name } if name == "bin"));
+        assert!(matches!(e.problems[1], TemplateProblem::Template(_)));
+        assert!(matches!(e.problems[2], TemplateProblem::Switch(_)));
+        assert!(e.to_string().starts_with("found 3 template problem(s)\n- "));
+        // nothing is registered unless the whole directory passes
+        assert!(handlebars.get_templates().is_empty());
+        let switch = handlebars.render_template("{{#switch 1}}{{/switch}}", &json!({}));
+        assert!(switch.is_err());
+
+        fs::remove_file(dir.join("broken.hbs")).unwrap();
+        fs::remove_file(dir.join("stray.hbs")).unwrap();
+        fs::remove_file(dir.join("bin.hbs")).unwrap();
+        let names = TemplateDirectory::new(&dir)
+            .with_hidden(true)
+            .register(&mut Handlebars::new(), &config);
+        assert_eq!(names.unwrap_err().problems.len(), 1);
+        let names = TemplateDirectory::new(&dir).register(&mut handlebars, &config);
+        assert_eq!(names.unwrap(), ["a", "mail/b"]);
+        assert_eq!(
+            handlebars.render("mail/b", &json!({"x": 1})).unwrap(),
+            "one!"
+        );
+        let tpl = "{{#switch x}}{{#case \"on\"}}yes{{/case}}{{/switch}}";
+        let rendered = handlebars.render_template(tpl, &json!({"x": "ON"}));
+        assert_eq!(rendered.unwrap(), "yes");
+
+        let missing =
+            TemplateDirectory::new(dir.join("missing")).register(&mut Handlebars::new(), &config);
+        assert!(matches!(
+            missing.unwrap_err().problems[..],
+            [TemplateProblem::Io { .. }]
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
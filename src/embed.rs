@@ -0,0 +1,246 @@
+use std::error::Error;
+use std::fmt;
+
+use handlebars::{Handlebars, TemplateError};
+
+use crate::config::SwitchConfig;
+use crate::validate::{validate_template, SwitchIssue};
+
+/// Registration Error
+///
+/// Why [`register_embedded`] refused a set of templates.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RegistrationError {
+    /// A template is not valid UTF-8.
+    Utf8 {
+        /// The name of the template.
+        name: String,
+    },
+    /// A template failed to compile.
+    Template(TemplateError),
+    /// Templates compiled but misuse the switch helper family.
+    Switch(Vec<SwitchIssue>),
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistrationError::Utf8 { name } => write!(f, "template {name:?} is not UTF-8"),
+            RegistrationError::Template(e) => e.fmt(f),
+            RegistrationError::Switch(issues) => {
+                write!(f, "found {} switch issue(s)", issues.len())?;
+                issues.iter().try_for_each(|issue| write!(f, "\n- {issue}"))
+            }
+        }
+    }
+}
+
+impl Error for RegistrationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RegistrationError::Template(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TemplateError> for RegistrationError {
+    fn from(e: TemplateError) -> RegistrationError {
+        RegistrationError::Template(e)
+    }
+}
+
+/// Register Embedded
+///
+/// Registers the switch helper family configured by `config` (see
+/// [`SwitchConfig::register`]) along with a set of `(name, contents)`
+/// templates in one step, such as the files embedded into the binary with
+/// `include_bytes!` or an embedding crate like `rust-embed` (see
+/// [`register_rust_embed`] with the `rust_embed` feature). Every template is
+/// compiled and its switch structure validated first, and nothing is
+/// registered unless all of them pass, so a failed startup leaves
+/// `handlebars` as it was. All switch issues are reported together so they
+/// can be fixed in one pass.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{register_embedded, RegistrationError, SwitchConfig};
+///
+/// let mut handlebars = Handlebars::new();
+/// let templates = [
+///     ("ok.hbs", "{{#switch a}}{{#case 1}}one{{/case}}{{/switch}}"),
+///     ("broken.hbs", "{{#default}}stray{{/default}}"),
+/// ];
+///
+/// match register_embedded(&mut handlebars, &SwitchConfig::default(), templates) {
+///     Err(RegistrationError::Switch(issues)) => assert_eq!(issues.len(), 1),
+///     _ => unreachable!(),
+/// }
+/// assert!(handlebars.get_template("ok.hbs").is_none());
+/// ```
+pub fn register_embedded<I, N, C>(
+    handlebars: &mut Handlebars,
+    config: &SwitchConfig,
+    templates: I,
+) -> Result<(), RegistrationError>
+where
+    I: IntoIterator<Item = (N, C)>,
+    N: AsRef<str>,
+    C: AsRef<[u8]>,
+{
+    // Register into a copy that only replaces `handlebars` once everything
+    // passed, compiling with the settings of `handlebars`
+    let mut staged = handlebars.clone();
+    config.register(&mut staged);
+
+    let mut names = Vec::new();
+    for (name, contents) in templates {
+        let name = name.as_ref();
+        let source =
+            std::str::from_utf8(contents.as_ref()).map_err(|_| RegistrationError::Utf8 {
+                name: name.to_owned(),
+            })?;
+        staged.register_template_string(name, source)?;
+        names.push(name.to_owned());
+    }
+
+    let issues: Vec<_> = names
+        .iter()
+        .filter_map(|name| staged.get_template(name))
+        .flat_map(validate_template)
+        .collect();
+    if !issues.is_empty() {
+        return Err(RegistrationError::Switch(issues));
+    }
+    *handlebars = staged;
+    Ok(())
+}
+
+/// Register Rust Embed
+///
+/// Registers the files of the `rust-embed` folder `E` ending with
+/// `extension`, such as `".hbs"`, like [`register_embedded`] does, named
+/// after their path in the folder without the extension, so
+/// `mail/welcome.hbs` is registered as `mail/welcome`. Other files are left
+/// out.
+///
+/// Requires the `rust_embed` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # extern crate rust_embed;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{register_rust_embed, SwitchConfig};
+/// use rust_embed::RustEmbed;
+///
+/// #[derive(RustEmbed)]
+/// #[folder = "tests/templates/"]
+/// struct Templates;
+///
+/// let mut handlebars = Handlebars::new();
+/// register_rust_embed::<Templates>(&mut handlebars, &SwitchConfig::default(), ".hbs").unwrap();
+/// assert_eq!(handlebars.render("badge", &json!({"role": "admin"})).unwrap(), "A");
+/// # }
+/// ```
+#[cfg(feature = "rust_embed")]
+pub fn register_rust_embed<E: rust_embed::RustEmbed>(
+    handlebars: &mut Handlebars,
+    config: &SwitchConfig,
+    extension: &str,
+) -> Result<(), RegistrationError> {
+    let templates = E::iter().filter_map(|path| {
+        let name = path.strip_suffix(extension)?.to_owned();
+        Some((name, E::get(&path)?.data))
+    });
+    register_embedded(handlebars, config, templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{register_embedded, RegistrationError};
+    use crate::{SwitchConfig, SwitchHelper};
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_register_embedded() {
+        let mut handlebars = Handlebars::new();
+        let config = SwitchConfig::default();
+        let templates = [(
+            "a",
+            "{{#switch x}}{{#case 1}}one{{/case}}{{/switch}}".as_bytes(),
+        )];
+
+        assert!(register_embedded(&mut handlebars, &config, templates).is_ok());
+        assert_eq!(handlebars.render("a", &json!({"x": 1})).unwrap(), "one");
+    }
+
+    #[test]
+    fn test_register_embedded_errors() {
+        let mut handlebars = Handlebars::new();
+        let config = SwitchConfig::default();
+
+        let r0 = register_embedded(&mut handlebars, &config, [("bin", &[0xff_u8][..])]);
+        assert!(matches!(r0, Err(RegistrationError::Utf8 { name }) if name == "bin"));
+
+        let r1 = register_embedded(&mut handlebars, &config, [("bad", "{{#if}}")]);
+        assert!(matches!(r1, Err(RegistrationError::Template(_))));
+
+        let templates = [
+            ("a", "{{#case 1}}{{/case}}"),
+            ("b", "{{#switch}}{{/switch}}"),
+        ];
+        let r2 = register_embedded(&mut handlebars, &config, templates).unwrap_err();
+        assert!(r2
+            .to_string()
+            .starts_with("found 2 switch issue(s)\n- `#case`"));
+
+        // nothing is registered unless every template passes
+        let templates = [("ok", "{{#switch x}}{{/switch}}"), ("bad", "{{#if}}")];
+        assert!(register_embedded(&mut handlebars, &config, templates).is_err());
+        assert!(handlebars.get_templates().is_empty());
+        let switch = handlebars.render_template("{{#switch 1}}{{/switch}}", &json!({}));
+        assert!(switch.is_err());
+    }
+
+    #[test]
+    fn test_register_embedded_with_config() {
+        let mut handlebars = Handlebars::new();
+        let config = SwitchConfig::new(SwitchHelper::new().with_case_insensitive(true));
+        let templates = [(
+            "badge",
+            "{{#switch role}}{{#case \"admin\"}}A{{/case}}{{#default}}U{{/default}}{{/switch}}",
+        )];
+
+        assert!(register_embedded(&mut handlebars, &config, templates).is_ok());
+        let rendered = handlebars.render("badge", &json!({"role": "ADMIN"}));
+        assert_eq!(rendered.unwrap(), "A");
+    }
+
+    #[cfg(feature = "rust_embed")]
+    #[test]
+    fn test_register_rust_embed() {
+        use super::register_rust_embed;
+
+        #[derive(rust_embed::RustEmbed)]
+        #[folder = "tests/templates/"]
+        struct Templates;
+
+        let mut handlebars = Handlebars::new();
+        let config = SwitchConfig::new(SwitchHelper::new().with_case_insensitive(true));
+        assert!(register_rust_embed::<Templates>(&mut handlebars, &config, ".hbs").is_ok());
+
+        let mut names: Vec<_> = handlebars.get_templates().keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, ["badge", "mail/welcome"]);
+        let rendered = handlebars.render("mail/welcome", &json!({"role": "ADMIN"}));
+        assert_eq!(rendered.unwrap(), "A!");
+    }
+}
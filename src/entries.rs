@@ -0,0 +1,135 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::block::child_block;
+use crate::config::SwitchFamily;
+use crate::switch::SwitchHelper;
+use crate::vars::{restore_vars, saved_vars};
+
+/// Switch Entries Helper
+///
+/// Provides the `{{#switch_entries}}` helper to a Handlebars template. It
+/// renders its block once for every entry of an object, behaving like a
+/// `{{#switch}}` on the entry's value with `@key`, `@index`, and `this` set
+/// as in `{{#each}}`. Any other value renders nothing.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchEntriesHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch_entries", Box::new(SwitchEntriesHelper));
+///
+/// let tpl = "\
+///     {{#switch_entries settings}}\
+///         {{#case true}}{{@key}}: on; {{/case}}\
+///         {{#case false}}{{@key}}: off; {{/case}}\
+///         {{#default}}{{@key}}: {{this}}; {{/default}}\
+///     {{/switch_entries}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars
+///         .render_template(tpl, &json!({"settings": {"cache": true, "ttl": 60}}))
+///         .unwrap(),
+///     "cache: on; ttl: 60; "
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SwitchEntriesHelper;
+
+impl SwitchFamily for SwitchEntriesHelper {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let param = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("switch_entries", 0))?;
+        let Value::Object(entries) = param.value() else {
+            return Ok(());
+        };
+
+        for (index, (key, value)) in entries.iter().enumerate() {
+            let mut block = child_block(rc);
+            block.set_base_value(value.clone());
+            block.set_local_var("key", json!(key));
+            block.set_local_var("index", json!(index));
+            block.set_local_var("first", json!(index == 0));
+            block.set_local_var("last", json!(index + 1 == entries.len()));
+
+            rc.push_block(block);
+            let result = switch.render_switch(Cow::Borrowed(value), h, r, ctx, rc, out);
+            let vars = saved_vars(rc);
+            rc.pop_block();
+            restore_vars(rc, vars);
+            result?;
+        }
+        Ok(())
+    }
+}
+
+impl HelperDef for SwitchEntriesHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_with(&SwitchHelper::new(), h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchEntriesHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_switch_entries() {
+        let tpl = "\
+            {{#switch_entries config}}\
+                {{#case \"\"}}{{@index}}.{{@key}} unset{{/case}}\
+                {{#case 0}}{{@index}}.{{@key}} zero{{/case}}\
+                {{#default}}{{@index}}.{{@key}}={{this}}{{/default}}\
+                {{#unless @last}}, {{/unless}}\
+            {{/switch_entries}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch_entries", Box::new(SwitchEntriesHelper));
+
+        let data = json!({"config": {"host": "", "port": 8080, "retries": 0}});
+        assert_eq!(
+            handlebars.render_template(tpl, &data).unwrap(),
+            "0.host unset, 1.port=8080, 2.retries zero"
+        );
+        assert_eq!(
+            handlebars
+                .render_template(tpl, &json!({"config": [1]}))
+                .unwrap(),
+            ""
+        );
+        assert!(handlebars
+            .render_template("{{#switch_entries}}{{/switch_entries}}", &json!({}))
+            .is_err());
+    }
+}
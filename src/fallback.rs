@@ -0,0 +1,31 @@
+use handlebars::{Output, RenderError, StringOutput};
+
+/// Arm Error Policy
+///
+/// What a `{{#switch}}` does when the body of its matched `{{#case}}` fails
+/// to render, for instance because of a missing partial or a failing helper.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArmErrorPolicy {
+    /// Fail the render.
+    #[default]
+    Propagate,
+    /// Discard the output of the failed arm, log the error as a warning, and
+    /// render a `{{#default}}` that comes after the arm instead.
+    FallBackToDefault,
+}
+
+/// Renders an arm into a buffer, so nothing of it is written when it fails.
+/// Returns the error of a failed arm, which the caller should recover from.
+pub(crate) fn render_buffered(
+    out: &mut dyn Output,
+    render: impl FnOnce(&mut dyn Output) -> Result<(), RenderError>,
+) -> Result<Option<RenderError>, RenderError> {
+    let mut buffer = StringOutput::new();
+    match render(&mut buffer) {
+        Ok(()) => {
+            out.write(&buffer.into_string()?)?;
+            Ok(None)
+        }
+        Err(err) => Ok(Some(err)),
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+/// Compares a `{{#case}}` field against a switch value field
+type Compare = dyn Fn(&Value, &Value) -> bool + Send + Sync;
+
+/// Field Comparator
+///
+/// Decides whether a field of a `{{#case}}` value equals the same field of
+/// the switch value, in place of the comparison options of the helper.
+/// Comparators are registered for a field with
+/// [`SwitchHelper::with_field_comparator`], for whole values with
+/// [`SwitchHelper::with_comparator`], or for whole values under a name that a
+/// `{{#switch}}` picks with [`SwitchHelper::with_named_comparator`].
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{FieldComparator, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(
+///         SwitchHelper::new()
+///             .with_field_comparator("/version", FieldComparator::semver())
+///             .with_field_comparator("/email", FieldComparator::ignore_case()),
+///     ),
+/// );
+///
+/// let tpl = "\
+///     {{#switch client}}\
+///         {{#case known}}Known{{/case}}\
+///         {{#default}}Unknown{{/default}}\
+///     {{/switch}}\
+/// ";
+/// let data = json!({
+///     "client": {"email": "Jo@Example.com", "version": "v2.1"},
+///     "known": {"email": "jo@example.com", "version": "2.1.0"},
+/// });
+///
+/// assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "Known");
+/// # }
+/// ```
+///
+/// [`SwitchHelper::with_field_comparator`]: crate::SwitchHelper::with_field_comparator
+/// [`SwitchHelper::with_comparator`]: crate::SwitchHelper::with_comparator
+/// [`SwitchHelper::with_named_comparator`]: crate::SwitchHelper::with_named_comparator
+#[derive(Clone)]
+pub struct FieldComparator(Arc<Compare>);
+
+impl FieldComparator {
+    /// Creates a comparator from a function of the `{{#case}}` field and the
+    /// switch value field.
+    pub fn new<F>(f: F) -> FieldComparator
+    where
+        F: Fn(&Value, &Value) -> bool + Send + Sync + 'static,
+    {
+        FieldComparator(Arc::new(f))
+    }
+
+    /// Creates a comparator of strings regardless of case, so
+    /// `"Jo@Example.com"` equals `"jo@example.com"`. Other values are equal
+    /// when they are the same.
+    pub fn ignore_case() -> FieldComparator {
+        FieldComparator::new(|a, b| match (a, b) {
+            (Value::String(a), Value::String(b)) => a.to_lowercase() == b.to_lowercase(),
+            (a, b) => a == b,
+        })
+    }
+
+    /// Creates a comparator of strings as semantic versions, so `"v2.1"`
+    /// equals `"2.1.0"`. A missing minor or patch version counts as `0`,
+    /// and build metadata like `+build.5` is ignored. Values that are not
+    /// versions are equal when they are the same.
+    pub fn semver() -> FieldComparator {
+        FieldComparator::new(|a, b| match (a, b) {
+            (Value::String(x), Value::String(y)) => match (parse_semver(x), parse_semver(y)) {
+                (Some(x), Some(y)) => x == y,
+                _ => a == b,
+            },
+            (a, b) => a == b,
+        })
+    }
+
+    pub(crate) fn eq(&self, a: &Value, b: &Value) -> bool {
+        (self.0)(a, b)
+    }
+}
+
+impl fmt::Debug for FieldComparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FieldComparator")
+    }
+}
+
+/// Reads a version like `v1.2.3-rc.1+build.5` into its numbers and its
+/// pre-release identifiers
+fn parse_semver(s: &str) -> Option<([u64; 3], &str)> {
+    let s = s.trim();
+    let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+    let s = s.split_once('+').map_or(s, |(version, _)| version);
+    let (core, pre) = s.split_once('-').unwrap_or((s, ""));
+    let mut numbers = [0; 3];
+    let mut parts = core.split('.');
+    for (i, part) in parts.by_ref().take(3).enumerate() {
+        // leading zeros are not allowed, as in `01.2.3`
+        if part.is_empty() || (part.len() > 1 && part.starts_with('0')) {
+            return None;
+        }
+        if !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        numbers[i] = part.parse().ok()?;
+    }
+    (parts.next().is_none()).then_some((numbers, pre))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_semver;
+
+    #[test]
+    fn test_parse_semver() {
+        assert_eq!(parse_semver("1.2.3"), Some(([1, 2, 3], "")));
+        assert_eq!(parse_semver("v2"), Some(([2, 0, 0], "")));
+        assert_eq!(
+            parse_semver("1.0.0-rc.1+build.5"),
+            Some(([1, 0, 0], "rc.1"))
+        );
+        assert_eq!(parse_semver("1.2.3.4"), None);
+        assert_eq!(parse_semver("01.2"), None);
+        assert_eq!(parse_semver("1..2"), None);
+        assert_eq!(parse_semver("latest"), None);
+    }
+}
@@ -0,0 +1,118 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, JsonTruthy, Output, RenderContext,
+    RenderErrorReason,
+};
+
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::config::SwitchFamily;
+use crate::switch::SwitchHelper;
+
+/// Switch Flags Helper
+///
+/// Provides the `{{#switch_flags}}` helper to a Handlebars template. It
+/// builds a truth table key from any number of flags, writing `1` for every
+/// truthy flag and `0` for every falsy one in the order given, and then
+/// behaves like `{{#switch}}` on that key.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchFlagsHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch_flags", Box::new(SwitchFlagsHelper));
+///
+/// let tpl = "\
+///     {{#switch_flags is_admin is_owner}}\
+///         {{#case \"11\"}}Admin and owner{{/case}}\
+///         {{#case \"10\"}}Admin{{/case}}\
+///         {{#case \"01\"}}Owner{{/case}}\
+///         {{#default}}Guest{{/default}}\
+///     {{/switch_flags}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars
+///         .render_template(tpl, &json!({"is_admin": true, "is_owner": false}))
+///         .unwrap(),
+///     "Admin"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SwitchFlagsHelper;
+
+impl SwitchFamily for SwitchFlagsHelper {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if h.params().is_empty() {
+            return Err(RenderErrorReason::ParamNotFoundForIndex("switch_flags", 0).into());
+        }
+
+        let key = h
+            .params()
+            .iter()
+            .map(|x| if x.value().is_truthy(false) { '1' } else { '0' })
+            .collect();
+
+        switch.render_switch(Cow::Owned(Value::String(key)), h, r, ctx, rc, out)
+    }
+}
+
+impl HelperDef for SwitchFlagsHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_with(&SwitchHelper::new(), h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchFlagsHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_switch_flags() {
+        let tpl = "\
+            {{#switch_flags a b c}}\
+                {{#case \"000\"}}none{{/case}}\
+                {{#case \"101\" \"011\"}}two{{/case}}\
+                {{#default}}other{{/default}}\
+            {{/switch_flags}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch_flags", Box::new(SwitchFlagsHelper));
+
+        let r0 = handlebars.render_template(tpl, &json!({"a": 0, "b": "", "c": null}));
+        assert_eq!(r0.unwrap(), "none");
+        let r1 = handlebars.render_template(tpl, &json!({"a": 1, "c": [1]}));
+        assert_eq!(r1.unwrap(), "two");
+        let r2 = handlebars.render_template(tpl, &json!({"a": true, "b": true, "c": true}));
+        assert_eq!(r2.unwrap(), "other");
+
+        assert!(handlebars
+            .render_template("{{#switch_flags}}{{/switch_flags}}", &json!({}))
+            .is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use handlebars::{
+    Context, Handlebars, RenderContext, RenderError, RenderErrorReason, Renderable, StringOutput,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Arm Selector
+///
+/// Picks the arm of a named `{{#switch ... name="..."}}` that
+/// [`render_forcing_case`] renders regardless of the value switched on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArmSelector {
+    /// The first `{{#case}}` listing `value` in the switch named `switch`.
+    Case {
+        /// The `name="..."` of the switch.
+        switch: String,
+        /// A value the `{{#case}}` lists, as written in the template.
+        value: Value,
+    },
+    /// The `{{#default}}` of the switch named `switch`.
+    Default {
+        /// The `name="..."` of the switch.
+        switch: String,
+    },
+}
+
+impl ArmSelector {
+    /// Selects the first `{{#case}}` listing `value` in the switch named
+    /// `switch`.
+    pub fn case(switch: &str, value: impl Into<Value>) -> ArmSelector {
+        ArmSelector::Case {
+            switch: switch.to_owned(),
+            value: value.into(),
+        }
+    }
+
+    /// Selects the `{{#default}}` of the switch named `switch`.
+    pub fn default_arm(switch: &str) -> ArmSelector {
+        ArmSelector::Default {
+            switch: switch.to_owned(),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            ArmSelector::Case { switch, value } => json!({"switch": switch, "case": value}),
+            ArmSelector::Default { switch } => json!({"switch": switch}),
+        }
+    }
+}
+
+/// The arm a render forces in the current switch.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ForcedArm {
+    /// The first `{{#case}}` listing the value.
+    Case(Value),
+    /// The `{{#default}}`, by letting no `{{#case}}` match.
+    Default,
+}
+
+impl ForcedArm {
+    /// Finds the arm forced in the switch named `name`, if any.
+    pub(crate) fn for_switch(rc: &RenderContext, name: Option<&str>) -> Option<ForcedArm> {
+        let forced = rc.block()?.get_local_var("force_arm")?;
+        if forced.get("switch")?.as_str() != Some(name?) {
+            return None;
+        }
+        Some(match forced.get("case") {
+            Some(value) => ForcedArm::Case(value.clone()),
+            None => ForcedArm::Default,
+        })
+    }
+}
+
+/// Render Forcing Case
+///
+/// Renders a registered template like `Handlebars::render` while forcing the
+/// arm picked by `arm` to render in place of whichever arm the data matches,
+/// so rarely reached arms such as error states can be exercised in visual
+/// regression tests without crafting data that reaches them. The other arms
+/// of the switch do not match, and switches without the selected name render
+/// as usual.
+///
+/// The selection is set on the root scope and carried into the blocks of the
+/// switch helper family, like the `@runtime` of
+/// [`render_with_runtime`](crate::render_with_runtime), so switches inside
+/// blocks that start a fresh scope for `@` variables, such as `{{#each}}`,
+/// are not forced.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{render_forcing_case, ArmSelector, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars
+///     .register_template_string(
+///         "status",
+///         "{{#switch status name=\"status\"}}{{#case \"failed\"}}Error!{{/case}}{{#default}}OK{{/default}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// let data = json!({"status": "sent"});
+/// assert_eq!(
+///     render_forcing_case(&handlebars, "status", &data, &ArmSelector::case("status", "failed"))
+///         .unwrap(),
+///     "Error!"
+/// );
+/// # }
+/// ```
+pub fn render_forcing_case<T>(
+    handlebars: &Handlebars,
+    name: &str,
+    data: &T,
+    arm: &ArmSelector,
+) -> Result<String, RenderError>
+where
+    T: Serialize,
+{
+    let template = handlebars
+        .get_template(name)
+        .ok_or_else(|| RenderErrorReason::TemplateNotFound(name.to_owned()))?;
+    let ctx = Context::wraps(data)?;
+
+    let mut rc = RenderContext::new(template.name.as_ref());
+    rc.set_recursive_lookup(handlebars.recursive_lookup());
+    if let Some(block) = rc.block_mut() {
+        block.set_local_var("force_arm", arm.to_value());
+    }
+
+    let mut out = StringOutput::new();
+    template.render(handlebars, &ctx, &mut rc, &mut out)?;
+    Ok(out.into_string()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_forcing_case, ArmSelector};
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_render_forcing_case() {
+        let tpl = "\
+            {{#switch status name=\"status\"}}\
+                {{#case \"ok\"}}OK{{/case}}\
+                {{#case \"failed\" \"timeout\"}}\
+                    Error {{#switch code name=\"code\"}}{{#case 1}}1{{/case}}{{#default}}?{{/default}}{{/switch}}\
+                {{/case}}\
+                {{#default}}Unknown{{/default}}\
+            {{/switch}}\
+            {{#switch status}}{{#case \"ok\"}} fine{{/case}}{{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let data = json!({"status": "ok", "code": 1});
+        let render = |arm| render_forcing_case(&handlebars, "tpl", &data, &arm).unwrap();
+        assert_eq!(
+            render(ArmSelector::case("status", "timeout")),
+            "Error 1 fine"
+        );
+        assert_eq!(render(ArmSelector::default_arm("status")), "Unknown fine");
+        assert_eq!(render(ArmSelector::default_arm("code")), "OK fine");
+        assert_eq!(
+            render(ArmSelector::case("status", "missing")),
+            "Unknown fine"
+        );
+        assert_eq!(render(ArmSelector::case("other", "x")), "OK fine");
+
+        let arm = ArmSelector::default_arm("status");
+        assert!(render_forcing_case(&handlebars, "missing", &data, &arm).is_err());
+    }
+}
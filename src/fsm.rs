@@ -0,0 +1,167 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::config::SwitchFamily;
+use crate::switch::SwitchHelper;
+
+/// FSM Helper
+///
+/// Provides the `{{#fsm}}` helper to a Handlebars template. It is configured
+/// with the transitions a state machine allows and, given the `from=` and
+/// `to=` states of a transition, behaves like `{{#switch}}` on the `to`
+/// state. A transition that was not allowed fails the render instead, so
+/// impossible states never reach the output.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::FsmHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "fsm",
+///     Box::new(FsmHelper::new().with_transitions([
+///         ("pending", "paid"),
+///         ("paid", "shipped"),
+///         ("pending", "cancelled"),
+///     ])),
+/// );
+///
+/// let tpl = "\
+///     {{#fsm from=order.previous to=order.status}}\
+///         {{#case \"paid\"}}Thanks for your payment{{/case}}\
+///         {{#case \"shipped\"}}Your order is on its way{{/case}}\
+///         {{#default}}Your order was updated{{/default}}\
+///     {{/fsm}}\
+/// ";
+///
+/// let order = json!({"order": {"previous": "paid", "status": "shipped"}});
+/// assert_eq!(handlebars.render_template(tpl, &order).unwrap(), "Your order is on its way");
+///
+/// let order = json!({"order": {"previous": "cancelled", "status": "shipped"}});
+/// assert!(handlebars.render_template(tpl, &order).is_err());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FsmHelper {
+    transitions: Vec<(Value, Value)>,
+}
+
+impl FsmHelper {
+    /// Creates an FSM helper that allows no transitions yet.
+    pub fn new() -> FsmHelper {
+        FsmHelper::default()
+    }
+
+    /// Allows the given `(from, to)` transitions. A state moving to itself
+    /// is only allowed when listed as well.
+    pub fn with_transitions<I, F, T>(mut self, transitions: I) -> FsmHelper
+    where
+        I: IntoIterator<Item = (F, T)>,
+        F: Into<Value>,
+        T: Into<Value>,
+    {
+        self.transitions.extend(
+            transitions
+                .into_iter()
+                .map(|(from, to)| (from.into(), to.into())),
+        );
+        self
+    }
+
+    fn allows(&self, from: &Value, to: &Value) -> bool {
+        self.transitions
+            .iter()
+            .any(|(allowed_from, allowed_to)| allowed_from == from && allowed_to == to)
+    }
+}
+
+impl SwitchFamily for FsmHelper {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        // Read in the states of the transition
+        let from = h
+            .hash_get("from")
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForName("fsm", "from".to_owned()))?
+            .value();
+        let to = h
+            .hash_get("to")
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForName("fsm", "to".to_owned()))?
+            .value();
+
+        if !self.allows(from, to) {
+            return Err(RenderErrorReason::Other(format!(
+                "`{{{{#fsm}}}}` does not allow a transition from {from} to {to}"
+            ))
+            .into());
+        }
+
+        switch.render_switch(Cow::Borrowed(to), h, r, ctx, rc, out)
+    }
+}
+
+impl HelperDef for FsmHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_with(&SwitchHelper::new(), h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FsmHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_fsm() {
+        let tpl = "\
+            {{#fsm from=from to=to}}\
+                {{#case 2}}two{{/case}}\
+                {{#case \"done\"}}done{{/case}}\
+                {{#default}}other{{/default}}\
+            {{/fsm}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        let helper = FsmHelper::new()
+            .with_transitions([(json!(1), json!(2)), (json!(2), json!("done"))])
+            .with_transitions([(json!(null), json!(1))]);
+        handlebars.register_helper("fsm", Box::new(helper));
+
+        let render = |from, to| handlebars.render_template(tpl, &json!({"from": from, "to": to}));
+        assert_eq!(render(json!(1), json!(2)).unwrap(), "two");
+        assert_eq!(render(json!(2), json!("done")).unwrap(), "done");
+        assert_eq!(render(json!(null), json!(1)).unwrap(), "other");
+
+        let err = render(json!("done"), json!(2)).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not allow a transition from \"done\" to 2"));
+        assert!(render(json!(2), json!(2)).is_err());
+        assert!(handlebars
+            .render_template("{{#fsm to=1}}{{/fsm}}", &json!({}))
+            .is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use handlebars::template::{HelperTemplate, Parameter, TemplateElement};
+use handlebars::{Handlebars, Path, PathSeg, Template};
+use serde_json::{Map, Value};
+
+/// Golden Contexts
+///
+/// Enumerates the arms of every `{{#switch}}` in the template registered as
+/// `name` and synthesizes a minimal context rendering each one, returning
+/// them as `(name, context)` pairs to feed golden-file tests. A `{{#case}}`
+/// gets its first literal value at the path switched on, and a
+/// `{{#default}}` a value no literal of the block lists. Arms of nested
+/// switches also get the values reaching the arm they are nested in.
+///
+/// Only switches on a plain path like `order.status` outside of blocks that
+/// change the context, such as `{{#each}}`, are enumerated, and arms listing
+/// no literals are skipped. The names have the form
+/// `"template:line:column case value"` and stay the same between runs as
+/// long as the template does. Returns `None` when no template is registered
+/// as `name`.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::golden_contexts;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars
+///     .register_template_string(
+///         "badge",
+///         "{{#switch order.status}}{{#case \"paid\"}}Paid{{/case}}{{#default}}Due{{/default}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// let contexts = golden_contexts(&handlebars, "badge").unwrap();
+/// assert_eq!(
+///     contexts,
+///     [
+///         ("badge:1:1 case \"paid\"".to_owned(), json!({"order": {"status": "paid"}})),
+///         ("badge:1:1 default".to_owned(), json!({"order": {"status": null}})),
+///     ]
+/// );
+/// # }
+/// ```
+pub fn golden_contexts(registry: &Handlebars, name: &str) -> Option<Vec<(String, Value)>> {
+    let template = registry.get_template(name)?;
+    let mut contexts = Vec::new();
+    walk(name, template, &Value::Object(Map::new()), &mut contexts);
+    Some(contexts)
+}
+
+/// Collects the contexts of the switches in `template`, reached with `base`
+fn walk(name: &str, template: &Template, base: &Value, contexts: &mut Vec<(String, Value)>) {
+    for (index, element) in template.elements.iter().enumerate() {
+        let TemplateElement::HelperBlock(ht) = element else {
+            continue;
+        };
+        if ht.name.as_name() != Some("switch") {
+            continue;
+        }
+        let Some(segments) = ht.params.first().and_then(plain_path) else {
+            continue;
+        };
+        let (line, column) = template.mapping.get(index).map_or((0, 0), |m| (m.0, m.1));
+        let prefix = format!("{name}:{line}:{column}");
+        let arms: Vec<&HelperTemplate> = ht
+            .template
+            .iter()
+            .flat_map(|t| &t.elements)
+            .filter_map(|element| match element {
+                TemplateElement::HelperBlock(arm) => Some(&**arm),
+                _ => None,
+            })
+            .collect();
+        let literals: Vec<&Value> = arms
+            .iter()
+            .filter(|arm| arm.name.as_name() == Some("case"))
+            .flat_map(|arm| arm.params.iter().filter_map(literal))
+            .collect();
+
+        for arm in arms {
+            let (label, value) = match arm.name.as_name() {
+                Some("case") => match arm.params.iter().find_map(literal) {
+                    Some(value) => (format!("case {value}"), value.clone()),
+                    None => continue,
+                },
+                Some("default") => ("default".to_owned(), unlisted(&literals)),
+                _ => continue,
+            };
+            let mut context = base.clone();
+            set_path(&mut context, &segments, value);
+            contexts.push((format!("{prefix} {label}"), context.clone()));
+            if let Some(t) = &arm.template {
+                walk(name, t, &context, contexts);
+            }
+        }
+    }
+}
+
+/// The segments of a path from the root of the context, like `a.b`
+fn plain_path(param: &Parameter) -> Option<Vec<String>> {
+    let Parameter::Path(Path::Relative((segments, _))) = param else {
+        return None;
+    };
+    segments
+        .iter()
+        .map(|segment| match segment {
+            PathSeg::Named(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|segments| !segments.is_empty())
+}
+
+fn literal(param: &Parameter) -> Option<&Value> {
+    match param {
+        Parameter::Literal(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// A value that none of the `literals` is
+fn unlisted(literals: &[&Value]) -> Value {
+    let mut value = Value::Null;
+    let mut candidate = "default".to_owned();
+    while literals.contains(&&value) {
+        value = Value::String(candidate.clone());
+        candidate.push('_');
+    }
+    value
+}
+
+/// Sets the value at the path of `segments`, making objects on the way
+fn set_path(context: &mut Value, segments: &[String], value: Value) {
+    let mut current = context;
+    for segment in segments {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let Value::Object(map) = current else {
+            return;
+        };
+        current = map.entry(segment.clone()).or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::golden_contexts;
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_golden_contexts_render_each_arm() {
+        let tpl = "\
+            {{#switch kind}}\
+                {{#case \"a\" \"b\"}}\
+                    A{{#switch sub.level}}{{#case 1}}1{{/case}}{{#default}}D{{/default}}{{/switch}}\
+                {{/case}}\
+                {{#case null}}N{{/case}}\
+                {{#case other}}O{{/case}}\
+                {{#default}}X{{/default}}\
+            {{/switch}}\
+            {{#each items}}{{#switch this}}{{#case 1}}{{/case}}{{/switch}}{{/each}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_template_string("tpl", tpl).unwrap();
+
+        let contexts = golden_contexts(&handlebars, "tpl").unwrap();
+        let rendered: Vec<_> = contexts
+            .iter()
+            .map(|(name, context)| (name.as_str(), handlebars.render("tpl", context).unwrap()))
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                ("tpl:1:1 case \"a\"", "AD".to_owned()),
+                ("tpl:1:35 case 1", "A1".to_owned()),
+                ("tpl:1:35 default", "AD".to_owned()),
+                ("tpl:1:1 case null", "N".to_owned()),
+                ("tpl:1:1 default", "X".to_owned()),
+            ]
+        );
+        assert_eq!(contexts[1].1, json!({"kind": "a", "sub": {"level": 1}}));
+        assert!(golden_contexts(&handlebars, "missing").is_none());
+    }
+}
@@ -0,0 +1,95 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+/// Arm Guard Helper
+///
+/// Stands in for an arm helper like `{{#case}}` or `{{#default}}` outside of
+/// the block that provides it. Arm helpers only exist inside their parent
+/// block, so a stray arm otherwise fails with a generic "helper not found"
+/// error. Registered globally, this helper fails with an error naming the
+/// parent block instead, while the arms inside the parent keep working.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{ArmGuardHelper, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars.register_helper("case", Box::new(ArmGuardHelper::new("switch")));
+/// handlebars.register_helper("default", Box::new(ArmGuardHelper::new("switch")));
+///
+/// let error = handlebars
+///     .render_template("{{#case \"admin\"}}Admin{{/case}}", &json!({}))
+///     .unwrap_err();
+/// assert!(error.to_string().contains("`#case` must appear inside `#switch`"));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ArmGuardHelper {
+    parent: String,
+}
+
+impl ArmGuardHelper {
+    /// Creates a guard for arms of the `parent` block helper.
+    pub fn new(parent: &str) -> ArmGuardHelper {
+        ArmGuardHelper {
+            parent: parent.to_owned(),
+        }
+    }
+}
+
+impl HelperDef for ArmGuardHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        Err(RenderErrorReason::Other(format!(
+            "`#{}` must appear inside `#{}`",
+            h.name(),
+            self.parent
+        ))
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArmGuardHelper;
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_guard_only_outside_switch() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("case", Box::new(ArmGuardHelper::new("switch")));
+        handlebars.register_helper("default", Box::new(ArmGuardHelper::new("switch")));
+
+        let r0 = handlebars.render_template(tpl, &json!({"access": "admin"}));
+        assert_eq!(r0.unwrap(), "Admin");
+
+        let r1 = handlebars.render_template("{{#default}}User{{/default}}", &json!({}));
+        assert!(r1
+            .unwrap_err()
+            .to_string()
+            .contains("`#default` must appear inside `#switch`"));
+    }
+}
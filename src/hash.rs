@@ -0,0 +1,129 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Stable Hash
+///
+/// Hashes a switch value with 64-bit FNV-1a. Strings are hashed by their
+/// UTF-8 bytes and every other value by its compact JSON serialization, so
+/// `"42"` and `42` land on the same arm. The result does not depend on the
+/// platform, the process, or the crate version and is safe to persist.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate handlebars_switch;
+/// # fn main() {
+/// use handlebars_switch::stable_hash;
+///
+/// assert_eq!(stable_hash(&json!("")), 0xcbf2_9ce4_8422_2325);
+/// assert_eq!(stable_hash(&json!("a")), 0xaf63_dc4c_8601_ec8c);
+/// # }
+/// ```
+pub fn stable_hash(value: &Value) -> u64 {
+    match value {
+        Value::String(s) => fnv1a(s.as_bytes()),
+        other => fnv1a(other.to_string().as_bytes()),
+    }
+}
+
+/// Seeded Hash
+///
+/// Hashes a switch value like [`stable_hash`], after first feeding the
+/// little-endian bytes of the `seed`. Different seeds spread the same values
+/// differently, while a fixed seed stays as stable as [`stable_hash`].
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate handlebars_switch;
+/// # fn main() {
+/// use handlebars_switch::seeded_hash;
+///
+/// assert_eq!(seeded_hash(7, &json!("a")), seeded_hash(7, &json!("a")));
+/// assert_ne!(seeded_hash(7, &json!("a")), seeded_hash(8, &json!("a")));
+/// # }
+/// ```
+pub fn seeded_hash(seed: u64, value: &Value) -> u64 {
+    let hash = fnv1a_from(FNV_OFFSET_BASIS, &seed.to_le_bytes());
+    match value {
+        Value::String(s) => fnv1a_from(hash, s.as_bytes()),
+        other => fnv1a_from(hash, other.to_string().as_bytes()),
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_from(FNV_OFFSET_BASIS, bytes)
+}
+
+fn fnv1a_from(basis: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(basis, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Hashes a switch value with SHA-256, reading it like [`stable_hash`] does
+pub(crate) fn value_sha256(value: &Value) -> [u8; 32] {
+    match value {
+        Value::String(s) => sha256(s.as_bytes()),
+        other => sha256(other.to_string().as_bytes()),
+    }
+}
+
+/// Masks a switch value for diagnostics with a short digest of it, so
+/// repeats of a value can be told apart without revealing it
+pub(crate) fn redacted(value: &Value) -> Value {
+    let digest = value_sha256(value);
+    let hex: String = digest[..6].iter().map(|b| format!("{b:02x}")).collect();
+    Value::String(format!("<redacted {hex}>"))
+}
+
+/// Computes the SHA-256 digest of `bytes`
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{seeded_hash, sha256, stable_hash};
+
+    #[test]
+    fn test_stable_hash_is_fixed() {
+        assert_eq!(stable_hash(&json!("foobar")), 0x85944171f73967e8);
+        assert_eq!(stable_hash(&json!(42)), stable_hash(&json!("42")));
+        assert_ne!(stable_hash(&json!([1, 2])), stable_hash(&json!([2, 1])));
+    }
+
+    #[test]
+    fn test_seeded_hash_is_fixed() {
+        assert_eq!(seeded_hash(0, &json!("")), 0xa8c7_f832_281a_39c5);
+        assert_eq!(seeded_hash(3, &json!(42)), seeded_hash(3, &json!("42")));
+        assert_ne!(
+            seeded_hash(3, &json!("foobar")),
+            stable_hash(&json!("foobar"))
+        );
+    }
+
+    #[test]
+    fn test_sha256() {
+        let hex = |bytes: [u8; 32]| bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        assert_eq!(
+            hex(sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+}
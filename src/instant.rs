@@ -0,0 +1,54 @@
+use chrono::DateTime;
+
+/// An instant as seconds and nanoseconds since the Unix epoch
+type Instant = (i64, u32);
+
+/// Parses an RFC 3339 timestamp like `2024-01-01T01:00:00+01:00` into the
+/// instant it stands for. A space or lower case `t` may separate the date
+/// and time, as RFC 3339 allows.
+fn parse_rfc3339(s: &str) -> Option<Instant> {
+    let instant = DateTime::parse_from_rfc3339(s).ok()?;
+    Some((instant.timestamp(), instant.timestamp_subsec_nanos()))
+}
+
+/// Compares two strings as instants when both are RFC 3339 timestamps,
+/// returning `None` otherwise.
+pub(crate) fn same_instant(a: &str, b: &str) -> Option<bool> {
+    Some(parse_rfc3339(a)? == parse_rfc3339(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rfc3339, same_instant};
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some((0, 0)));
+        assert_eq!(
+            parse_rfc3339("2024-02-29T12:00:00.5Z"),
+            Some((1709208000, 500_000_000))
+        );
+        assert_eq!(
+            parse_rfc3339("1969-12-31t23:59:59.123456789123-00:00"),
+            Some((-1, 123_456_789))
+        );
+        assert_eq!(parse_rfc3339("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2024-01-01T00:00:00"), None);
+        assert_eq!(parse_rfc3339("2024-01-01T00:00:00+1:00"), None);
+        assert_eq!(parse_rfc3339("2024-01-01T24:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2024-01-01T00:00:00.Z"), None);
+        assert_eq!(parse_rfc3339("2024-01-01Té0:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_same_instant() {
+        let utc = "2024-01-01T00:00:00Z";
+        assert_eq!(same_instant(utc, "2024-01-01T01:00:00+01:00"), Some(true));
+        assert_eq!(same_instant(utc, "2023-12-31 19:00:00-05:00"), Some(true));
+        assert_eq!(
+            same_instant(utc, "2024-01-01T00:00:00.000000001Z"),
+            Some(false)
+        );
+        assert_eq!(same_instant(utc, "2024-01-01"), None);
+    }
+}
@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::fmt;
+
+use serde_json::Value;
+
+/// Unsupported Option
+///
+/// An option of a [`SwitchHelper`](crate::SwitchHelper) that
+/// [`SwitchHelper::to_javascript`](crate::SwitchHelper::to_javascript) cannot
+/// reproduce in JavaScript, naming the builder method that set it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedOption(pub &'static str);
+
+impl fmt::Display for UnsupportedOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` has no JavaScript equivalent", self.0)
+    }
+}
+
+impl Error for UnsupportedOption {}
+
+/// The JavaScript helpers, with `__CONFIG__` standing for the options of the
+/// switch helper as a JSON object
+const HELPERS: &str = r#"(function (Handlebars) {
+  "use strict";
+
+  var config = __CONFIG__;
+  var SWITCH_HASH = ["arrays", "ignore_case_style", "uuids", "ptr", "decode", "name", "memo", "budget_ms"];
+  var hasOwn = Object.prototype.hasOwnProperty;
+
+  function isObject(x) {
+    return x !== null && typeof x === "object" && !Array.isArray(x);
+  }
+
+  function parseRadix(s) {
+    var m = /^(-?)0([xXoObB])([0-9a-fA-F_]*)$/.exec(s);
+    if (!m || m[3] === "" || m[3][0] === "_") return null;
+    var radix = { x: 16, o: 8, b: 2 }[m[2].toLowerCase()];
+    var digits = m[3].replace(/_/g, "");
+    var valid = { 16: /^[0-9a-fA-F]+$/, 8: /^[0-7]+$/, 2: /^[01]+$/ }[radix];
+    if (!valid.test(digits)) return null;
+    var n = parseInt(digits, radix);
+    return m[1] ? -n : n;
+  }
+
+  function uuidDigits(s) {
+    s = s.trim();
+    if (s.indexOf("urn:uuid:") === 0) s = s.slice(9);
+    else if (s[0] === "{" && s[s.length - 1] === "}") s = s.slice(1, -1);
+    var hyphenated = s.length === 36 && s.split("").every(function (c, i) {
+      return (c === "-") === [8, 13, 18, 23].indexOf(i) >= 0;
+    });
+    if (!hyphenated && s.length !== 32) return null;
+    var digits = s.replace(/-/g, "");
+    return /^[0-9a-fA-F]{32}$/.test(digits) ? digits.toLowerCase() : null;
+  }
+
+  function fold(s, cmp) {
+    return cmp.ignore_case_style ? s.replace(/[_\-. ]/g, "").toLowerCase() : s;
+  }
+
+  function eq(a, b, cmp) {
+    if (a === undefined) a = null;
+    if (b === undefined) b = null;
+    if (cmp.uuids && typeof a === "string" && typeof b === "string") {
+      var x = uuidDigits(a), y = uuidDigits(b);
+      if (x !== null && y !== null) return x === y;
+    }
+    if (Array.isArray(a) && Array.isArray(b)) {
+      if (cmp.arrays === "set") {
+        return a.every(function (x) { return b.some(function (y) { return eq(x, y, cmp); }); }) &&
+          b.every(function (y) { return a.some(function (x) { return eq(x, y, cmp); }); });
+      }
+      return a.length === b.length && a.every(function (x, i) { return eq(x, b[i], cmp); });
+    }
+    if (isObject(a) && isObject(b)) {
+      var keys = Object.keys(a);
+      return keys.length === Object.keys(b).length && keys.every(function (k) {
+        return hasOwn.call(b, k) && eq(a[k], b[k], cmp);
+      });
+    }
+    if (typeof a === "string" && typeof b === "string") return fold(a, cmp) === fold(b, cmp);
+    if (typeof a === "number" && typeof b === "string") return Number.isInteger(a) && parseRadix(b) === a;
+    if (typeof a === "string" && typeof b === "number") return eq(b, a, cmp);
+    return a === b;
+  }
+
+  function pointer(value, ptr) {
+    if (typeof ptr !== "string") throw new Error("`{{#switch}}` expects `ptr` to be a string");
+    if (ptr === "") return value;
+    if (ptr[0] !== "/") return null;
+    var tokens = ptr.slice(1).split("/");
+    for (var i = 0; i < tokens.length; i++) {
+      var token = tokens[i].replace(/~1/g, "/").replace(/~0/g, "~");
+      if (Array.isArray(value) && /^(0|[1-9][0-9]*)$/.test(token) && +token < value.length) {
+        value = value[+token];
+      } else if (isObject(value) && hasOwn.call(value, token)) {
+        value = value[token];
+      } else {
+        return null;
+      }
+    }
+    return value === undefined ? null : value;
+  }
+
+  function urlBytes(s) {
+    var bytes = [], literal = "";
+    function flush() {
+      bytes.push.apply(bytes, Array.from(new TextEncoder().encode(literal)));
+      literal = "";
+    }
+    for (var i = 0; i < s.length; i++) {
+      if (s[i] === "%") {
+        var hex = s.slice(i + 1, i + 3);
+        if (!/^[0-9a-fA-F]{2}$/.test(hex)) return null;
+        flush();
+        bytes.push(parseInt(hex, 16));
+        i += 2;
+      } else if (s[i] === "+") {
+        flush();
+        bytes.push(32);
+      } else {
+        literal += s[i];
+      }
+    }
+    flush();
+    return bytes;
+  }
+
+  function base64Bytes(s) {
+    s = s.replace(/=+$/, "");
+    var alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    var bytes = [], buffer = 0, bits = 0;
+    for (var i = 0; i < s.length; i++) {
+      var sextet = alphabet.indexOf(s[i]);
+      if (s[i] === "+" || s[i] === "-") sextet = 62;
+      if (s[i] === "/" || s[i] === "_") sextet = 63;
+      if (sextet < 0) return null;
+      buffer = (buffer << 6) | sextet;
+      bits += 6;
+      if (bits >= 8) {
+        bits -= 8;
+        bytes.push((buffer >> bits) & 0xff);
+        buffer &= (1 << bits) - 1;
+      }
+    }
+    return bits < 6 ? bytes : null;
+  }
+
+  function decode(value, decoder) {
+    var toBytes = { url: urlBytes, base64: base64Bytes }[decoder];
+    if (!toBytes) throw new Error("`{{#switch}}` expects `decode` to be \"url\" or \"base64\"");
+    if (typeof value !== "string") return value;
+    var bytes = toBytes(value);
+    if (bytes === null) return null;
+    try {
+      return new TextDecoder("utf-8", { fatal: true }).decode(new Uint8Array(bytes));
+    } catch (e) {
+      return null;
+    }
+  }
+
+  function option(hash, name, fallback, valid, expected) {
+    if (!hasOwn.call(hash, name)) return fallback;
+    if (!valid(hash[name])) throw new Error("`{{#switch}}` expects `" + name + "` to be " + expected);
+    return hash[name];
+  }
+
+  function isBool(x) {
+    return typeof x === "boolean";
+  }
+
+  Handlebars.registerHelper("switch", function (value, options) {
+    if (arguments.length < 2) throw new Error("`{{#switch}}` requires a value");
+    var hash = options.hash || {};
+    Object.keys(hash).forEach(function (key) {
+      if (SWITCH_HASH.indexOf(key) < 0) throw new Error("`{{#switch}}` option `" + key + "` is not supported in JavaScript");
+    });
+    if (hasOwn.call(hash, "ptr")) value = pointer(value, hash.ptr);
+    if (hasOwn.call(hash, "decode")) value = decode(value, hash.decode);
+    if (typeof value === "string" && hasOwn.call(config.aliases, value)) value = config.aliases[value];
+
+    var cmp = {
+      arrays: option(hash, "arrays", config.arrays, function (x) { return x === "positional" || x === "set"; }, "\"positional\" or \"set\""),
+      ignore_case_style: option(hash, "ignore_case_style", config.ignore_case_style, isBool, "a boolean"),
+      uuids: option(hash, "uuids", config.uuids, isBool, "a boolean")
+    };
+    var data = Handlebars.createFrame(options.data || {});
+    data._switch = { value: value === undefined ? null : value, cmp: cmp, matched: false, frame: data };
+    data.switch_break = false;
+    return options.fn(this, { data: data });
+  });
+
+  Handlebars.registerHelper("case", function () {
+    var options = arguments[arguments.length - 1];
+    var values = Array.prototype.slice.call(arguments, 0, -1);
+    var state = options.data && options.data._switch;
+    if (!state) throw new Error("`{{#case}}` must appear inside `{{#switch}}`");
+    var hash = options.hash || {};
+    Object.keys(hash).forEach(function (key) {
+      if (key !== "not" && key !== "label") throw new Error("`{{#case}}` option `" + key + "` is not supported in JavaScript");
+    });
+    var found = values.some(function (x) { return eq(x, state.value, state.cmp); });
+    if (!state.matched && found !== (hash.not === true)) {
+      state.matched = true;
+      state.frame.switch_break = true;
+      return options.fn(this);
+    }
+    return options.inverse(this);
+  });
+
+  Handlebars.registerHelper("default", function (options) {
+    var state = options.data && options.data._switch;
+    if (!state) throw new Error("`{{#default}}` must appear inside `{{#switch}}`");
+    if (state.matched) return "";
+    state.matched = true;
+    return options.fn(this);
+  });
+})(Handlebars);
+"#;
+
+/// Fills the options of a switch helper into the JavaScript helpers
+pub(crate) fn generate(config: &Value) -> String {
+    HELPERS.replace("__CONFIG__", &config.to_string())
+}
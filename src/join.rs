@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use handlebars::{Helper, RenderError, RenderErrorReason};
+
+/// Collects the output of the arms of a `{{#switch}}` given `join=", "`, to
+/// write them separated by the separator once the block has rendered. The
+/// `last_join=" and "` hash parameter separates the last two arms instead.
+#[derive(Clone, Debug)]
+pub(crate) struct Joiner {
+    separator: String,
+    last: Option<String>,
+    parts: Arc<Mutex<Vec<String>>>,
+}
+
+impl Joiner {
+    /// Starts joining the arms of a `{{#switch}}`, if it asks for it.
+    pub(crate) fn from_hash(h: &Helper) -> Result<Option<Joiner>, RenderError> {
+        let Some(separator) = h.hash_get("join") else {
+            return Ok(None);
+        };
+        let string = |name: &str, value: &serde_json::Value| {
+            value.as_str().map(str::to_owned).ok_or_else(|| {
+                RenderErrorReason::HashTypeMismatchForName(
+                    "switch",
+                    name.to_owned(),
+                    "string".to_owned(),
+                )
+            })
+        };
+        Ok(Some(Joiner {
+            separator: string("join", separator.value())?,
+            last: h
+                .hash_get("last_join")
+                .map(|x| string("last_join", x.value()))
+                .transpose()?,
+            parts: Arc::default(),
+        }))
+    }
+
+    /// Adds the output of an arm.
+    pub(crate) fn push(&self, rendered: String) {
+        self.parts().push(rendered);
+    }
+
+    /// Joins the output of every arm added so far.
+    pub(crate) fn finish(&self) -> String {
+        let parts = self.parts();
+        match (parts.split_last(), &self.last) {
+            (Some((last, rest)), Some(last_separator)) if !rest.is_empty() => {
+                format!("{}{last_separator}{last}", rest.join(&self.separator))
+            }
+            _ => parts.join(&self.separator),
+        }
+    }
+
+    fn parts(&self) -> MutexGuard<'_, Vec<String>> {
+        // a panic while holding the lock cannot leave a half-written part
+        self.parts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Joiner;
+
+    #[test]
+    fn test_finish() {
+        let joiner = |parts: &[&str], last: Option<&str>| Joiner {
+            separator: ", ".to_owned(),
+            last: last.map(str::to_owned),
+            parts: std::sync::Arc::new(
+                parts
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+        };
+        assert_eq!(joiner(&["a", "b", "c"], None).finish(), "a, b, c");
+        assert_eq!(
+            joiner(&["a", "b", "c"], Some(" and ")).finish(),
+            "a, b and c"
+        );
+        assert_eq!(joiner(&["a"], Some(" and ")).finish(), "a");
+        assert_eq!(joiner(&[], Some(" and ")).finish(), "");
+    }
+}
@@ -20,7 +20,7 @@
 //! use handlebars_switch::SwitchHelper;
 //!
 //! let mut handlebars = Handlebars::new();
-//! handlebars.register_helper("switch", Box::new(SwitchHelper));
+//! handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
 //! ```
 //!
 //! ### Example
@@ -39,7 +39,7 @@
 //!
 //! fn main() {
 //!   let mut handlebars = Handlebars::new();
-//!   handlebars.register_helper("switch", Box::new(SwitchHelper));
+//!   handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
 //!
 //!   let tpl = "\
 //!       {{#switch access}}\
@@ -59,11 +59,143 @@
 //!   );
 //! }
 //! ```
+//!
+//! ## Untrusted Templates
+//!
+//! The helpers report malformed blocks, bad hash parameters, and switch values
+//! nested too deeply to compare as render errors rather than panicking, so
+//! they are safe to use when rendering untrusted templates. Patterns given to
+//! `regex=` are compiled by the `regex` crate, limited in size and nesting,
+//! and match in time linear in the value, so no pattern can exhaust the
+//! stack or stall a render. The one
+//! exception is [`SwitchHelper::with_debug_assert_match`], which panics by
+//! design.
 
 extern crate handlebars;
+extern crate serde;
 #[macro_use]
 extern crate serde_json;
 
+pub use self::adaptive::AdaptiveOrder;
+pub use self::audit::{AuditLog, AuditSink, SwitchObservation};
+pub use self::batch::render_batch;
+#[cfg(feature = "rayon")]
+pub use self::batch::render_batch_parallel;
+pub use self::bins::BinsHelper;
+pub use self::cmp::CmpHelper;
+pub use self::compare::{ArrayEquality, CompareLimits, LimitExceeded};
+pub use self::compat::CompatMode;
+pub use self::config::SwitchConfig;
+pub use self::conformance::{
+    check_conformance, conformance_fixtures, ArmSpec, CaseMatcher, ConformanceFailure,
+    ConformanceFixture,
+};
+pub use self::containers::ContainerPolicy;
+pub use self::directory::{DirectoryError, TemplateDirectory, TemplateProblem};
+#[cfg(feature = "rust_embed")]
+pub use self::embed::register_rust_embed;
+pub use self::embed::{register_embedded, RegistrationError};
+pub use self::entries::SwitchEntriesHelper;
+pub use self::fallback::ArmErrorPolicy;
+pub use self::fields::FieldComparator;
+pub use self::flags::SwitchFlagsHelper;
+pub use self::forcing::{render_forcing_case, ArmSelector};
+pub use self::fsm::FsmHelper;
+pub use self::golden::golden_contexts;
+pub use self::guard::ArmGuardHelper;
+pub use self::hash::{seeded_hash, stable_hash};
+pub use self::javascript::UnsupportedOption;
+pub use self::literals::{ArrHelper, ObjHelper};
+pub use self::memo::MemoCache;
+pub use self::migrate::{eq_chains_to_switches, switches_to_if_chains, TranspileError};
+#[cfg(feature = "unicode")]
+pub use self::normalize::Nfc;
+pub use self::normalize::{AliasMap, Lowercase, Normalize, Pipeline, Trim};
+pub use self::option::SwitchSomeHelper;
+pub use self::ordinal::SwitchOrdinalHelper;
+pub use self::outline::{outline_switches, ArmOutline, SwitchOutline};
+pub use self::overrides::{ArmOverrides, OverridesError};
+pub use self::register::register;
+#[cfg(feature = "misc_helpers")]
+pub use self::register::register_with_misc_helpers;
+pub use self::result::SwitchResultHelper;
+pub use self::rewrite::expand_inverse_cases;
+pub use self::runtime::render_with_runtime;
+pub use self::sentinel::Sentinel;
+pub use self::slots::{SlotHelper, Slots};
+pub use self::source::{ArmSource, CachedArmSource, SourcedArm};
 pub use self::switch::SwitchHelper;
+#[cfg(feature = "testing")]
+pub use self::testing::{arm_contexts, arm_strategies};
+pub use self::trace::{ArmKind, ArmTrace, RenderTracer, SwitchTrace};
+pub use self::validate::{
+    validate_registry, validate_registry_with, validate_template, validate_template_with,
+    SwitchIssue, SwitchIssueKind, ValidateOptions,
+};
+pub use self::vars::{CaptureHelper, SetHelper};
 
+mod adaptive;
+mod audit;
+mod batch;
+mod bins;
+mod block;
+mod budget;
+mod buffers;
+mod cmp;
+mod collation;
+mod color;
+mod compare;
+mod compat;
+mod config;
+mod conformance;
+mod containers;
+#[cfg(feature = "currency")]
+mod currency;
+mod decode;
+#[cfg(feature = "unicode")]
+mod diacritics;
+mod directory;
+mod embed;
+mod entries;
+mod fallback;
+mod fields;
+mod flags;
+mod forcing;
+mod fsm;
+mod golden;
+mod guard;
+mod hash;
+#[cfg(feature = "instants")]
+mod instant;
+mod javascript;
+mod join;
+mod literals;
+mod matchers;
+mod maxlen;
+mod memo;
+mod migrate;
+mod normalize;
+mod option;
+mod ordinal;
+mod outline;
+mod overrides;
+#[cfg(feature = "phones")]
+mod phone;
+mod postprocess;
+mod raw;
+mod regex;
+mod register;
+mod result;
+mod rewrite;
+mod runtime;
+mod sentinel;
+mod shadow;
+mod slots;
+mod source;
 mod switch;
+#[cfg(feature = "testing")]
+mod testing;
+mod trace;
+mod units;
+mod validate;
+mod vars;
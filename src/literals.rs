@@ -0,0 +1,144 @@
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde_json::Value;
+
+/// Array Helper
+///
+/// Provides the `(arr ...)` subexpression, which builds an array out of its
+/// params. Handlebars has no array literal syntax, so this lets a template
+/// spell out a static set for a `{{#case}}` to match, either as a whole
+/// value or through `in=`, without adding it to the context.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{ArrHelper, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars.register_helper("arr", Box::new(ArrHelper));
+///
+/// let tpl = "\
+///     {{#switch size}}\
+///         {{#case (arr 1 2)}}pair{{/case}}\
+///         {{#case in=(arr \"s\" \"m\")}}small{{/case}}\
+///         {{#default}}large{{/default}}\
+///     {{/switch}}\
+/// ";
+///
+/// assert_eq!(handlebars.render_template(tpl, &json!({"size": [1, 2]})).unwrap(), "pair");
+/// assert_eq!(handlebars.render_template(tpl, &json!({"size": "m"})).unwrap(), "small");
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ArrHelper;
+
+impl HelperDef for ArrHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let items = h.params().iter().map(|x| x.value().clone()).collect();
+        Ok(ScopedJson::Derived(Value::Array(items)))
+    }
+}
+
+/// Object Helper
+///
+/// Provides the `(obj ...)` subexpression, which builds an object out of its
+/// hash parameters, so a `{{#case}}` can spell out a structured value to
+/// compare the switch value against inline.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{ObjHelper, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars.register_helper("obj", Box::new(ObjHelper));
+///
+/// let tpl = "\
+///     {{#switch user}}\
+///         {{#case (obj role=\"admin\" active=true)}}Admin{{/case}}\
+///         {{#default}}User{{/default}}\
+///     {{/switch}}\
+/// ";
+///
+/// let user = json!({"role": "admin", "active": true});
+/// assert_eq!(handlebars.render_template(tpl, &json!({ "user": user })).unwrap(), "Admin");
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ObjHelper;
+
+impl HelperDef for ObjHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let fields = h
+            .hash()
+            .iter()
+            .map(|(key, x)| ((*key).to_owned(), x.value().clone()))
+            .collect();
+        Ok(ScopedJson::Derived(Value::Object(fields)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArrHelper, ObjHelper};
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_arr() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+        handlebars.register_helper("json", Box::new(json_helper));
+
+        let render = |tpl| {
+            handlebars
+                .render_template(tpl, &json!({"x": {"y": 1}}))
+                .unwrap()
+        };
+        assert_eq!(render("{{{json (arr)}}}"), "[]");
+        assert_eq!(
+            render("{{{json (arr 1 \"a\" x null)}}}"),
+            "[1,\"a\",{\"y\":1},null]"
+        );
+        assert_eq!(render("{{{json (arr (arr 1) true)}}}"), "[[1],true]");
+    }
+
+    #[test]
+    fn test_obj() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+        handlebars.register_helper("obj", Box::new(ObjHelper));
+        handlebars.register_helper("json", Box::new(json_helper));
+
+        let render = |tpl| handlebars.render_template(tpl, &json!({"x": 1})).unwrap();
+        assert_eq!(render("{{{json (obj)}}}"), "{}");
+        assert_eq!(
+            render("{{{json (obj b=x a=(arr 1) c=(obj d=null))}}}"),
+            "{\"a\":[1],\"b\":1,\"c\":{\"d\":null}}"
+        );
+    }
+
+    handlebars::handlebars_helper!(json_helper: |v: Json| v.to_string());
+}
@@ -0,0 +1,478 @@
+use std::ops::{Bound, RangeBounds};
+use std::sync::OnceLock;
+
+use handlebars::{Helper, RenderError, RenderErrorReason};
+use serde_json::Value;
+
+use crate::decode::{hex_decode, value_bytes};
+use crate::hash::value_sha256;
+use crate::regex::RegexCache;
+
+/// The `{{#case}}` hash parameters that match on a part of the switch value
+/// rather than on the whole of it
+const MATCHERS: &[&str] = &[
+    "email_domain",
+    "scheme",
+    "host",
+    "port",
+    "path",
+    "path_prefix",
+];
+
+/// The components of a URL
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Url {
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+    path: String,
+}
+
+/// The parts of a switch value that matchers look at, parsed at most once
+/// per `{{#switch}}` however many arms use them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ValueParts {
+    url: OnceLock<Option<Url>>,
+    sha256: OnceLock<[u8; 32]>,
+}
+
+impl ValueParts {
+    fn url(&self, value: &Value) -> Option<&Url> {
+        self.url
+            .get_or_init(|| value.as_str().and_then(parse_url))
+            .as_ref()
+    }
+
+    fn sha256(&self, value: &Value) -> &[u8; 32] {
+        self.sha256.get_or_init(|| value_sha256(value))
+    }
+}
+
+/// Checks the matcher hash parameters of a `{{#case}}` against the switch
+/// value, returning `None` when the arm has none. Every matcher given must
+/// match for the arm to match.
+pub(crate) fn hash_matches(
+    h: &Helper,
+    value: &Value,
+    parts: &ValueParts,
+    regexes: &RegexCache,
+) -> Result<Option<bool>, RenderError> {
+    let mut matched = None;
+    for name in MATCHERS {
+        let Some(pattern) = h.hash_get(name) else {
+            continue;
+        };
+        let pattern = pattern.value();
+        let is_match = match (*name, pattern) {
+            ("port", Value::Number(port)) => parts
+                .url(value)
+                .is_some_and(|url| url.port.map(u64::from) == port.as_u64()),
+            (name, Value::String(pattern)) if name != "port" => {
+                let url = || parts.url(value);
+                match name {
+                    "email_domain" => {
+                        email_domain(value).is_some_and(|x| domain_matches(pattern, &x))
+                    }
+                    "scheme" => url().is_some_and(|x| x.scheme.eq_ignore_ascii_case(pattern)),
+                    "host" => url().is_some_and(|x| domain_matches(pattern, &x.host)),
+                    "path" => url().is_some_and(|x| x.path == *pattern),
+                    "path_prefix" => url().is_some_and(|x| x.path.starts_with(pattern.as_str())),
+                    _ => false,
+                }
+            }
+            _ => {
+                let expected = if *name == "port" { "number" } else { "string" };
+                return Err(RenderErrorReason::HashTypeMismatchForName(
+                    "case",
+                    (*name).to_owned(),
+                    expected.to_owned(),
+                )
+                .into());
+            }
+        };
+        matched = Some(matched.unwrap_or(true) && is_match);
+    }
+    if let Some(hex) = h.hash_get("hex") {
+        let bytes = hex.value().as_str().and_then(hex_decode).ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "case",
+                "hex".to_owned(),
+                "hex string".to_owned(),
+            )
+        })?;
+        let is_match = value_bytes(value).is_some_and(|x| x == bytes);
+        matched = Some(matched.unwrap_or(true) && is_match);
+    }
+    if let Some(digest) = h.hash_get("sha256") {
+        let digest = (digest.value().as_str())
+            .and_then(hex_decode)
+            .filter(|x| x.len() == 32)
+            .ok_or_else(|| {
+                RenderErrorReason::HashTypeMismatchForName(
+                    "case",
+                    "sha256".to_owned(),
+                    "SHA-256 hex digest".to_owned(),
+                )
+            })?;
+        let is_match = parts.sha256(value)[..] == digest[..];
+        matched = Some(matched.unwrap_or(true) && is_match);
+    }
+    if let Some(range) = h.hash_get("range") {
+        let range = range
+            .value()
+            .as_str()
+            .and_then(parse_range)
+            .ok_or_else(|| {
+                RenderErrorReason::HashTypeMismatchForName(
+                    "case",
+                    "range".to_owned(),
+                    "range like \"18..65\"".to_owned(),
+                )
+            })?;
+        let is_match = value.as_f64().is_some_and(|x| range.contains(&x));
+        matched = Some(matched.unwrap_or(true) && is_match);
+    }
+    if let Some(pattern) = h.hash_get("regex") {
+        let pattern = pattern.value().as_str().ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "case",
+                "regex".to_owned(),
+                "string".to_owned(),
+            )
+        })?;
+        let regex = regexes.get(pattern).map_err(|e| {
+            RenderErrorReason::Other(format!(
+                "invalid `regex` {pattern:?} of `{{{{#case}}}}`: {e}"
+            ))
+        })?;
+        let is_match = value.as_str().is_some_and(|x| regex.is_match(x));
+        matched = Some(matched.unwrap_or(true) && is_match);
+    }
+    // `glob=true` makes the listed values patterns instead, see `CaseHelper`
+    match h.hash_get("glob").map(|x| x.value()) {
+        None | Some(Value::Bool(_)) => {}
+        Some(Value::String(glob)) => {
+            let is_match = value.as_str().is_some_and(|x| glob_matches(glob, x));
+            matched = Some(matched.unwrap_or(true) && is_match);
+        }
+        Some(_) => {
+            return Err(RenderErrorReason::HashTypeMismatchForName(
+                "case",
+                "glob".to_owned(),
+                "string or boolean".to_owned(),
+            )
+            .into())
+        }
+    }
+    #[cfg(feature = "phones")]
+    if let Some(code) = h.hash_get("country_code") {
+        let code = code.value().as_str().ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "case",
+                "country_code".to_owned(),
+                "string".to_owned(),
+            )
+        })?;
+        let is_match = value
+            .as_str()
+            .is_some_and(|number| crate::phone::has_country_code(number, code));
+        matched = Some(matched.unwrap_or(true) && is_match);
+    }
+    Ok(matched)
+}
+
+/// Matches a string against a shell-style wildcard pattern like `page-*`:
+/// `*` stands for any run of characters, `?` for any single one, and `[a-z]`
+/// or `[!a-z]` for one in or out of a set, while `\` makes the next character
+/// literal.
+///
+/// Walks the string and pattern together, going back only to the latest `*`
+/// on a mismatch, so it never recurses and never revisits more than one `*`.
+pub(crate) fn glob_matches(glob: &str, s: &str) -> bool {
+    let pattern = glob_tokens(glob);
+    let chars: Vec<char> = s.chars().collect();
+    let (mut p, mut i) = (0, 0);
+    // where the pattern resumes after the latest `*`, and where in `s` that
+    // `*` stopped
+    let mut star = None;
+    while i < chars.len() {
+        match pattern.get(p) {
+            Some(Glob::Star) => {
+                p += 1;
+                star = Some((p, i));
+            }
+            Some(token) if token.matches(chars[i]) => {
+                p += 1;
+                i += 1;
+            }
+            _ => match star {
+                // let the `*` take one more character and try again
+                Some((resume, stopped)) => {
+                    p = resume;
+                    i = stopped + 1;
+                    star = Some((resume, i));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|token| *token == Glob::Star)
+}
+
+/// A piece of a wildcard pattern
+#[derive(Debug, PartialEq)]
+enum Glob {
+    Char(char),
+    Any,
+    Star,
+    Set(Vec<(char, char)>, bool),
+}
+
+impl Glob {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Glob::Char(x) => *x == c,
+            Glob::Any => true,
+            Glob::Star => false,
+            Glob::Set(ranges, negated) => {
+                ranges.iter().any(|&(low, high)| low <= c && c <= high) != *negated
+            }
+        }
+    }
+}
+
+fn glob_tokens(glob: &str) -> Vec<Glob> {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let token = match chars[i] {
+            '*' => Glob::Star,
+            '?' => Glob::Any,
+            '\\' => {
+                i += 1;
+                Glob::Char(chars.get(i).copied().unwrap_or('\\'))
+            }
+            // the first `]` of a set is part of it, as in `[]a]`, and a `[`
+            // that is never closed is a literal
+            '[' => match chars.iter().skip(i + 2).position(|&c| c == ']') {
+                Some(end) => {
+                    let set = &chars[i + 1..i + 2 + end];
+                    i += 2 + end;
+                    glob_set(set)
+                }
+                None => Glob::Char('['),
+            },
+            c => Glob::Char(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    tokens
+}
+
+/// Reads the inside of `[...]`, where `a-z` is a range and a `-` at either
+/// end is a literal
+fn glob_set(set: &[char]) -> Glob {
+    let (set, negated) = match set.split_first() {
+        Some(('!', rest)) if !rest.is_empty() => (rest, true),
+        _ => (set, false),
+    };
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < set.len() {
+        if set.get(i + 1) == Some(&'-') && i + 2 < set.len() {
+            ranges.push((set[i], set[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((set[i], set[i]));
+            i += 1;
+        }
+    }
+    Glob::Set(ranges, negated)
+}
+
+/// Parses an absolute URL like `https://user@Example.com:8080/a/b?q#f`. The
+/// host is read in lower case and an empty path as `/`.
+fn parse_url(s: &str) -> Option<Url> {
+    let (scheme, rest) = s.trim().split_once("://")?;
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return None;
+    }
+
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, rest) = rest.split_at(authority_end);
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, x)| x);
+    let (host, port) = match host_port.rsplit_once(':') {
+        // the colons of an IPv6 address do not start a port
+        Some((host, port)) if !host.starts_with('[') || host.ends_with(']') => {
+            (host, Some(port.parse().ok()?))
+        }
+        _ => (host_port, None),
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let path_end = rest.find(['?', '#']).unwrap_or(rest.len());
+    let path = match &rest[..path_end] {
+        "" => "/",
+        path => path,
+    };
+    Some(Url {
+        scheme: scheme.to_ascii_lowercase(),
+        host: host.to_ascii_lowercase(),
+        port,
+        path: path.to_owned(),
+    })
+}
+
+/// Parses a range of numbers written like a Rust range: `18..65` up to but
+/// excluding its end, `18..=65` including it, and `18..` or `..65` open at
+/// one end.
+fn parse_range(s: &str) -> Option<(Bound<f64>, Bound<f64>)> {
+    let bound = |x: &str| -> Option<Option<f64>> {
+        match x.trim() {
+            "" => Some(None),
+            x => x.parse().ok().filter(|x: &f64| !x.is_nan()).map(Some),
+        }
+    };
+    let (start, end) = match s.split_once("..=") {
+        Some((start, end)) => (bound(start)?, Bound::Included(bound(end)??)),
+        None => {
+            let (start, end) = s.split_once("..")?;
+            let end = bound(end)?.map_or(Bound::Unbounded, Bound::Excluded);
+            (bound(start)?, end)
+        }
+    };
+    Some((start.map_or(Bound::Unbounded, Bound::Included), end))
+}
+
+/// Reads the lower case domain of an email address
+fn email_domain(value: &Value) -> Option<String> {
+    let (local, domain) = value.as_str()?.trim().rsplit_once('@')?;
+    (!local.is_empty() && !domain.is_empty()).then(|| domain.to_ascii_lowercase())
+}
+
+/// Matches a domain against a pattern like `example.com` or `*.example.com`,
+/// where the wildcard stands for one or more subdomain labels but not for
+/// the bare domain
+fn domain_matches(pattern: &str, domain: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(parent) => domain
+            .strip_suffix(parent)
+            .and_then(|x| x.strip_suffix('.'))
+            .is_some_and(|x| !x.is_empty()),
+        None => domain == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{domain_matches, email_domain, glob_matches, parse_range, parse_url, Url};
+    use std::ops::Bound::{Excluded, Included, Unbounded};
+
+    #[test]
+    fn test_email_domain() {
+        assert_eq!(
+            email_domain(&json!("Jo@Mail.Example.com")).as_deref(),
+            Some("mail.example.com")
+        );
+        assert_eq!(
+            email_domain(&json!("\"a@b\"@example.com")).as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(email_domain(&json!("@example.com")), None);
+        assert_eq!(email_domain(&json!("example.com")), None);
+        assert_eq!(email_domain(&json!(1)), None);
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let url = |scheme: &str, host: &str, port, path: &str| Url {
+            scheme: scheme.to_owned(),
+            host: host.to_owned(),
+            port,
+            path: path.to_owned(),
+        };
+        assert_eq!(
+            parse_url("HTTPS://jo:pw@Docs.Example.com:8443/api/v1?q=1#top"),
+            Some(url("https", "docs.example.com", Some(8443), "/api/v1"))
+        );
+        assert_eq!(
+            parse_url("http://example.com?q=/x"),
+            Some(url("http", "example.com", None, "/"))
+        );
+        assert_eq!(
+            parse_url("http://[::1]:80/"),
+            Some(url("http", "[::1]", Some(80), "/"))
+        );
+        assert_eq!(
+            parse_url("http://[::1]/"),
+            Some(url("http", "[::1]", None, "/"))
+        );
+        assert_eq!(parse_url("example.com/a"), None);
+        assert_eq!(parse_url("http://:80/"), None);
+        assert_eq!(parse_url("http://a:port/"), None);
+        assert_eq!(parse_url("1http://a/"), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("18..65"),
+            Some((Included(18.0), Excluded(65.0)))
+        );
+        assert_eq!(
+            parse_range("18..=65"),
+            Some((Included(18.0), Included(65.0)))
+        );
+        assert_eq!(parse_range(" -1.5 .. "), Some((Included(-1.5), Unbounded)));
+        assert_eq!(parse_range("..200"), Some((Unbounded, Excluded(200.0))));
+        assert_eq!(parse_range("1..="), None);
+        assert_eq!(parse_range("18-65"), None);
+        assert_eq!(parse_range("a..b"), None);
+        assert_eq!(parse_range("NaN..1"), None);
+    }
+
+    #[test]
+    fn test_domain_matches() {
+        assert!(domain_matches("*.example.com", "eu.example.com"));
+        assert!(domain_matches("*.Example.com", "a.b.example.com"));
+        assert!(!domain_matches("*.example.com", "example.com"));
+        assert!(!domain_matches("*.example.com", "badexample.com"));
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(!domain_matches("example.com", "eu.example.com"));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("page-*", "page-about"));
+        assert!(glob_matches("page-*", "page-"));
+        assert!(!glob_matches("page-*", "my-page-about"));
+        assert!(glob_matches("error.*", "error.not_found"));
+        assert!(!glob_matches("error.*", "errors"));
+        assert!(glob_matches("v?.[0-9]", "v2.5"));
+        assert!(!glob_matches("v?.[!0-9]", "v2.5"));
+        assert!(glob_matches("[]x]*", "]"));
+        assert!(glob_matches("[a-]", "-"));
+        assert!(glob_matches("a[b", "a[b"));
+        assert!(glob_matches("\\*\\?", "*?"));
+        assert!(!glob_matches("\\*", "x"));
+        assert!(glob_matches("multi*line", "multi\nline"));
+        assert!(glob_matches("(a|b)^$+{}", "(a|b)^$+{}"));
+        assert!(glob_matches("*a*b*", "xxaxxbxx"));
+        assert!(!glob_matches("*a*b", "xxaxxbxxa"));
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("", "a"));
+
+        let long = "a".repeat(10_000);
+        assert!(glob_matches("a*", &long));
+        assert!(!glob_matches("*a*a*a*b", &long[..5_000]));
+    }
+}
@@ -0,0 +1,80 @@
+use handlebars::{Helper, RenderError, RenderErrorReason};
+
+/// The number of characters an arm may render, as set by the `max_len=`
+/// hash parameter of the arm or of its `{{#switch}}`. Longer output fails
+/// the render, or is cut off when `truncate=true` is passed alongside.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct MaxLen {
+    limit: usize,
+    truncate: bool,
+}
+
+impl MaxLen {
+    /// Reads the cap of the `helper`, if it asks for one.
+    pub(crate) fn from_hash(
+        helper: &'static str,
+        h: &Helper,
+    ) -> Result<Option<MaxLen>, RenderError> {
+        let Some(limit) = h.hash_get("max_len") else {
+            return Ok(None);
+        };
+        let mismatch = |name: &str, expected: &str| {
+            RenderErrorReason::HashTypeMismatchForName(helper, name.to_owned(), expected.to_owned())
+        };
+        let limit = limit
+            .value()
+            .as_u64()
+            .ok_or_else(|| mismatch("max_len", "non-negative integer"))?;
+        let truncate = match h.hash_get("truncate") {
+            Some(x) => x
+                .value()
+                .as_bool()
+                .ok_or_else(|| mismatch("truncate", "boolean"))?,
+            None => false,
+        };
+        Ok(Some(MaxLen {
+            limit: usize::try_from(limit).unwrap_or(usize::MAX),
+            truncate,
+        }))
+    }
+
+    /// Checks the rendered output of an arm of the `helper` against the cap.
+    pub(crate) fn apply(&self, helper: &str, mut rendered: String) -> Result<String, RenderError> {
+        let Some((end, _)) = rendered.char_indices().nth(self.limit) else {
+            return Ok(rendered);
+        };
+        if self.truncate {
+            rendered.truncate(end);
+            return Ok(rendered);
+        }
+        Err(RenderErrorReason::Other(format!(
+            "`{{{{#{helper}}}}}` rendered {} characters, exceeding its max_len of {}",
+            rendered.chars().count(),
+            self.limit
+        ))
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MaxLen;
+
+    #[test]
+    fn test_apply() {
+        let cap = |limit, truncate| MaxLen { limit, truncate };
+        assert_eq!(
+            cap(4, false).apply("case", "café".to_owned()).unwrap(),
+            "café"
+        );
+        assert_eq!(
+            cap(3, true).apply("case", "café".to_owned()).unwrap(),
+            "caf"
+        );
+        assert_eq!(cap(0, true).apply("case", "café".to_owned()).unwrap(), "");
+        let err = cap(3, false).apply("case", "café".to_owned()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`{{#case}}` rendered 4 characters, exceeding its max_len of 3"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde_json::Value;
+
+use crate::trace::ArmKind;
+
+/// The number of renders a [`MemoCache::new`] cache holds.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Identifies a single `{{#switch}}` block rendered against a single value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct MemoKey {
+    pub(crate) template: String,
+    pub(crate) position: (usize, usize),
+    pub(crate) value: String,
+}
+
+/// Memo Cache
+///
+/// Stores the rendered output of `{{#switch ... memo=true}}` blocks keyed by
+/// the template, the position of the block within it, and the switch value.
+/// Only opt a switch into memoization when its arms depend on nothing but the
+/// switched value, since any other context is ignored on a cache hit. Blocks
+/// in templates without a name, like those passed to
+/// `Handlebars::render_template`, are never memoized, since nothing tells
+/// them apart. Neither are renders whose arms set `@` variables with
+/// `{{set}}`, `{{capture}}`, or `{{slot}}`, since a hit would not set them.
+///
+/// The cache holds a bounded number of renders, 1024 unless created with
+/// [`MemoCache::with_capacity`], and forgets the oldest one to make room for
+/// another.
+///
+/// The cache is cheap to clone and every clone shares the same entries, so
+/// keep a handle around to invalidate entries after templates change.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{MemoCache, SwitchHelper};
+///
+/// let memo = MemoCache::new();
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_memo(memo.clone())));
+/// handlebars
+///     .register_template_string(
+///         "badge",
+///         "{{#switch status memo=true}}{{#case \"ok\"}}<b>OK</b>{{/case}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// assert_eq!(handlebars.render("badge", &json!({"status": "ok"})).unwrap(), "<b>OK</b>");
+/// assert_eq!(memo.len(), 1);
+///
+/// memo.invalidate_template("badge");
+/// assert!(memo.is_empty());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MemoCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+/// A cached render, along with the kind of arm that produced it.
+pub(crate) type Memoized = (String, Option<ArmKind>);
+
+/// The cached renders, and their keys from oldest to newest
+#[derive(Debug)]
+struct Entries {
+    rendered: HashMap<MemoKey, Memoized>,
+    order: VecDeque<MemoKey>,
+    capacity: usize,
+}
+
+impl Default for MemoCache {
+    fn default() -> MemoCache {
+        MemoCache::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl MemoCache {
+    /// Creates an empty cache.
+    pub fn new() -> MemoCache {
+        MemoCache::default()
+    }
+
+    /// Creates an empty cache holding at most `capacity` renders, caching
+    /// nothing when `capacity` is zero.
+    pub fn with_capacity(capacity: usize) -> MemoCache {
+        MemoCache {
+            entries: Arc::new(Mutex::new(Entries {
+                rendered: HashMap::new(),
+                order: VecDeque::new(),
+                capacity,
+            })),
+        }
+    }
+
+    /// Returns the number of cached renders.
+    pub fn len(&self) -> usize {
+        self.entries().rendered.len()
+    }
+
+    /// Returns `true` if nothing has been cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries().rendered.is_empty()
+    }
+
+    /// Drops every cached render.
+    pub fn clear(&self) {
+        let mut entries = self.entries();
+        entries.rendered.clear();
+        entries.order.clear();
+    }
+
+    /// Drops the cached renders of every switch in the named template.
+    pub fn invalidate_template(&self, name: &str) {
+        self.entries().retain(|key| key.template != name);
+    }
+
+    /// Drops the cached renders of every switch for the given value.
+    pub fn invalidate_value(&self, value: &Value) {
+        let value = value.to_string();
+        self.entries().retain(|key| key.value != value);
+    }
+
+    pub(crate) fn get(&self, key: &MemoKey) -> Option<Memoized> {
+        self.entries().rendered.get(key).cloned()
+    }
+
+    pub(crate) fn insert(&self, key: MemoKey, rendered: Memoized) {
+        let mut entries = self.entries();
+        if entries.capacity == 0 {
+            return;
+        }
+        if !entries.rendered.contains_key(&key) {
+            if entries.order.len() == entries.capacity {
+                if let Some(oldest) = entries.order.pop_front() {
+                    entries.rendered.remove(&oldest);
+                }
+            }
+            entries.order.push_back(key.clone());
+        }
+        entries.rendered.insert(key, rendered);
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        // a panic while holding the lock cannot leave a half-written entry
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Entries {
+    fn retain(&mut self, mut keep: impl FnMut(&MemoKey) -> bool) {
+        self.rendered.retain(|key, _| keep(key));
+        self.order.retain(|key| keep(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoCache, MemoKey};
+
+    fn key(template: &str, value: &str) -> MemoKey {
+        MemoKey {
+            template: template.to_owned(),
+            position: (1, 1),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let memo = MemoCache::new();
+        memo.insert(key("a", "\"x\""), ("ax".to_owned(), None));
+        memo.insert(key("a", "\"y\""), ("ay".to_owned(), None));
+        memo.insert(key("b", "\"x\""), ("bx".to_owned(), None));
+        assert_eq!(memo.len(), 3);
+
+        memo.invalidate_value(&json!("x"));
+        assert_eq!(memo.len(), 1);
+        assert_eq!(memo.get(&key("a", "\"y\"")), Some(("ay".to_owned(), None)));
+
+        memo.invalidate_template("a");
+        assert!(memo.is_empty());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let memo = MemoCache::with_capacity(2);
+        memo.insert(key("a", "1"), ("a1".to_owned(), None));
+        memo.insert(key("a", "2"), ("a2".to_owned(), None));
+        memo.insert(key("a", "1"), ("a1".to_owned(), None));
+        memo.insert(key("a", "3"), ("a3".to_owned(), None));
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.get(&key("a", "1")), None);
+        assert_eq!(memo.get(&key("a", "3")), Some(("a3".to_owned(), None)));
+
+        let disabled = MemoCache::with_capacity(0);
+        disabled.insert(key("a", "1"), ("a1".to_owned(), None));
+        assert!(disabled.is_empty());
+    }
+}
@@ -0,0 +1,681 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use handlebars::TemplateError;
+
+use crate::outline::{outline_switches, SwitchOutline};
+use crate::rewrite::tag_end;
+
+/// Eq Chains To Switches
+///
+/// Rewrites chains like `{{#if (eq x "a")}}...{{else if (eq x "b")}}...
+/// {{else}}...{{/if}}` that compare the same value in every branch into the
+/// equivalent `{{#switch x}}{{#case "a"}}...{{/case}}...{{/switch}}`, to help
+/// move existing templates over to this helper. Branch bodies, whitespace
+/// control, and everything outside such chains are left untouched, and
+/// chains nested in branch bodies are rewritten as well. An `{{#if}}` with a
+/// single `eq` branch is not a chain and is left alone.
+///
+/// Note that `{{#case}}` compares the way [`SwitchHelper`](crate::SwitchHelper)
+/// was configured, so a helper matching radix literals or ignoring case
+/// styles can match where `eq` did not.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars_switch::eq_chains_to_switches;
+///
+/// assert_eq!(
+///     eq_chains_to_switches(
+///         "{{#if (eq role \"admin\")}}A{{else if (eq role \"owner\")}}O{{else}}U{{/if}}"
+///     ),
+///     "{{#switch role}}{{#case \"admin\"}}A{{/case}}{{#case \"owner\"}}O{{/case}}\
+///      {{#default}}U{{/default}}{{/switch}}"
+/// );
+/// ```
+pub fn eq_chains_to_switches(source: &str) -> String {
+    let tags = tags(source);
+    convert(source, &tags, 0..source.len())
+}
+
+/// A mustache tag in the source
+struct Tag<'a> {
+    span: Range<usize>,
+    /// The contents between the braces and any `~`
+    inner: &'a str,
+    strip_before: bool,
+    strip_after: bool,
+}
+
+impl Tag<'_> {
+    fn tildes(&self) -> (&'static str, &'static str) {
+        let tilde = |strip| if strip { "~" } else { "" };
+        (tilde(self.strip_before), tilde(self.strip_after))
+    }
+
+    /// Reads what separates the two tags replacing this one so they stay on
+    /// lines of their own, keeping handlebars from rendering the line break
+    /// of a tag that stands alone on its line
+    fn separator<'s>(&self, source: &'s str) -> (&'s str, &'s str) {
+        let line_start = source[..self.span.start].rfind('\n').map_or(0, |x| x + 1);
+        let indent = &source[line_start..self.span.start];
+        let rest = &source[self.span.end..];
+        let rest = rest.trim_start_matches([' ', '\t']);
+        let newline = if rest.starts_with("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let standalone = indent.trim_start_matches([' ', '\t']).is_empty()
+            && (rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n"));
+        if standalone {
+            (newline, indent)
+        } else {
+            ("", "")
+        }
+    }
+}
+
+/// Collects the tags of the source, skipping comments and escaped tags
+fn tags(source: &str) -> Vec<Tag<'_>> {
+    let mut tags = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = source[offset..].find("{{").map(|x| x + offset) {
+        let tag = &source[start..];
+        if source[..start].ends_with('\\') {
+            offset = start + 2;
+            continue;
+        }
+        let is_comment = tag.starts_with("{{!");
+        let end = if let Some(comment) = tag.strip_prefix("{{!--") {
+            comment.find("--}}").map(|x| start + 5 + x + 4)
+        } else if is_comment || tag.starts_with("{{{{") {
+            tag.find("}}").map(|x| start + x + 2)
+        } else {
+            tag_end(tag).map(|x| start + x + 2)
+        };
+        let Some(end) = end else {
+            break;
+        };
+        offset = end;
+        if is_comment {
+            continue;
+        }
+
+        let inner = &source[start + 2..end - 2];
+        let (strip_before, inner) = match inner.strip_prefix('~') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let (strip_after, inner) = match inner.strip_suffix('~') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        tags.push(Tag {
+            span: start..end,
+            inner: inner.trim(),
+            strip_before,
+            strip_after,
+        });
+    }
+    tags
+}
+
+/// Rewrites the chains among `tags`, which all lie within `bytes`
+fn convert(source: &str, tags: &[Tag], bytes: Range<usize>) -> String {
+    let mut converted = String::with_capacity(bytes.len());
+    let mut offset = bytes.start;
+    let mut i = 0;
+    while i < tags.len() {
+        let Some(chain) = eq_chain(&tags[i..]) else {
+            i += 1;
+            continue;
+        };
+        let chain_tags = &tags[i..=i + chain.end];
+        converted.push_str(&source[offset..chain_tags[0].span.start]);
+        write_switch(&mut converted, source, chain_tags, &chain);
+        offset = chain_tags[chain.end].span.end;
+        i += chain.end + 1;
+    }
+    converted.push_str(&source[offset..bytes.end]);
+    converted
+}
+
+/// A chain of `eq` branches on one value
+struct Chain<'a> {
+    subject: &'a str,
+    /// The index of the tag opening each branch, along with the value of a
+    /// `{{#case}}` or `None` for the `{{#default}}`
+    branches: Vec<(usize, Option<&'a str>)>,
+    /// The index of the closing `{{/if}}`
+    end: usize,
+}
+
+/// Reads the chain opened by the first tag, if it is one
+fn eq_chain<'a>(tags: &[Tag<'a>]) -> Option<Chain<'a>> {
+    let (subject, value) = tags[0].inner.strip_prefix("#if").and_then(eq_params)?;
+    let mut branches = vec![(0, Some(value))];
+    let mut depth = 0usize;
+    for (index, tag) in tags.iter().enumerate().skip(1) {
+        let inner = tag.inner;
+        if inner.starts_with('#') || (inner.starts_with('^') && inner.len() > 1) {
+            depth += 1;
+        } else if let Some(name) = inner.strip_prefix('/') {
+            if depth == 0 {
+                let is_chain = name.trim() == "if"
+                    && branches.iter().filter(|(_, value)| value.is_some()).count() > 1;
+                return is_chain.then_some(Chain {
+                    subject,
+                    branches,
+                    end: index,
+                });
+            }
+            depth -= 1;
+        } else if depth == 0 && (inner == "else" || inner == "^") {
+            branches.push((index, None));
+        } else if depth == 0 && inner.starts_with("else") {
+            let (other, value) = inner
+                .strip_prefix("else")
+                .and_then(|x| x.trim_start().strip_prefix("if"))
+                .and_then(eq_params)?;
+            if other != subject {
+                return None;
+            }
+            branches.push((index, Some(value)));
+        }
+    }
+    None
+}
+
+/// Reads the two params of ` (eq a b)`, following the `if` of a tag
+fn eq_params(rest: &str) -> Option<(&str, &str)> {
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let call = rest.trim().strip_prefix('(')?.strip_suffix(')')?;
+    match split_params(call)?[..] {
+        ["eq", subject, value] if !subject.starts_with('(') && !value.starts_with('(') => {
+            Some((subject, value))
+        }
+        _ => None,
+    }
+}
+
+/// Splits the params of a tag at whitespace outside of string literals and
+/// subexpressions
+fn split_params(params: &str) -> Option<Vec<&str>> {
+    let mut split = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    let mut depth = 0usize;
+    for (i, c) in params.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, c) if c.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    split.push(&params[start..i]);
+                }
+            }
+            (None, c) => {
+                match c {
+                    '"' | '\'' => quote = Some(c),
+                    '(' => depth += 1,
+                    ')' => depth = depth.checked_sub(1)?,
+                    _ => {}
+                }
+                if !c.is_whitespace() {
+                    start.get_or_insert(i);
+                }
+            }
+        }
+    }
+    if quote.is_some() || depth > 0 {
+        return None;
+    }
+    if let Some(start) = start {
+        split.push(&params[start..]);
+    }
+    Some(split)
+}
+
+fn write_switch(converted: &mut String, source: &str, tags: &[Tag], chain: &Chain) {
+    let arm_name = |value: Option<&str>| if value.is_some() { "case" } else { "default" };
+    let (before, _) = tags[0].tildes();
+    converted.push_str(&format!("{{{{{before}#switch {}}}}}", chain.subject));
+
+    for (k, &(index, value)) in chain.branches.iter().enumerate() {
+        let (_, after) = tags[index].tildes();
+        if index == 0 {
+            let (newline, indent) = tags[0].separator(source);
+            converted.push_str(newline);
+            converted.push_str(indent);
+        }
+        match value {
+            Some(value) => converted.push_str(&format!("{{{{#case {value}{after}}}}}")),
+            None => converted.push_str(&format!("{{{{#default{after}}}}}")),
+        }
+
+        let next = chain
+            .branches
+            .get(k + 1)
+            .map_or(chain.end, |(next, _)| *next);
+        let body = tags[index].span.end..tags[next].span.start;
+        converted.push_str(&convert(source, &tags[index + 1..next], body));
+
+        let (before, _) = tags[next].tildes();
+        converted.push_str(&format!("{{{{{before}/{}}}}}", arm_name(value)));
+        let (newline, indent) = tags[next].separator(source);
+        converted.push_str(newline);
+        converted.push_str(indent);
+    }
+
+    let (_, after) = tags[chain.end].tildes();
+    converted.push_str(&format!("{{{{/switch{after}}}}}"));
+}
+
+/// Transpile Error
+///
+/// Why [`switches_to_if_chains`] could not rewrite a template.
+#[derive(Debug)]
+pub enum TranspileError {
+    /// The template failed to compile.
+    Template(TemplateError),
+    /// A block has no equivalent `{{#if}}` chain.
+    Unsupported {
+        /// The bytes of the offending block.
+        span: Range<usize>,
+        /// What keeps the block from being rewritten.
+        reason: String,
+    },
+}
+
+impl fmt::Display for TranspileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranspileError::Template(e) => e.fmt(f),
+            TranspileError::Unsupported { span, reason } => {
+                write!(f, "cannot rewrite the block at bytes {span:?}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for TranspileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TranspileError::Template(e) => Some(e),
+            TranspileError::Unsupported { .. } => None,
+        }
+    }
+}
+
+/// Switches To If Chains
+///
+/// Rewrites every `{{#switch}}` into the equivalent chain of built-in
+/// `{{#if (eq ...)}}...{{else if (eq ...)}}...{{else}}...{{/if}}` blocks,
+/// so templates can be shared with environments where this helper cannot be
+/// registered. It is the reverse of [`eq_chains_to_switches`], and leaves
+/// arm bodies, whitespace control, and everything outside the switches
+/// untouched.
+///
+/// Only switches that an `{{#if}}` chain can express are rewritten: plain
+/// `{{#case}}` values without hash parameters, at most one `{{#default}}`
+/// as the last arm, no `{{else}}` placeholders, and nothing but whitespace
+/// that does not render between the arms. Anything else, including the
+/// other blocks of the switch helper family, fails with
+/// [`TranspileError::Unsupported`]. As with [`eq_chains_to_switches`], `eq`
+/// does not know about the comparison options of
+/// [`SwitchHelper`](crate::SwitchHelper).
+///
+/// # Examples
+///
+/// ```
+/// use handlebars_switch::switches_to_if_chains;
+///
+/// assert_eq!(
+///     switches_to_if_chains(
+///         "{{#switch role}}{{#case \"admin\" \"owner\"}}A{{/case}}{{#default}}U{{/default}}{{/switch}}"
+///     )
+///     .unwrap(),
+///     "{{#if (or (eq role \"admin\") (eq role \"owner\"))}}A{{else}}U{{/if}}"
+/// );
+/// ```
+pub fn switches_to_if_chains(source: &str) -> Result<String, TranspileError> {
+    let mut edits = Vec::new();
+    for switch in outline_switches(source).map_err(TranspileError::Template)? {
+        chain_edits(source, &switch, &mut edits)?;
+    }
+    edits.sort_by_key(|(span, _)| span.start);
+
+    let mut transpiled = String::with_capacity(source.len());
+    let mut offset = 0;
+    for (span, replacement) in edits {
+        transpiled.push_str(&source[offset..span.start]);
+        transpiled.push_str(&replacement);
+        offset = span.end;
+    }
+    transpiled.push_str(&source[offset..]);
+    Ok(transpiled)
+}
+
+/// Collects the tags replacing those of a switch and of the switches nested
+/// in its arms, along with the bytes they replace
+fn chain_edits(
+    source: &str,
+    switch: &SwitchOutline,
+    edits: &mut Vec<(Range<usize>, String)>,
+) -> Result<(), TranspileError> {
+    let unsupported = |reason: &str| {
+        Err(TranspileError::Unsupported {
+            span: switch.span.clone(),
+            reason: reason.to_owned(),
+        })
+    };
+    if switch.helper != "switch" {
+        return unsupported("only `{{#switch}}` blocks have an `{{#if}}` equivalent");
+    }
+    let subject = match split_params(&source[switch.expression.clone()]).as_deref() {
+        Some([subject]) if !is_hash(subject) => *subject,
+        _ => return unsupported("the `{{#switch}}` must take a single value and no hash"),
+    };
+    if switch.arms.is_empty() {
+        return unsupported("the `{{#switch}}` has no arms");
+    }
+
+    let (switch_open, switch_close) = block_tags(source, &switch.span);
+    let mut previous = switch_open.clone();
+    let mut replaced_start = switch_open.start;
+    for (index, arm) in switch.arms.iter().enumerate() {
+        let (open, close) = block_tags(source, &arm.span);
+        let condition = match arm.name.as_str() {
+            "default" if index + 1 == switch.arms.len() => None,
+            "default" => return unsupported("`{{#default}}` must be the last arm"),
+            "case" => {
+                let values = split_params(&source[arm.params.clone()]).unwrap_or_default();
+                if values.is_empty() || values.iter().any(|x| is_hash(x)) {
+                    return unsupported("every `{{#case}}` must list values and no hash");
+                }
+                let tests: Vec<String> = values
+                    .iter()
+                    .map(|value| format!("(eq {subject} {value})"))
+                    .collect();
+                Some(match &tests[..] {
+                    [test] => test.clone(),
+                    tests => format!("(or {})", tests.join(" ")),
+                })
+            }
+            _ => return unsupported("only `{{#case}}` and `{{#default}}` arms can be rewritten"),
+        };
+        if has_placeholder(&source[open.end..close.start]) {
+            return unsupported("`{{else}}` placeholders have no `{{#if}}` equivalent");
+        }
+        if !gap_vanishes(source, &previous, &open) {
+            return unsupported("only whitespace that does not render may separate the arms");
+        }
+
+        let before = tilde_before(source, replaced_start);
+        let after = tilde_after(source, open.end);
+        let tag = match (index, condition) {
+            (0, Some(condition)) => format!("{{{{{before}#if {condition}{after}}}}}"),
+            (0, None) => format!("{{{{{before}#if true{after}}}}}"),
+            (_, Some(condition)) => format!("{{{{{before}else if {condition}{after}}}}}"),
+            (_, None) => format!("{{{{{before}else{after}}}}}"),
+        };
+        edits.push((replaced_start..open.end, tag));
+        previous = close.clone();
+        replaced_start = close.start;
+    }
+
+    if !gap_vanishes(source, &previous, &switch_close) {
+        return unsupported("only whitespace that does not render may follow the last arm");
+    }
+    let before = tilde_before(source, replaced_start);
+    let after = tilde_after(source, switch_close.end);
+    edits.push((
+        replaced_start..switch_close.end,
+        format!("{{{{{before}/if{after}}}}}"),
+    ));
+
+    for child in &switch.children {
+        chain_edits(source, child, edits)?;
+    }
+    Ok(())
+}
+
+/// Finds the opening and closing tags of a compiled block
+fn block_tags(source: &str, span: &Range<usize>) -> (Range<usize>, Range<usize>) {
+    let block = &source[span.clone()];
+    let open_end = tag_end(block).map_or(block.len(), |x| x + 2);
+    let close_start = block.rfind("{{").unwrap_or(open_end).max(open_end);
+    (
+        span.start..span.start + open_end,
+        span.start + close_start..span.end,
+    )
+}
+
+/// Reads the `~` the tag starting at `start` opens with
+fn tilde_before(source: &str, start: usize) -> &'static str {
+    if source[start..].starts_with("{{~") {
+        "~"
+    } else {
+        ""
+    }
+}
+
+/// Reads the `~` the tag ending at `end` closes with
+fn tilde_after(source: &str, end: usize) -> &'static str {
+    if source[..end].ends_with("~}}") {
+        "~"
+    } else {
+        ""
+    }
+}
+
+/// Reads whether a param is part of a hash or of block params
+fn is_hash(param: &str) -> bool {
+    param.starts_with('|')
+        || param == "as"
+        || (!param.starts_with(['(', '"', '\'']) && param.contains('='))
+}
+
+/// Checks an arm body for an `{{else}}` outside of its nested blocks
+fn has_placeholder(body: &str) -> bool {
+    let mut depth = 0usize;
+    for tag in tags(body) {
+        let inner = tag.inner;
+        if inner.starts_with('#') || (inner.starts_with('^') && inner.len() > 1) {
+            depth += 1;
+        } else if inner.starts_with('/') {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && (inner == "^" || inner.starts_with("else")) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Checks that two tags can be merged into one without changing the
+/// output: the source between them must render nothing, and the merged tag
+/// must stand alone on its line exactly when both tags did, since handlebars
+/// drops the line break of such tags
+fn gap_vanishes(source: &str, left: &Range<usize>, right: &Range<usize>) -> bool {
+    let gap = &source[left.end..right.start];
+    if !gap.chars().all(char::is_whitespace) {
+        return false;
+    }
+    let stripped =
+        !tilde_after(source, left.end).is_empty() || !tilde_before(source, right.start).is_empty();
+    let line_start = source[..left.start].rfind('\n').map_or(0, |x| x + 1);
+    let indent = &source[line_start..left.start];
+    let rest = source[right.end..].trim_start_matches([' ', '\t']);
+    let line_ends = rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n");
+
+    // which tags stand alone on their lines, dropping the indent before
+    // them and the line break after them
+    let breaks = gap.matches('\n').count();
+    let left_alone = indent.trim_start_matches([' ', '\t']).is_empty() && breaks > 0;
+    let right_alone = line_ends && breaks > 0;
+    let merged_alone = indent.trim_start_matches([' ', '\t']).is_empty() && line_ends;
+
+    let vanishes = match breaks {
+        _ if stripped => true,
+        0 => gap.is_empty(),
+        1 => left_alone && right_alone,
+        _ => false,
+    };
+    vanishes && (left_alone == merged_alone || indent.is_empty()) && right_alone == merged_alone
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{eq_chains_to_switches, switches_to_if_chains, TranspileError};
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_eq_chains_to_switches() {
+        assert_eq!(
+            eq_chains_to_switches(
+                "{{~#if (eq x 1) ~}} a {{~else if (eq x 'b c')}}\
+                 {{#if y}}{{else}}{{/if}}{{^}}d{{/if~}}"
+            ),
+            "{{~#switch x}}{{#case 1~}} a {{~/case}}{{#case 'b c'}}\
+             {{#if y}}{{else}}{{/if}}{{/case}}{{#default}}d{{/default}}{{/switch~}}"
+        );
+        assert_eq!(
+            eq_chains_to_switches(
+                "{{#each a}}{{#if (eq x 1)}}{{else if (eq x 2)}}\
+                 {{#if (eq y 1)}}{{else if (eq y 2)}}{{/if}}{{/if}}{{/each}}"
+            ),
+            "{{#each a}}{{#switch x}}{{#case 1}}{{/case}}{{#case 2}}\
+             {{#switch y}}{{#case 1}}{{/case}}{{#case 2}}{{/case}}{{/switch}}\
+             {{/case}}{{/switch}}{{/each}}"
+        );
+    }
+
+    #[test]
+    fn test_non_chains_are_untouched() {
+        let sources = [
+            "{{#if (eq x 1)}}a{{else}}b{{/if}}",
+            "{{#if (eq x 1)}}a{{else if (eq y 2)}}b{{/if}}",
+            "{{#if (eq x 1)}}a{{else if z}}b{{/if}}",
+            "{{#if (eq x (f 1))}}a{{else if (eq x 2)}}b{{/if}}",
+            "{{#if (eq x 1) includeZero=true}}a{{else if (eq x 2)}}b{{/if}}",
+            "{{!-- {{#if (eq x 1)}}a{{else if (eq x 2)}}b{{/if}} --}}",
+            "\\{{#if (eq x 1)}}a{{else if (eq x 2)}}b{{/if}}",
+            "{{#if (eq x 1)}}a{{else if (eq x 2)}}",
+        ];
+        for source in sources {
+            assert_eq!(eq_chains_to_switches(source), source);
+        }
+    }
+
+    #[test]
+    fn test_converted_templates_render_the_same() {
+        let sources = [
+            "Plan:\n{{#if (eq plan \"pro\")}}\nPro {{name}}\n{{else if (eq plan \"free\")}}\n\
+             Free\n{{else}}\nNone\n{{/if}}\nDone\n",
+            "<ul>\r\n  {{#if (eq plan \"pro\")}}  \r\n  <li>Pro</li>\r\n  \
+             {{else if (eq plan \"free\")}}\r\n  <li>Free</li>\r\n  {{/if}}\r\n</ul>",
+            "{{#if (eq plan \"pro\")}} Pro {{else if (eq plan \"free\")}}\nFree\n{{/if}}",
+            "{{#if (eq plan \"pro\")~}}\n Pro\n{{~else if (eq plan \"free\")}}\nFree\n{{/if}}",
+            "  {{#if (eq plan \"pro\")~}}\n  Pro\n  {{else if (eq plan \"free\")~}}\n  \
+             {{#if (eq name \"Jo\")}}\n    Jo\n  {{else if (eq name 1)}}\n{{/if}}\n{{else~}}\n  X\n{{/if~}}\n.",
+        ];
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        for source in sources {
+            let converted = eq_chains_to_switches(source);
+            assert!(converted.contains("{{#switch plan}}"));
+
+            for plan in ["pro", "free", "other"] {
+                let data = json!({"plan": plan, "name": "Jo"});
+                assert_eq!(
+                    handlebars.render_template(&converted, &data).unwrap(),
+                    handlebars.render_template(source, &data).unwrap(),
+                    "{converted:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_switches_to_if_chains() {
+        assert_eq!(
+            switches_to_if_chains(
+                "{{~#switch (lower x) ~}} {{#case 1}}a{{~/case}}{{#case \"b c\"~}}\
+                 {{#switch y}}{{#default}}{{#if z}}{{else}}{{/if}}{{/default}}{{/switch}}\
+                 {{/case}}{{/switch~}}"
+            )
+            .unwrap(),
+            "{{~#if (eq (lower x) 1)}}a{{~else if (eq (lower x) \"b c\")~}}\
+             {{#if true}}{{#if z}}{{else}}{{/if}}{{/if}}{{/if~}}"
+        );
+
+        let source = "{{#if (eq x 1)}}a{{else if (eq x 2)}}b{{else}}c{{/if}}";
+        let switched = eq_chains_to_switches(source);
+        assert_eq!(switches_to_if_chains(&switched).unwrap(), source);
+    }
+
+    #[test]
+    fn test_unsupported_switches() {
+        let sources = [
+            "{{#switch x arrays=\"set\"}}{{#case 1}}{{/case}}{{/switch}}",
+            "{{#switch x}}{{#case 1 not=true}}{{/case}}{{/switch}}",
+            "{{#switch x}}{{#case}}{{/case}}{{/switch}}",
+            "{{#switch x}}{{#default}}{{/default}}{{#case 1}}{{/case}}{{/switch}}",
+            "{{#switch x}}{{#case 1}}a{{else}}b{{/case}}{{/switch}}",
+            "{{#switch x}}{{#case 1}}{{/case}} {{#case 2}}{{/case}}{{/switch}}",
+            "{{#switch x}}{{#if y}}{{#case 1}}{{/case}}{{/if}}{{/switch}}",
+            "{{#switch x}}\n\n{{#case 1}}{{/case}}{{/switch}}",
+            "{{#switch x}}\n{{#case 1}}{{/case}} {{~#case 2}}\n{{/case}}{{/switch}}",
+            "{{#switch x}}\n{{#case 1}}a\n{{/case}}{{#case 2}}\n{{/case}}{{/switch}}",
+            "{{#switch x}}\n{{#case 1}}\n{{/case}}{{#default}}\nb\n{{/default}}\n{{/switch}}",
+            "{{#switch x}}{{/switch}}",
+            "{{#switch_flags a b}}{{#case \"11\"}}{{/case}}{{/switch_flags}}",
+        ];
+        for source in sources {
+            assert!(
+                matches!(
+                    switches_to_if_chains(source),
+                    Err(TranspileError::Unsupported { .. })
+                ),
+                "{source}"
+            );
+        }
+        assert!(matches!(
+            switches_to_if_chains("{{#switch x}}"),
+            Err(TranspileError::Template(_))
+        ));
+    }
+
+    #[test]
+    fn test_transpiled_templates_render_the_same() {
+        let sources = [
+            "{{#switch plan}}{{#case \"pro\" \"team\"}}Pro {{name}}{{/case}}{{/switch}}",
+            "<ul>\r\n  {{#switch plan}}\r\n  {{#case \"pro\"}}  \r\n  <li>Pro</li>\r\n  \
+             {{/case}}\r\n  {{#default}}\r\n  <li>Free</li>\r\n  {{/default}}\r\n  {{/switch}}\r\n</ul>",
+            "Plan:\n{{#switch plan~}}\n\n  {{#case \"pro\"}} Pro {{/case}} \n {{~#case \"team\"}} \
+             Team\n{{/case}}\n{{/switch}}\nDone",
+        ];
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        for source in sources {
+            let transpiled = switches_to_if_chains(source).unwrap();
+            assert!(!transpiled.contains("switch"));
+
+            for plan in ["pro", "team", "other"] {
+                let data = json!({"plan": plan, "name": "Jo"});
+                assert_eq!(
+                    handlebars.render_template(&transpiled, &data).unwrap(),
+                    handlebars.render_template(source, &data).unwrap(),
+                    "{transpiled:?}"
+                );
+            }
+        }
+    }
+}
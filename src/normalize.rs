@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+/// Normalize
+///
+/// A step of the normalization a switch helper applies to the switch value
+/// and to every `{{#case}}` value before comparing them, so inputs that
+/// differ only in form match the same arm. Steps are chained into a
+/// [`Pipeline`], which is itself a step, and registered with
+/// [`SwitchHelper::with_normalizer`], or under a name that a `{{#switch}}`
+/// selects with `normalize="name"`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{AliasMap, Lowercase, Pipeline, SwitchHelper, Trim};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(SwitchHelper::new().with_normalizer(
+///         Pipeline::new()
+///             .then(Trim)
+///             .then(Lowercase)
+///             .then(AliasMap::new([("ts", "typescript")])),
+///     )),
+/// );
+///
+/// let tpl = "{{#switch lang}}{{#case \"TypeScript\"}}.ts{{/case}}{{/switch}}";
+/// assert_eq!(handlebars.render_template(tpl, &json!({"lang": " TS "})).unwrap(), ".ts");
+/// # }
+/// ```
+///
+/// [`SwitchHelper::with_normalizer`]: crate::SwitchHelper::with_normalizer
+pub trait Normalize: Send + Sync {
+    /// Returns the normal form of `value`.
+    fn normalize(&self, value: Value) -> Value;
+}
+
+/// Applies `f` to a string value, leaving other values alone
+fn map_string(value: Value, f: impl FnOnce(&str) -> String) -> Value {
+    match value {
+        Value::String(s) => Value::String(f(&s)),
+        other => other,
+    }
+}
+
+/// Strips leading and trailing whitespace from strings.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Trim;
+
+impl Normalize for Trim {
+    fn normalize(&self, value: Value) -> Value {
+        map_string(value, |s| s.trim().to_owned())
+    }
+}
+
+/// Converts strings to lower case.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lowercase;
+
+impl Normalize for Lowercase {
+    fn normalize(&self, value: Value) -> Value {
+        map_string(value, str::to_lowercase)
+    }
+}
+
+/// Brings strings into Unicode normalization form C, so a letter followed by
+/// combining marks like `"e\u{301}"` becomes the precomposed `"é"` and
+/// canonically equivalent strings compare equal. Requires the `unicode`
+/// feature.
+#[cfg(feature = "unicode")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Nfc;
+
+#[cfg(feature = "unicode")]
+impl Normalize for Nfc {
+    fn normalize(&self, value: Value) -> Value {
+        use unicode_normalization::UnicodeNormalization;
+
+        map_string(value, |s| s.nfc().collect())
+    }
+}
+
+/// Maps strings onto canonical values, like
+/// [`SwitchHelper::with_aliases`](crate::SwitchHelper::with_aliases) but at
+/// any point of a [`Pipeline`].
+#[derive(Clone, Debug, Default)]
+pub struct AliasMap(HashMap<String, Value>);
+
+impl AliasMap {
+    /// Creates an alias map, where later entries replace earlier ones with
+    /// the same key.
+    pub fn new<I, K, V>(aliases: I) -> AliasMap
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        AliasMap(
+            aliases
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Normalize for AliasMap {
+    fn normalize(&self, value: Value) -> Value {
+        match value {
+            Value::String(s) => self.0.get(&s).cloned().unwrap_or(Value::String(s)),
+            other => other,
+        }
+    }
+}
+
+/// Applies steps one after the other, in the order they were added.
+#[derive(Clone, Default)]
+pub struct Pipeline(Vec<Arc<dyn Normalize>>);
+
+impl Pipeline {
+    /// Creates a pipeline that leaves values alone.
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Adds a step to the end of the pipeline.
+    pub fn then(mut self, step: impl Normalize + 'static) -> Pipeline {
+        self.0.push(Arc::new(step));
+        self
+    }
+}
+
+impl Normalize for Pipeline {
+    fn normalize(&self, value: Value) -> Value {
+        self.0
+            .iter()
+            .fold(value, |value, step| step.normalize(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AliasMap, Lowercase, Normalize, Pipeline, Trim};
+
+    #[test]
+    fn test_pipeline() {
+        let pipeline = Pipeline::new()
+            .then(Trim)
+            .then(AliasMap::new([("Yes", true)]))
+            .then(Lowercase);
+        assert_eq!(pipeline.normalize(json!(" Yes ")), json!(true));
+        assert_eq!(pipeline.normalize(json!(" YES ")), json!("yes"));
+        assert_eq!(pipeline.normalize(json!([" A "])), json!([" A "]));
+        assert_eq!(Pipeline::new().normalize(json!(" A ")), json!(" A "));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn test_nfc() {
+        use super::Nfc;
+
+        assert_eq!(Nfc.normalize(json!("Jose\u{301}")), json!("José"));
+        assert_eq!(Nfc.normalize(json!("C\u{327}a\u{300}")), json!("Çà"));
+        assert_eq!(Nfc.normalize(json!("q\u{301}")), json!("q\u{301}"));
+        assert_eq!(Nfc.normalize(json!("\u{301}e")), json!("\u{301}e"));
+        assert_eq!(Nfc.normalize(json!("e\u{323}\u{302}")), json!("ệ"));
+        assert_eq!(Nfc.normalize(json!("\u{212b}")), json!("Å"));
+        assert_eq!(Nfc.normalize(json!("\u{1100}\u{1161}")), json!("가"));
+    }
+}
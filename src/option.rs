@@ -0,0 +1,125 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+    Renderable,
+};
+
+use serde_json::Value;
+
+use crate::block::PayloadHelper;
+use crate::vars::{restore_vars, saved_vars};
+
+/// Switch Some Helper
+///
+/// Provides the `{{#switch_some}}` helper to a Handlebars template. It
+/// branches on whether a value is present, rendering the `{{#some}}` arm for
+/// any value other than `null` or a missing key and the `{{#none}}` arm
+/// otherwise. The `{{#some}}` arm can bind the value with `as |v|`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchSomeHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch_some", Box::new(SwitchSomeHelper));
+///
+/// let tpl = "\
+///     {{#switch_some nickname}}\
+///         {{#some as |name|}}Hi {{name}}{{/some}}\
+///         {{#none}}Hi there{{/none}}\
+///     {{/switch_some}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"nickname": "Bo"})).unwrap(),
+///     "Hi Bo"
+/// );
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"nickname": null})).unwrap(),
+///     "Hi there"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SwitchSomeHelper;
+
+impl HelperDef for SwitchSomeHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        // Read in the optional variable or expression
+        let param = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("switch_some", 0))?;
+
+        let value = Some(param.value()).filter(|x| !x.is_null()).cloned();
+        let absent = value.is_none().then_some(Value::Null);
+
+        // Add the `{{#some}}` and `{{#none}}` helpers within the block
+        let mut local_rc = rc.clone();
+        local_rc.register_local_helper("some", Box::new(PayloadHelper { payload: value }));
+        local_rc.register_local_helper("none", Box::new(PayloadHelper { payload: absent }));
+
+        // Render the `{{#switch_some}}` block
+        let result = match h.template() {
+            Some(t) => t.render(r, ctx, &mut local_rc, out),
+            None => Ok(()),
+        };
+        restore_vars(rc, saved_vars(&local_rc));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchSomeHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_switch_some() {
+        let tpl = "\
+            {{#switch_some user.email}}\
+                {{#some as |email|}}<{{email}}> {{user.name}}{{/some}}\
+                {{#none}}{{user.name}}{{/none}}\
+            {{/switch_some}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch_some", Box::new(SwitchSomeHelper));
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let r0 = handlebars.render("tpl", &json!({"user": {"name": "Al", "email": "a@b.c"}}));
+        assert_eq!(r0.unwrap(), "<a@b.c> Al");
+
+        let r1 = handlebars.render("tpl", &json!({"user": {"name": "Al", "email": null}}));
+        assert_eq!(r1.unwrap(), "Al");
+
+        let r2 = handlebars.render("tpl", &json!({"user": {"name": "Al"}}));
+        assert_eq!(r2.unwrap(), "Al");
+
+        // falsy values are still present
+        let r3 = handlebars.render("tpl", &json!({"user": {"name": "Al", "email": ""}}));
+        assert_eq!(r3.unwrap(), "<> Al");
+    }
+
+    #[test]
+    fn test_some_helper_not_defined() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch_some", Box::new(SwitchSomeHelper));
+
+        assert!(handlebars
+            .render_template("{{#some}}x{{/some}}", &json!({}))
+            .is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::config::SwitchFamily;
+use crate::switch::SwitchHelper;
+
+/// Switch Ordinal Helper
+///
+/// Provides the `{{#switch_ordinal}}` helper to a Handlebars template. It
+/// places a whole number into its CLDR ordinal plural category, one of
+/// `"one"`, `"two"`, `"few"`, `"many"`, and `"other"`, and then behaves like
+/// `{{#switch}}` on that category, so a ranking can pick the suffix of
+/// "1st", "22nd", "103rd", and "11th".
+///
+/// The rules are English unless `locale="fr"` or similar names another
+/// language. French, Italian, and Swedish have rules of their own, and every
+/// other language switches on `"other"` for all numbers, which suits the many
+/// languages that write every ordinal the same way, such as German `1.` or
+/// Spanish `1.º`. Negative numbers take the category of their magnitude, and
+/// values that are not whole numbers switch on `null`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchOrdinalHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch_ordinal", Box::new(SwitchOrdinalHelper));
+///
+/// let tpl = "\
+///     {{rank}}{{#switch_ordinal rank}}\
+///         {{#case \"one\"}}st{{/case}}\
+///         {{#case \"two\"}}nd{{/case}}\
+///         {{#case \"few\"}}rd{{/case}}\
+///         {{#default}}th{{/default}}\
+///     {{/switch_ordinal}}\
+/// ";
+///
+/// assert_eq!(handlebars.render_template(tpl, &json!({"rank": 22})).unwrap(), "22nd");
+/// assert_eq!(handlebars.render_template(tpl, &json!({"rank": 113})).unwrap(), "113th");
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SwitchOrdinalHelper;
+
+impl SwitchFamily for SwitchOrdinalHelper {
+    fn render_with<'reg: 'rc, 'rc>(
+        &self,
+        switch: &SwitchHelper,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let param = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("switch_ordinal", 0))?;
+
+        let locale = match h.hash_get("locale").map(|x| x.value()) {
+            Some(Value::String(locale)) => locale.as_str(),
+            Some(_) => {
+                return Err(RenderErrorReason::HashTypeMismatchForName(
+                    "switch_ordinal",
+                    "locale".to_owned(),
+                    "string".to_owned(),
+                )
+                .into())
+            }
+            None => "en",
+        };
+
+        let value = whole_number(param.value())
+            .map_or(Value::Null, |n| Value::from(ordinal_category(locale, n)));
+
+        switch.render_switch(Cow::Owned(value), h, r, ctx, rc, out)
+    }
+}
+
+impl HelperDef for SwitchOrdinalHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_with(&SwitchHelper::new(), h, r, ctx, rc, out)
+    }
+}
+
+/// Reads the magnitude of a whole number, like `3` or `-3.0`
+fn whole_number(value: &Value) -> Option<u64> {
+    let n = value.as_f64()?;
+    (n.fract() == 0.0 && n.abs() < u64::MAX as f64).then(|| n.abs() as u64)
+}
+
+/// Looks up the CLDR ordinal plural category of `n` in the language of
+/// `locale`, like `en` or `fr-CA`
+fn ordinal_category(locale: &str, n: u64) -> &'static str {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    let (n10, n100) = (n % 10, n % 100);
+    match language.to_ascii_lowercase().as_str() {
+        "en" => match (n10, n100) {
+            (1, x) if x != 11 => "one",
+            (2, x) if x != 12 => "two",
+            (3, x) if x != 13 => "few",
+            _ => "other",
+        },
+        "fr" if n == 1 => "one",
+        "it" if matches!(n, 8 | 11 | 80 | 800) => "many",
+        "sv" if matches!(n10, 1 | 2) && !matches!(n100, 11 | 12) => "one",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ordinal_category, SwitchOrdinalHelper};
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_ordinal_category() {
+        let en: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 23, 101, 111, 112]
+            .into_iter()
+            .map(|n| ordinal_category("en-GB", n))
+            .collect();
+        assert_eq!(
+            en,
+            [
+                "one", "two", "few", "other", "other", "other", "other", "one", "two", "few",
+                "one", "other", "other"
+            ]
+        );
+        assert_eq!(ordinal_category("fr", 1), "one");
+        assert_eq!(ordinal_category("fr", 21), "other");
+        assert_eq!(ordinal_category("it", 80), "many");
+        assert_eq!(ordinal_category("sv_SE", 32), "one");
+        assert_eq!(ordinal_category("sv", 12), "other");
+        assert_eq!(ordinal_category("de", 1), "other");
+    }
+
+    #[test]
+    fn test_switch_ordinal() {
+        let tpl = "\
+            {{#switch_ordinal n locale=locale}}\
+                {{#case \"one\"}}one{{/case}}\
+                {{#case \"two\" \"few\"}}two or few{{/case}}\
+                {{#case null}}none{{/case}}\
+                {{#default}}other{{/default}}\
+            {{/switch_ordinal}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch_ordinal", Box::new(SwitchOrdinalHelper));
+
+        let render = |n, locale| {
+            handlebars
+                .render_template(tpl, &json!({"n": n, "locale": locale}))
+                .unwrap()
+        };
+        assert_eq!(render(json!(-21), "en"), "one");
+        assert_eq!(render(json!(3.0), "en"), "two or few");
+        assert_eq!(render(json!(1.5), "en"), "none");
+        assert_eq!(render(json!("1"), "en"), "none");
+        assert_eq!(render(json!(1), "fr"), "one");
+        assert_eq!(render(json!(2), "fr"), "other");
+
+        assert!(handlebars
+            .render_template(
+                "{{#switch_ordinal 1 locale=2}}{{/switch_ordinal}}",
+                &json!({})
+            )
+            .is_err());
+        assert!(handlebars
+            .render_template("{{#switch_ordinal}}{{/switch_ordinal}}", &json!({}))
+            .is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use std::ops::Range;
+
+use handlebars::{Template, TemplateError};
+
+use crate::rewrite::tag_end;
+use crate::validate::ARMS;
+
+/// Arm Outline
+///
+/// A single arm of a [`SwitchOutline`], such as a `{{#case}}` or the
+/// `{{#default}}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArmOutline {
+    /// The name of the arm helper.
+    pub name: String,
+    /// The bytes of the whole arm, from its opening tag through its closing
+    /// tag.
+    pub span: Range<usize>,
+    /// The bytes of the parameters in the opening tag, empty for arms like
+    /// `{{#default}}` that take none.
+    pub params: Range<usize>,
+}
+
+/// Switch Outline
+///
+/// A block of the switch helper family found by [`outline_switches`], along
+/// with its arms and the blocks nested inside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwitchOutline {
+    /// The name of the block helper, such as `switch`.
+    pub helper: String,
+    /// The bytes of the whole block, from its opening tag through its
+    /// closing tag.
+    pub span: Range<usize>,
+    /// The bytes of the parameters in the opening tag, starting with the
+    /// value switched on.
+    pub expression: Range<usize>,
+    /// The arms of the block in template order, including arms wrapped in
+    /// other helpers like `{{#if}}`.
+    pub arms: Vec<ArmOutline>,
+    /// The blocks of the switch helper family nested inside this one.
+    pub children: Vec<SwitchOutline>,
+}
+
+impl SwitchOutline {
+    /// Returns the `{{#default}}` arm, if any.
+    pub fn default_arm(&self) -> Option<&ArmOutline> {
+        self.arms.iter().find(|arm| arm.name == "default")
+    }
+}
+
+/// Outline Switches
+///
+/// Parses a template source and returns every block of the switch helper
+/// family with the byte spans of its value and arms, so editors can offer an
+/// outline of the template and jump to single arms. Handlebars does not keep
+/// the source of registered templates around, so pass in the same source
+/// that was registered. Blocks inside raw blocks are not reported.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars_switch::outline_switches;
+///
+/// let source = "{{#switch role}}{{#case \"admin\"}}A{{/case}}{{#default}}U{{/default}}{{/switch}}";
+/// let outline = outline_switches(source).unwrap();
+///
+/// assert_eq!(&source[outline[0].expression.clone()], "role");
+/// assert_eq!(&source[outline[0].arms[0].params.clone()], "\"admin\"");
+/// assert_eq!(
+///     &source[outline[0].default_arm().unwrap().span.clone()],
+///     "{{#default}}U{{/default}}"
+/// );
+/// ```
+pub fn outline_switches(source: &str) -> Result<Vec<SwitchOutline>, TemplateError> {
+    // reject what handlebars rejects, so the blocks below are well-formed
+    Template::compile(source)?;
+    Ok(switches_in(&blocks(source)))
+}
+
+/// A block of any helper in the source
+struct Block<'a> {
+    name: &'a str,
+    span: Range<usize>,
+    params: Range<usize>,
+    children: Vec<Block<'a>>,
+}
+
+/// Collects the blocks of the source into a tree
+fn blocks(source: &str) -> Vec<Block<'_>> {
+    let mut open: Vec<Block> = Vec::new();
+    let mut roots = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = source[offset..].find("{{").map(|x| x + offset) {
+        let tag = &source[start..];
+        if source[..start].ends_with('\\') {
+            offset = start + 2;
+            continue;
+        }
+        let end = if let Some(comment) = tag.strip_prefix("{{!--") {
+            comment.find("--}}").map(|x| start + 5 + x + 4)
+        } else if tag.starts_with("{{!") || tag.starts_with("{{{{") {
+            tag.find("}}").map(|x| start + x + 2)
+        } else {
+            tag_end(tag).map(|x| start + x + 2)
+        };
+        let Some(end) = end else {
+            break;
+        };
+        offset = end;
+
+        let opening = if tag.starts_with("{{~") { 3 } else { 2 };
+        let inner = &source[start + opening..end - 2];
+        let inner = inner.strip_suffix('~').unwrap_or(inner);
+        let inner_start = start + opening;
+        if let Some(rest) = inner.strip_prefix('#') {
+            let skip = 1 + usize::from(rest.starts_with(['>', '*']));
+            let (name, params) = split_name(&source[inner_start + skip..inner_start + inner.len()]);
+            let params = inner_start + skip + params.start..inner_start + skip + params.end;
+            open.push(Block {
+                name,
+                span: start..end,
+                params,
+                children: Vec::new(),
+            });
+        } else if let Some(rest) = inner.strip_prefix('/') {
+            let (name, _) = split_name(rest);
+            // the template compiled, so the innermost open block matches
+            while let Some(mut block) = open.pop() {
+                let matched = block.name == name;
+                block.span.end = end;
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(block),
+                    None => roots.push(block),
+                }
+                if matched {
+                    break;
+                }
+            }
+        }
+    }
+    roots
+}
+
+/// Splits the contents of a tag into the helper name and the span of its
+/// trimmed parameters, relative to `inner`
+fn split_name(inner: &str) -> (&str, Range<usize>) {
+    let name_start = inner.len() - inner.trim_start().len();
+    let name_end = inner[name_start..]
+        .find(char::is_whitespace)
+        .map_or(inner.len(), |x| name_start + x);
+    let rest = &inner[name_end..];
+    let params_start = name_end + (rest.len() - rest.trim_start().len());
+    let params_end = inner.trim_end().len().max(params_start);
+    (&inner[name_start..name_end], params_start..params_end)
+}
+
+fn is_provider(name: &str) -> bool {
+    ARMS.iter().any(|(_, providers)| providers.contains(&name))
+}
+
+fn switches_in(blocks: &[Block]) -> Vec<SwitchOutline> {
+    let mut switches = Vec::new();
+    for block in blocks {
+        if is_provider(block.name) {
+            let mut switch = SwitchOutline {
+                helper: block.name.to_owned(),
+                span: block.span.clone(),
+                expression: block.params.clone(),
+                arms: Vec::new(),
+                children: Vec::new(),
+            };
+            collect_arms(&block.children, &mut switch);
+            switches.push(switch);
+        } else {
+            switches.extend(switches_in(&block.children));
+        }
+    }
+    switches
+}
+
+fn collect_arms(blocks: &[Block], switch: &mut SwitchOutline) {
+    for block in blocks {
+        let is_arm = ARMS.iter().any(|(arm, providers)| {
+            *arm == block.name && providers.contains(&switch.helper.as_str())
+        });
+        if is_provider(block.name) {
+            switch
+                .children
+                .extend(switches_in(std::slice::from_ref(block)));
+        } else if is_arm {
+            switch.arms.push(ArmOutline {
+                name: block.name.to_owned(),
+                span: block.span.clone(),
+                params: block.params.clone(),
+            });
+            switch.children.extend(switches_in(&block.children));
+        } else {
+            collect_arms(&block.children, switch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::outline_switches;
+
+    #[test]
+    fn test_outline_switches() {
+        let source = "\
+            {{!-- {{#switch ignored}} --}}\
+            {{#each users}}\
+                {{~#switch  access   ~}}\
+                    {{#case \"admin\" \"}}\"}}\
+                        {{#switch_some email}}{{#some}}x{{/some}}{{/switch_some}}\
+                    {{/case}}\
+                    {{#if @first}}{{#default}}U{{/default}}{{/if}}\
+                {{~/switch~}}\
+            {{/each}}\
+            \\{{#switch escaped}}\
+        ";
+        let outline = outline_switches(source).unwrap();
+        let text = |span: &std::ops::Range<usize>| &source[span.clone()];
+
+        assert_eq!(outline.len(), 1);
+        let switch = &outline[0];
+        assert_eq!(switch.helper, "switch");
+        assert_eq!(text(&switch.expression), "access");
+        assert!(text(&switch.span).starts_with("{{~#switch"));
+        assert!(text(&switch.span).ends_with("{{~/switch~}}"));
+
+        let arms: Vec<_> = switch.arms.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(arms, ["case", "default"]);
+        assert_eq!(text(&switch.arms[0].params), "\"admin\" \"}}\"");
+        assert_eq!(text(&switch.default_arm().unwrap().params), "");
+
+        assert_eq!(switch.children.len(), 1);
+        let nested = &switch.children[0];
+        assert_eq!(nested.helper, "switch_some");
+        assert_eq!(text(&nested.expression), "email");
+        assert_eq!(text(&nested.arms[0].span), "{{#some}}x{{/some}}");
+    }
+
+    #[test]
+    fn test_outline_invalid_template() {
+        assert!(outline_switches("{{#switch a}}").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use handlebars::{
+    Context, Handlebars, Output, RenderContext, RenderError, Renderable, Template, TemplateError,
+};
+
+use serde_json::Value;
+
+/// Overrides Error
+///
+/// Why [`ArmOverrides::set`] rejected an overrides document.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum OverridesError {
+    /// The document does not have the expected shape.
+    InvalidDocument(String),
+    /// A replacement arm failed to compile.
+    Template(TemplateError),
+}
+
+impl fmt::Display for OverridesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverridesError::InvalidDocument(reason) => {
+                write!(f, "invalid overrides document: {reason}")
+            }
+            OverridesError::Template(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for OverridesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OverridesError::Template(e) => Some(e),
+            OverridesError::InvalidDocument(_) => None,
+        }
+    }
+}
+
+/// What to do with a single arm.
+pub(crate) enum ArmOverride<'a> {
+    Keep,
+    Disable,
+    Replace(&'a Template),
+}
+
+/// The overrides of the arms of one named switch.
+#[derive(Debug, Default)]
+pub(crate) struct SwitchOverrides {
+    cases: HashMap<String, Option<Template>>,
+    default: Option<Option<Template>>,
+}
+
+impl SwitchOverrides {
+    /// Finds the override for a `{{#case}}` with the given params.
+    pub(crate) fn for_case<'a, I>(&self, params: I) -> ArmOverride<'_>
+    where
+        I: IntoIterator<Item = &'a Value>,
+    {
+        params
+            .into_iter()
+            .find_map(|x| self.cases.get(&arm_key(x)))
+            .map_or(ArmOverride::Keep, to_override)
+    }
+
+    /// Finds the override for the `{{#default}}`.
+    pub(crate) fn for_default(&self) -> ArmOverride<'_> {
+        self.default.as_ref().map_or(ArmOverride::Keep, to_override)
+    }
+}
+
+fn to_override(replacement: &Option<Template>) -> ArmOverride<'_> {
+    match replacement {
+        Some(t) => ArmOverride::Replace(t),
+        None => ArmOverride::Disable,
+    }
+}
+
+/// Strings are keyed by their contents, everything else by its JSON
+fn arm_key(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Arm Overrides
+///
+/// Replaces or disables arms of named `{{#switch ... name="..."}}` blocks at
+/// render time, so deployments can swap out the copy of single arms without
+/// forking the whole template. The overrides document maps switch names to
+/// the arms to change:
+///
+/// ```json
+/// {
+///   "pricing": {
+///     "cases": { "premium": "Call {{sales_phone}} for a quote", "trial": null },
+///     "default": "Contact us"
+///   }
+/// }
+/// ```
+///
+/// `cases` is keyed by the `{{#case}}` value, with strings written as is and
+/// other values as JSON. A string replaces the body of the arm and is
+/// rendered as a template in its place, seeing the same `this`, `@index`,
+/// block params, `../`, and `@root` the arm would, while `null` disables
+/// the arm so it never matches. The handle is cheap to clone and every clone
+/// shares the same document, so it can be swapped while rendering.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{ArmOverrides, SwitchHelper};
+///
+/// let overrides = ArmOverrides::new();
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(SwitchHelper::new().with_overrides(overrides.clone())),
+/// );
+///
+/// let tpl = "\
+///     {{#switch plan name=\"pricing\"}}\
+///         {{#case \"premium\"}}$99{{/case}}\
+///         {{#case \"trial\"}}Free for 30 days{{/case}}\
+///         {{#default}}$9{{/default}}\
+///     {{/switch}}\
+/// ";
+///
+/// overrides
+///     .set(&json!({"pricing": {"cases": {"premium": "{{vendor}} pricing", "trial": null}}}))
+///     .unwrap();
+///
+/// let data = json!({"plan": "premium", "vendor": "ACME"});
+/// assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "ACME pricing");
+/// assert_eq!(handlebars.render_template(tpl, &json!({"plan": "trial"})).unwrap(), "$9");
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ArmOverrides {
+    switches: Arc<RwLock<HashMap<String, Arc<SwitchOverrides>>>>,
+}
+
+impl ArmOverrides {
+    /// Creates a handle without any overrides.
+    pub fn new() -> ArmOverrides {
+        ArmOverrides::default()
+    }
+
+    /// Replaces all overrides with the ones in `document`.
+    pub fn set(&self, document: &Value) -> Result<(), OverridesError> {
+        let switches = parse_document(document)?;
+        *self
+            .switches
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = switches;
+        Ok(())
+    }
+
+    /// Removes all overrides.
+    pub fn clear(&self) {
+        self.switches
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+
+    pub(crate) fn get(&self, switch: &str) -> Option<Arc<SwitchOverrides>> {
+        self.switches
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(switch)
+            .cloned()
+    }
+}
+
+fn parse_document(
+    document: &Value,
+) -> Result<HashMap<String, Arc<SwitchOverrides>>, OverridesError> {
+    let invalid = |reason: &str| OverridesError::InvalidDocument(reason.to_owned());
+    let compile = |replacement: &Value| match replacement {
+        Value::Null => Ok(None),
+        Value::String(source) => Template::compile(source)
+            .map(Some)
+            .map_err(OverridesError::Template),
+        _ => Err(invalid("an arm must be a template string or null")),
+    };
+
+    let switches = document
+        .as_object()
+        .ok_or_else(|| invalid("expected an object of switch names"))?;
+    switches
+        .iter()
+        .map(|(name, arms)| {
+            let arms = arms
+                .as_object()
+                .ok_or_else(|| invalid("expected an object with `cases` and `default`"))?;
+            let mut overrides = SwitchOverrides::default();
+            for (key, value) in arms {
+                match key.as_str() {
+                    "cases" => {
+                        let cases = value
+                            .as_object()
+                            .ok_or_else(|| invalid("`cases` must be an object"))?;
+                        for (case, replacement) in cases {
+                            overrides.cases.insert(case.clone(), compile(replacement)?);
+                        }
+                    }
+                    "default" => overrides.default = Some(compile(value)?),
+                    _ => return Err(invalid("only `cases` and `default` can be overridden")),
+                }
+            }
+            Ok((name.clone(), Arc::new(overrides)))
+        })
+        .collect()
+}
+
+/// Renders a replacement arm where the arm it replaces would have rendered,
+/// so it sees the same blocks, `@root`, and partials
+pub(crate) fn render_replacement<'reg: 'rc, 'rc>(
+    replacement: &Template,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> Result<(), RenderError> {
+    // the replacement is only borrowed for this render, shorter than `rc`
+    // lives, so it renders in a copy of `rc` narrowed to that borrow
+    let mut replacement_rc: RenderContext<'reg, '_> = rc.clone();
+    replacement.render(r, ctx, &mut replacement_rc, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArmOverride, ArmOverrides, OverridesError};
+
+    #[test]
+    fn test_set_overrides() {
+        let overrides = ArmOverrides::new();
+        let document = json!({"s": {"cases": {"a": "A", "1": null}, "default": "D"}});
+        assert!(overrides.set(&document).is_ok());
+
+        let s = overrides.get("s").unwrap();
+        assert!(matches!(s.for_case([&json!("a")]), ArmOverride::Replace(_)));
+        assert!(matches!(
+            s.for_case([&json!("b"), &json!(1)]),
+            ArmOverride::Disable
+        ));
+        assert!(matches!(s.for_case([&json!("1")]), ArmOverride::Disable));
+        assert!(matches!(s.for_case([&json!("b")]), ArmOverride::Keep));
+        assert!(matches!(s.for_default(), ArmOverride::Replace(_)));
+        assert!(overrides.get("t").is_none());
+
+        overrides.clear();
+        assert!(overrides.get("s").is_none());
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        let overrides = ArmOverrides::new();
+        let invalid = [
+            json!([]),
+            json!({"s": 1}),
+            json!({"s": {"cases": {"a": 1}}}),
+        ];
+        for document in invalid {
+            let result = overrides.set(&document);
+            assert!(matches!(result, Err(OverridesError::InvalidDocument(_))));
+        }
+        let result = overrides.set(&json!({"s": {"default": "{{#if}}"}}));
+        assert!(matches!(result, Err(OverridesError::Template(_))));
+    }
+}
@@ -0,0 +1,88 @@
+use phonenumber::{Mode, PhoneNumber};
+
+/// Parses a phone number in international format, written with `+` or `00`
+/// before the country code, if the metadata of libphonenumber finds it
+/// valid. Numbers without a country code are not parsed, since their country
+/// cannot be known.
+fn parse_international(s: &str) -> Option<PhoneNumber> {
+    let s = s.trim();
+    let number = match s.strip_prefix("00") {
+        Some(rest) => phonenumber::parse(None, format!("+{rest}")),
+        None if s.starts_with('+') => phonenumber::parse(None, s),
+        None => return None,
+    };
+    number.ok().filter(phonenumber::is_valid)
+}
+
+/// Normalizes a phone number in international format to E.164, so
+/// `"+44 (0)20 7946-0958"`, `"0044 20 7946 0958"`, and `"+442079460958"`
+/// all become `"+442079460958"`.
+pub(crate) fn to_e164(s: &str) -> Option<String> {
+    let number = parse_international(s)?;
+    Some(number.format().mode(Mode::E164).to_string())
+}
+
+/// Compares two strings as phone numbers when both are in international
+/// format, returning `None` otherwise.
+pub(crate) fn same_phone(a: &str, b: &str) -> Option<bool> {
+    Some(to_e164(a)? == to_e164(b)?)
+}
+
+/// Matches a phone number against a country calling code like `"+44"` or
+/// `"44"`.
+pub(crate) fn has_country_code(number: &str, code: &str) -> bool {
+    let code = code.trim().trim_start_matches('+');
+    let Ok(code) = code.parse::<u16>() else {
+        return false;
+    };
+    parse_international(number).is_some_and(|x| x.code().value() == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_country_code, same_phone, to_e164};
+
+    #[test]
+    fn test_to_e164() {
+        assert_eq!(
+            to_e164("+44 (0)20 7946-0958").as_deref(),
+            Some("+442079460958")
+        );
+        assert_eq!(
+            to_e164("0044 20 7946 0958").as_deref(),
+            Some("+442079460958")
+        );
+        assert_eq!(
+            to_e164("+1 (415) 555.0100").as_deref(),
+            Some("+14155550100")
+        );
+        assert_eq!(to_e164("020 7946 0958"), None);
+        assert_eq!(to_e164("+0 123 456 789"), None);
+        assert_eq!(to_e164("+1 23"), None);
+        assert_eq!(to_e164("+44 20 7946"), None);
+        // letters stand for the digits of a keypad
+        assert_eq!(to_e164("+1 415 CALL NOW").as_deref(), Some("+14152255669"));
+    }
+
+    #[test]
+    fn test_same_phone() {
+        assert_eq!(
+            same_phone("+1 415 555 0100", "001-415-555-0100"),
+            Some(true)
+        );
+        assert_eq!(
+            same_phone("+1 415 555 0100", "+1 415 555 0101"),
+            Some(false)
+        );
+        assert_eq!(same_phone("+1 415 555 0100", "415 555 0100"), None);
+    }
+
+    #[test]
+    fn test_has_country_code() {
+        assert!(has_country_code("+1 415 555 0100", "+1"));
+        assert!(has_country_code("0044 20 7946 0958", "44"));
+        assert!(!has_country_code("+44 20 7946 0958", "+1"));
+        assert!(!has_country_code("+44 20 7946 0958", "+"));
+        assert!(!has_country_code("020 7946 0958", "+44"));
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use handlebars::{Helper, HelperResult, Output, RenderError, RenderErrorReason, StringOutput};
+use serde_json::Value;
+
+use crate::join::Joiner;
+use crate::maxlen::MaxLen;
+
+/// Rewrites the rendered output of an arm
+pub(crate) type PostProcess = Arc<dyn Fn(String) -> Result<String, RenderError> + Send + Sync>;
+
+/// The post-processors of a switch helper, looked up by the `label="..."`
+/// hash parameter of an arm
+#[derive(Clone, Default)]
+pub(crate) struct PostProcessors {
+    labeled: HashMap<String, PostProcess>,
+    unlabeled: Option<PostProcess>,
+}
+
+impl PostProcessors {
+    pub(crate) fn insert(&mut self, label: Option<String>, f: PostProcess) {
+        match label {
+            Some(label) => {
+                self.labeled.insert(label, f);
+            }
+            None => self.unlabeled = Some(f),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.labeled.is_empty() && self.unlabeled.is_none()
+    }
+
+    /// Finds the post-processor for the arm `h` of the `helper`, if any
+    fn for_arm(
+        &self,
+        helper: &'static str,
+        h: &Helper,
+    ) -> Result<Option<&PostProcess>, RenderError> {
+        match h.hash_get("label").map(|x| x.value()) {
+            None => Ok(self.unlabeled.as_ref()),
+            Some(Value::String(label)) => Ok(self.labeled.get(label)),
+            Some(_) => Err(RenderErrorReason::HashTypeMismatchForName(
+                helper,
+                "label".to_owned(),
+                "string".to_owned(),
+            )
+            .into()),
+        }
+    }
+}
+
+/// Renders an arm of the `helper`, through its post-processor if it has one
+/// and then its length cap, falling back to the cap of the `{{#switch}}`.
+/// The output goes to the `joiner` of the `{{#switch}}` if it has one.
+pub(crate) fn render_post_processed(
+    post: Option<&PostProcessors>,
+    max_len: Option<MaxLen>,
+    joiner: Option<&Joiner>,
+    helper: &'static str,
+    h: &Helper,
+    out: &mut dyn Output,
+    render: impl FnOnce(&mut dyn Output) -> HelperResult,
+) -> HelperResult {
+    let f = post.map(|x| x.for_arm(helper, h)).transpose()?.flatten();
+    let max_len = MaxLen::from_hash(helper, h)?.or(max_len);
+    if f.is_none() && max_len.is_none() && joiner.is_none() {
+        return render(out);
+    }
+    let mut buffer = StringOutput::new();
+    render(&mut buffer)?;
+    let mut rendered = buffer.into_string()?;
+    if let Some(f) = f {
+        rendered = f(rendered)?;
+    }
+    if let Some(max_len) = max_len {
+        rendered = max_len.apply(helper, rendered)?;
+    }
+    match joiner {
+        Some(joiner) => joiner.push(rendered),
+        None => out.write(&rendered)?,
+    }
+    Ok(())
+}
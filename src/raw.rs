@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+/// Parses the part of the JSON document `json` at the JSON Pointer `ptr`.
+/// The objects and arrays on the way are read as [`RawValue`]s, which are
+/// checked but borrowed from `json`, so only the part at the pointer is
+/// built into a `Value`. Fails when `json` is not valid JSON, and returns
+/// `None` when nothing is at the pointer.
+pub(crate) fn parse_at(json: &str, ptr: &str) -> Result<Option<Value>, serde_json::Error> {
+    let root: &RawValue = serde_json::from_str(json)?;
+    Ok(point_into(root, ptr))
+}
+
+/// Parses the part of the checked document `current` at `ptr`
+fn point_into(mut current: &RawValue, ptr: &str) -> Option<Value> {
+    if !ptr.is_empty() && !ptr.starts_with('/') {
+        return None;
+    }
+    for token in ptr.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        current = match current.get().as_bytes().first()? {
+            b'{' => {
+                let mut members: HashMap<String, &RawValue> =
+                    serde_json::from_str(current.get()).ok()?;
+                members.remove(&token)?
+            }
+            b'[' => {
+                let items: Vec<&RawValue> = serde_json::from_str(current.get()).ok()?;
+                *items.get(parse_index(&token)?)?
+            }
+            _ => return None,
+        };
+    }
+    serde_json::from_str(current.get()).ok()
+}
+
+/// Reads an array index of a JSON Pointer, which has no sign or leading zeros
+fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.starts_with('0') && token.len() > 1) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_at;
+
+    #[test]
+    fn test_parse_at() {
+        let json = r#" {"items": [{"status": "shipped"}, {"a/b": 2, "~": [true]}], "n": 1} "#;
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        for ptr in [
+            "",
+            "/items",
+            "/items/0/status",
+            "/items/1/a~1b",
+            "/items/1/~0/0",
+            "/items/2",
+            "/items/01",
+            "/items/+1",
+            "/n/0",
+            "/missing",
+            "items",
+        ] {
+            let parsed = parse_at(json, ptr).unwrap();
+            assert_eq!(parsed, value.pointer(ptr).cloned(), "{ptr}");
+        }
+        assert!(parse_at("{\"a\": [1,", "/a/0").is_err());
+        assert!(parse_at("{\"a\": 1} x", "/b").is_err());
+        assert_eq!(parse_at("\"text\"", "").unwrap(), Some(json!("text")));
+    }
+}
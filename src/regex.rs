@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A regular expression for the `regex=` parameter of `{{#case}}`, compiled
+/// by the `regex` crate, which matches in time linear in the length of the
+/// value.
+#[cfg(feature = "regex")]
+pub(crate) use ::regex::Regex;
+
+/// Stands in for a regular expression without the `regex` feature, where
+/// no pattern compiles.
+#[cfg(not(feature = "regex"))]
+#[derive(Debug)]
+pub(crate) enum Regex {}
+
+#[cfg(not(feature = "regex"))]
+impl Regex {
+    pub(crate) fn is_match(&self, _: &str) -> bool {
+        match *self {}
+    }
+}
+
+/// The most memory in bytes a compiled pattern may take, bounding patterns
+/// like `a{1000000}`.
+#[cfg(feature = "regex")]
+const MAX_SIZE: usize = 1 << 20;
+
+/// The deepest groups may nest, so parsing cannot overflow the stack.
+#[cfg(feature = "regex")]
+const MAX_NESTING: u32 = 64;
+
+/// The most patterns a [`RegexCache`] keeps before starting over.
+const MAX_CACHED: usize = 256;
+
+/// Compiles `pattern`, describing why it failed otherwise.
+#[cfg(feature = "regex")]
+fn compile(pattern: &str) -> Result<Regex, String> {
+    ::regex::RegexBuilder::new(pattern)
+        .size_limit(MAX_SIZE)
+        .nest_limit(MAX_NESTING)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "regex"))]
+fn compile(_: &str) -> Result<Regex, String> {
+    Err("`regex=` requires the `regex` feature".to_owned())
+}
+
+/// A compiled pattern, or why it failed to compile
+type Compiled = Result<Arc<Regex>, String>;
+
+/// The compiled patterns of `regex=` parameters, by pattern, shared by every
+/// render of a [`SwitchHelper`](crate::SwitchHelper) so each pattern only
+/// compiles once. Holds at most [`MAX_CACHED`] patterns, forgetting them all
+/// when full so templates making up patterns cannot grow it without end.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RegexCache {
+    compiled: Arc<Mutex<HashMap<String, Compiled>>>,
+}
+
+impl RegexCache {
+    pub(crate) fn get(&self, pattern: &str) -> Compiled {
+        let mut compiled = self
+            .compiled
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(regex) = compiled.get(pattern) {
+            return regex.clone();
+        }
+        let regex = compile(pattern).map(Arc::new);
+        if compiled.len() == MAX_CACHED {
+            compiled.clear();
+        }
+        compiled.insert(pattern.to_owned(), regex.clone());
+        regex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RegexCache, MAX_CACHED};
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_limits() {
+        let cache = RegexCache::default();
+        let is_match = |pattern: &str, s: &str| cache.get(pattern).unwrap().is_match(s);
+        let long = "a".repeat(200_000);
+        assert!(!is_match("^a.*z", &long));
+        assert!(is_match("^(a|a)*$", &long));
+        assert!(!is_match("^(a|a)*b", &long));
+        assert!(!is_match("(a*)*b", &long));
+        assert!(is_match("(?i)^HELLO\\b", "hello world"));
+        assert!(cache.get("a{1000000}").is_err());
+        assert!(cache.get(&"(".repeat(100_000)).is_err());
+        assert!(cache
+            .get(&format!("{}a{}", "(".repeat(64), ")".repeat(64)))
+            .is_ok());
+        assert!(cache.get("(a").is_err());
+
+        let cache = RegexCache::default();
+        for i in 0..MAX_CACHED * 2 {
+            assert!(cache.get(&i.to_string()).is_ok());
+        }
+        assert!(cache.compiled.lock().unwrap().len() <= MAX_CACHED);
+    }
+
+    #[cfg(not(feature = "regex"))]
+    #[test]
+    fn test_regex_requires_feature() {
+        let cache = RegexCache::default();
+        assert!(cache.get("^admin-.*").is_err());
+        assert!(cache.compiled.lock().unwrap().len() <= MAX_CACHED);
+    }
+}
@@ -0,0 +1,216 @@
+use handlebars::Handlebars;
+
+use crate::SwitchConfig;
+
+/// Register
+///
+/// Registers every helper of this crate with its default configuration under
+/// its documented name: `switch`, `switch_some`, `switch_result`,
+/// `switch_flags`, `switch_entries`, `switch_ordinal`, `bins`, `cmp`, `arr`,
+/// `obj`, `set`, `capture`, and `slot`. It mirrors the `register` function
+/// of helper collections such as `handlebars_misc_helpers`, so both can be
+/// set up side by side and their helpers used as subexpressions in
+/// `{{#case}}` params. To share options across the switch family, register
+/// through a [`SwitchConfig`] instead.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars_switch::register(&mut handlebars);
+///
+/// let tpl = "{{#switch_some name}}{{#some as |n|}}{{n}}{{/some}}{{/switch_some}}";
+/// assert_eq!(handlebars.render_template(tpl, &json!({"name": "Jo"})).unwrap(), "Jo");
+/// # }
+/// ```
+pub fn register(handlebars: &mut Handlebars) {
+    SwitchConfig::default().register(handlebars);
+}
+
+/// Register With Misc Helpers
+///
+/// Registers the string and JSON helpers of `handlebars_misc_helpers`, such
+/// as `to_lower_case`, `trim`, and `json_query`, followed by every helper of
+/// this crate like [`register`] does, so the former can compute the switch
+/// value and `{{#case}}` params. Both collections define `set`, and the one
+/// of this crate wins, keeping `{{set}}` usable inside arms; the other stays
+/// available as `assign`. To share options across the switch family, call
+/// `handlebars_misc_helpers::register` and then [`SwitchConfig::register`]
+/// instead.
+///
+/// Requires the `misc_helpers` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars_switch::register_with_misc_helpers(&mut handlebars);
+///
+/// let tpl = "\
+///     {{#switch (to_lower_case (trim role))}}\
+///         {{#case \"admin\"}}Admin{{/case}}\
+///         {{#default}}User{{/default}}\
+///     {{/switch}}\
+/// ";
+/// assert_eq!(handlebars.render_template(tpl, &json!({"role": " ADMIN "})).unwrap(), "Admin");
+/// # }
+/// ```
+#[cfg(feature = "misc_helpers")]
+pub fn register_with_misc_helpers(handlebars: &mut Handlebars) {
+    handlebars_misc_helpers::register(handlebars);
+    register(handlebars);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::register;
+    #[cfg(feature = "misc_helpers")]
+    use super::register_with_misc_helpers;
+    use handlebars::{handlebars_helper, Handlebars};
+
+    handlebars_helper!(to_lower_case: |s: str| s.to_lowercase());
+
+    #[test]
+    fn test_register_with_other_helpers() {
+        let tpl = "\
+            {{#switch (to_lower_case role)}}\
+                {{#case (to_lower_case \"ADMIN\")}}Admin{{/case}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        register(&mut handlebars);
+        handlebars.register_helper("to_lower_case", Box::new(to_lower_case));
+
+        let r0 = handlebars.render_template(tpl, &json!({"role": "Admin"}));
+        assert_eq!(r0.unwrap(), "Admin");
+
+        let tpl = "\
+            {{#switch_some 1}}{{#some}}a{{/some}}{{/switch_some}}\
+            {{#switch_result r}}{{#ok}}b{{/ok}}{{/switch_result}}\
+            {{#switch_flags 1}}{{#case \"1\"}}c{{/case}}{{/switch_flags}}\
+            {{#bins 1 edges=e}}{{#case 1}}d{{/case}}{{/bins}}\
+        ";
+        let r1 = handlebars.render_template(tpl, &json!({"r": {"Ok": 1}, "e": [0]}));
+        assert_eq!(r1.unwrap(), "abcd");
+    }
+
+    #[test]
+    fn test_adversarial_templates_do_not_panic() {
+        let templates = [
+            "{{#case}}{{/case}}{{#default}}{{/default}}{{#some}}{{/some}}{{#ok}}{{/ok}}",
+            "{{#switch}}{{/switch}}{{#switch_some}}{{/switch_some}}{{#bins}}{{/bins}}",
+            "{{#switch_result}}{{/switch_result}}{{#switch_flags}}{{/switch_flags}}",
+            "{{#switch v shard=true}}{{/switch}}{{#switch v shard=true}}x{{/switch}}",
+            "{{#switch v shard=1 memo=\"yes\" arrays=3 not=true}}{{#case}}{{/case}}{{/switch}}",
+            "{{#switch v}}{{#case v not=true}}{{else}}{{/case}}{{#default}}{{else}}{{/default}}{{/switch}}",
+            "{{#switch v}}{{#switch v}}{{#case v}}{{#default}}{{/default}}{{/case}}{{/switch}}{{/switch}}",
+            "{{#switch v}}{{#each v}}{{#case this}}{{@index}}{{../v}}{{/case}}{{/each}}{{/switch}}",
+            "{{#bins v edges=v labels=v}}{{/bins}}{{#bins 1 edges=(lookup v 0) labels=v}}{{/bins}}",
+            "{{#bins v edges=n}}{{/bins}}{{#bins n edges=e labels=l}}{{#case 1}}{{/case}}{{/bins}}",
+            "{{#switch_result v ok=1 err=v}}{{#ok as |a b|}}{{a}}{{b}}{{/ok}}{{/switch_result}}",
+            "{{#switch_some v}}{{#some as |a|}}{{#none}}{{a}}{{/none}}{{/some}}{{/switch_some}}",
+            "{{#switch_flags v n e l}}{{#case \"1111\"}}{{/case}}{{/switch_flags}}",
+            "{{#switch deep}}{{#case deep}}{{/case}}{{/switch}}",
+            // the parsers of structured strings, against each other's inputs
+            "{{#switch v multi=true units=true colors=true}}{{#each s}}{{#case this}}{{/case}}{{/each}}{{/switch}}",
+            "{{#switch v multi=true currency=true instants=true phones=true}}{{#each s}}{{#case this}}{{/case}}{{/each}}{{/switch}}",
+            "{{#each s}}{{#switch this multi=true units=true colors=true currency=true instants=true phones=true}}{{#case ../v}}{{/case}}{{/switch}}{{/each}}",
+            "{{#switch v multi=true}}{{#each s}}{{#case regex=this}}{{/case}}{{#case \"\" regex=this}}{{/case}}{{/each}}{{/switch}}",
+        ];
+        let values = [
+            json!(null),
+            json!(-1.5),
+            json!("\u{1F600}"),
+            json!([[], {}]),
+            json!({"Ok": {"Err": 1}}),
+            json!("18446744073709551616 KiB"),
+            json!("rgb(999, -1, nan)"),
+            json!("+00 000"),
+            json!("9999-99-99T99:99:99+99:99"),
+            json!("$1e400"),
+        ];
+        let strings = json!([
+            "",
+            " ",
+            "\u{0}",
+            "\u{1F600}\u{301}",
+            "1e400 KiB",
+            "-0.5.5 ms",
+            "1..2 GB",
+            "99999999999999999999999999 h",
+            "#",
+            "#gggggg",
+            "#ffffffffff",
+            "rgb(",
+            "rgba(1e309, 1, 1, 2)",
+            "hsl(-1e308, 200%, -5%)",
+            "+",
+            "00",
+            "+999 9999999999999999999999",
+            "+1 (((((",
+            "2024-02-30T24:00:00Z",
+            "2024-13-01T00:00:00+25:00",
+            "0000-01-01T00:00:00.0000000000000000001Z",
+            "$",
+            "€-",
+            "¥ 9999999999999999999999999.9999",
+            "USD 1,,000",
+            "-$-1",
+            "(a*)*b",
+            "\\",
+            "a{99999999}",
+            "[",
+            "(?P<",
+        ]);
+
+        let mut handlebars = Handlebars::new();
+        register(&mut handlebars);
+        let deep = (0..512).fold(json!(1), |x, _| json!([x]));
+        for tpl in templates {
+            for v in &values {
+                let data = json!({
+                    "v": v, "n": 2, "e": [1, 3], "l": ["a"], "deep": deep, "s": strings
+                });
+                // errors are fine, panics are not
+                let _ = handlebars.render_template(tpl, &data);
+            }
+        }
+    }
+
+    #[cfg(feature = "misc_helpers")]
+    #[test]
+    fn test_register_with_misc_helpers() {
+        let tpl = "\
+            {{#switch (json_query \"user.role\" payload) as |role|}}\
+                {{#case (to_upper_case \"admin\") (trim \" owner \")}}Staff {{role}}{{/case}}\
+                {{#default}}{{set class=\"guest\"}}{{/default}}\
+            {{/switch}}\
+            {{@class}} {{to_lower_case (assign \"x\" \"ignored\")}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        register_with_misc_helpers(&mut handlebars);
+
+        let render = |role: &str| {
+            let data = json!({"payload": {"user": {"role": role}}});
+            handlebars.render_template(tpl, &data)
+        };
+        assert_eq!(render("ADMIN").unwrap(), "Staff ADMIN ");
+        assert_eq!(render("owner").unwrap(), "Staff owner ");
+        assert_eq!(render("guest").unwrap(), "guest ");
+    }
+}
@@ -0,0 +1,186 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+    Renderable,
+};
+
+use serde_json::Value;
+
+use crate::block::PayloadHelper;
+use crate::vars::{restore_vars, saved_vars};
+
+/// Switch Result Helper
+///
+/// Provides the `{{#switch_result}}` helper to a Handlebars template. It
+/// branches on result-shaped values the way serde serializes a `Result`,
+/// rendering the `{{#ok}}` arm for `{"Ok": …}` and the `{{#err}}` arm for
+/// `{"Err": …}`. Both arms can bind the payload with `as |v|`. Any other value
+/// renders neither arm.
+///
+/// The tags default to `Ok` and `Err` and can be changed with
+/// [`SwitchResultHelper::with_tags`] or per block with the `ok="..."` and
+/// `err="..."` hash parameters.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchResultHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch_result", Box::new(SwitchResultHelper::new()));
+///
+/// let tpl = "\
+///     {{#switch_result payment}}\
+///         {{#ok as |id|}}Paid ({{id}}){{/ok}}\
+///         {{#err as |e|}}Failed: {{e.reason}}{{/err}}\
+///     {{/switch_result}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"payment": {"Ok": 42}})).unwrap(),
+///     "Paid (42)"
+/// );
+///
+/// assert_eq!(
+///     handlebars
+///         .render_template(tpl, &json!({"payment": {"Err": {"reason": "declined"}}}))
+///         .unwrap(),
+///     "Failed: declined"
+/// );
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SwitchResultHelper {
+    ok_tag: String,
+    err_tag: String,
+}
+
+impl Default for SwitchResultHelper {
+    fn default() -> SwitchResultHelper {
+        SwitchResultHelper::with_tags("Ok", "Err")
+    }
+}
+
+impl SwitchResultHelper {
+    /// Creates a result helper using serde's `Ok` and `Err` tags.
+    pub fn new() -> SwitchResultHelper {
+        SwitchResultHelper::default()
+    }
+
+    /// Creates a result helper using custom tags, e.g. `success`/`error`.
+    pub fn with_tags(ok_tag: &str, err_tag: &str) -> SwitchResultHelper {
+        SwitchResultHelper {
+            ok_tag: ok_tag.to_owned(),
+            err_tag: err_tag.to_owned(),
+        }
+    }
+}
+
+impl HelperDef for SwitchResultHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        // Read in the result variable or expression
+        let param = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("switch_result", 0))?;
+
+        let tag = |name, default: &str| {
+            h.hash_get(name)
+                .and_then(|x| x.value().as_str())
+                .unwrap_or(default)
+                .to_owned()
+        };
+        let ok_tag = tag("ok", &self.ok_tag);
+        let err_tag = tag("err", &self.err_tag);
+
+        // Only a single-key object carrying one of the tags is a result
+        let payload = |tag: &str| match param.value() {
+            Value::Object(map) if map.len() == 1 => map.get(tag).cloned(),
+            _ => None,
+        };
+
+        // Add the `{{#ok}}` and `{{#err}}` helpers within the block
+        let mut local_rc = rc.clone();
+        local_rc.register_local_helper(
+            "ok",
+            Box::new(PayloadHelper {
+                payload: payload(&ok_tag),
+            }),
+        );
+        local_rc.register_local_helper(
+            "err",
+            Box::new(PayloadHelper {
+                payload: payload(&err_tag),
+            }),
+        );
+
+        // Render the `{{#switch_result}}` block
+        let result = match h.template() {
+            Some(t) => t.render(r, ctx, &mut local_rc, out),
+            None => Ok(()),
+        };
+        restore_vars(rc, saved_vars(&local_rc));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchResultHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_switch_result() {
+        let tpl = "\
+            {{#switch_result res}}\
+                {{#ok as |v|}}ok {{v}}{{/ok}}\
+                {{#err as |e|}}err {{e}}{{/err}}\
+            {{/switch_result}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch_result", Box::new(SwitchResultHelper::new()));
+
+        let r0 = handlebars.render_template(tpl, &json!({"res": {"Ok": 1}}));
+        assert_eq!(r0.unwrap(), "ok 1");
+        let r1 = handlebars.render_template(tpl, &json!({"res": {"Err": "no"}}));
+        assert_eq!(r1.unwrap(), "err no");
+        let r2 = handlebars.render_template(tpl, &json!({"res": {"Ok": 1, "Err": 2}}));
+        assert_eq!(r2.unwrap(), "");
+        let r3 = handlebars.render_template(tpl, &json!({}));
+        assert_eq!(r3.unwrap(), "");
+    }
+
+    #[test]
+    fn test_custom_tags() {
+        let tpl = "\
+            {{#switch_result res ok=\"success\"}}\
+                {{#ok}}yes{{/ok}}\
+                {{#err}}no{{/err}}\
+            {{/switch_result}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch_result",
+            Box::new(SwitchResultHelper::with_tags("data", "error")),
+        );
+
+        let r0 = handlebars.render_template(tpl, &json!({"res": {"success": true}}));
+        assert_eq!(r0.unwrap(), "yes");
+        let r1 = handlebars.render_template(tpl, &json!({"res": {"error": true}}));
+        assert_eq!(r1.unwrap(), "no");
+        let r2 = handlebars.render_template(tpl, &json!({"res": {"data": true}}));
+        assert_eq!(r2.unwrap(), "");
+    }
+}
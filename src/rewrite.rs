@@ -0,0 +1,128 @@
+/// Expand Inverse Cases
+///
+/// Rewrites inverse arms like `{{^case "admin"}}...{{/case}}`, which the
+/// handlebars parser rejects, into the equivalent
+/// `{{#case "admin" not=true}}...{{/case}}` so the template can be
+/// registered. Everything else in the source is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars_switch::expand_inverse_cases;
+///
+/// assert_eq!(
+///     expand_inverse_cases("{{#switch role}}{{^case \"admin\"}}User{{/case}}{{/switch}}"),
+///     "{{#switch role}}{{#case \"admin\" not=true}}User{{/case}}{{/switch}}"
+/// );
+/// ```
+pub fn expand_inverse_cases(source: &str) -> String {
+    let mut expanded = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let (before, tag) = rest.split_at(start);
+        expanded.push_str(before);
+
+        let escaped = before.ends_with('\\');
+        let opening = if tag.starts_with("{{~") { 3 } else { 2 };
+        let is_inverse_case = !escaped
+            && tag[opening..].strip_prefix("^case").is_some_and(|x| {
+                x.starts_with(|c: char| c.is_whitespace() || c == '}' || c == '~')
+            });
+
+        match tag_end(tag).filter(|_| is_inverse_case) {
+            Some(end) => {
+                let closing = if tag[..end].ends_with('~') {
+                    end - 1
+                } else {
+                    end
+                };
+                expanded.push_str(&tag[..opening]);
+                expanded.push('#');
+                expanded.push_str(tag[opening + 1..closing].trim_end());
+                expanded.push_str(" not=true");
+                expanded.push_str(&tag[closing..end + 2]);
+                rest = &tag[end + 2..];
+            }
+            None => {
+                expanded.push_str("{{");
+                rest = &tag[2..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Finds the offset of the `}}` closing the tag at the start of `tag`,
+/// skipping over string literals
+pub(crate) fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    let bytes = tag.as_bytes();
+    let mut i = 2;
+    while i < bytes.len() {
+        match (quote, bytes[i]) {
+            (Some(q), b) if b == q => quote = None,
+            (None, b @ (b'"' | b'\'')) => quote = Some(b),
+            (None, b'}') if bytes.get(i + 1) == Some(&b'}') => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_inverse_cases;
+
+    #[test]
+    fn test_malformed_sources_do_not_panic() {
+        let pieces = [
+            "{{",
+            "}}",
+            "~",
+            "^case",
+            "#case",
+            "\\",
+            "\"",
+            "'",
+            "é",
+            " ",
+            "{{{{",
+            "!--",
+            "{{#if (eq x 1)}}",
+            "{{else if (eq x 2)}}",
+            "{{/if}}",
+            "\n",
+        ];
+        for a in pieces {
+            for b in pieces {
+                for c in pieces {
+                    for d in pieces {
+                        let source = [a, b, c, d].concat();
+                        let _ = expand_inverse_cases(&source);
+                        let _ = crate::outline_switches(&source);
+                        let _ = crate::eq_chains_to_switches(&source);
+                        let _ = crate::switches_to_if_chains(&source);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_expand_inverse_cases() {
+        assert_eq!(
+            expand_inverse_cases("{{~^case \"}}\" x ~}}a{{/case}}"),
+            "{{~#case \"}}\" x not=true~}}a{{/case}}"
+        );
+        assert_eq!(
+            expand_inverse_cases("{{#if a}}{{^}}{{/if}}{{^cases}}\\{{^case 1}}"),
+            "{{#if a}}{{^}}{{/if}}{{^cases}}\\{{^case 1}}"
+        );
+        assert_eq!(
+            expand_inverse_cases("{{^case 1}}{{^case"),
+            "{{#case 1 not=true}}{{^case"
+        );
+    }
+}
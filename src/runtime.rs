@@ -0,0 +1,108 @@
+use handlebars::{
+    Context, Handlebars, RenderContext, RenderError, RenderErrorReason, Renderable, StringOutput,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Render With Runtime
+///
+/// Renders a registered template like `Handlebars::render` while exposing
+/// `runtime` as the `@runtime` variable, so values scoped to a single render
+/// (output format, experiment bucket, tenant, ...) can be switched on without
+/// merging them into the data. Handlebars does not follow paths into `@`
+/// variables, so fields are read with the built-in `lookup` helper:
+///
+/// ```text
+/// {{#switch (lookup @runtime "format")}}...{{/switch}}
+/// ```
+///
+/// `@runtime` is set on the root scope and carried into the blocks of the
+/// switch helper family. Block helpers that start a fresh scope for `@`
+/// variables, such as `{{#each}}`, still reach it as `@../runtime`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{render_with_runtime, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars
+///     .register_template_string(
+///         "greeting",
+///         "{{#switch (lookup @runtime \"format\")}}{{#case \"html\"}}<p>{{name}}</p>{{/case}}{{#default}}{{name}}{{/default}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// let data = json!({"name": "Ada"});
+/// assert_eq!(
+///     render_with_runtime(&handlebars, "greeting", &data, &json!({"format": "html"})).unwrap(),
+///     "<p>Ada</p>"
+/// );
+/// assert_eq!(
+///     render_with_runtime(&handlebars, "greeting", &data, &json!({"format": "text"})).unwrap(),
+///     "Ada"
+/// );
+/// # }
+/// ```
+pub fn render_with_runtime<T>(
+    handlebars: &Handlebars,
+    name: &str,
+    data: &T,
+    runtime: &Value,
+) -> Result<String, RenderError>
+where
+    T: Serialize,
+{
+    let template = handlebars
+        .get_template(name)
+        .ok_or_else(|| RenderErrorReason::TemplateNotFound(name.to_owned()))?;
+    let ctx = Context::wraps(data)?;
+
+    let mut rc = RenderContext::new(template.name.as_ref());
+    rc.set_recursive_lookup(handlebars.recursive_lookup());
+    if let Some(block) = rc.block_mut() {
+        block.set_local_var("runtime", runtime.clone());
+    }
+
+    let mut out = StringOutput::new();
+    template.render(handlebars, &ctx, &mut rc, &mut out)?;
+    Ok(out.into_string()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_with_runtime;
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_runtime_in_nested_blocks() {
+        let tpl = "\
+            {{#switch (lookup @runtime \"tenant\")}}\
+                {{#case \"acme\"}}\
+                    {{#switch (lookup @runtime \"bucket\")}}{{#case 1}}A1{{/case}}{{/switch}}\
+                {{/case}}\
+            {{/switch}}\
+            {{#each items}}\
+                {{#switch (lookup @../runtime \"bucket\")}}{{#case 1}} {{this}}{{/case}}{{/switch}}\
+            {{/each}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let runtime = json!({"tenant": "acme", "bucket": 1});
+        let rendered = render_with_runtime(&handlebars, "tpl", &json!({"items": ["x"]}), &runtime);
+        assert_eq!(rendered.unwrap(), "A1 x");
+
+        assert!(render_with_runtime(&handlebars, "missing", &json!({}), &runtime).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use serde_json::Value;
+
+/// Sentinel
+///
+/// A named escape hatch that a `{{#case}}` lists as `@NAME`, which matches
+/// the switch values its test accepts instead of being compared as a value.
+/// Sentinels are registered with [`SwitchHelper::with_sentinel`].
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{Sentinel, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(
+///         SwitchHelper::new()
+///             .with_sentinel("NONE", Sentinel::none())
+///             .with_sentinel("LIST", Sentinel::new(serde_json::Value::is_array)),
+///     ),
+/// );
+///
+/// let tpl = "\
+///     {{#switch tags}}\
+///         {{#case @NONE}}untagged{{/case}}\
+///         {{#case @LIST}}{{#each tags}}#{{this}} {{/each}}{{/case}}\
+///         {{#default}}#{{tags}}{{/default}}\
+///     {{/switch}}\
+/// ";
+///
+/// assert_eq!(handlebars.render_template(tpl, &json!({})).unwrap(), "untagged");
+/// assert_eq!(handlebars.render_template(tpl, &json!({"tags": ["a", "b"]})).unwrap(), "#a #b ");
+/// assert_eq!(handlebars.render_template(tpl, &json!({"tags": "a"})).unwrap(), "#a");
+/// # }
+/// ```
+///
+/// [`SwitchHelper::with_sentinel`]: crate::SwitchHelper::with_sentinel
+#[derive(Clone)]
+pub struct Sentinel(Arc<dyn Fn(&Value) -> bool + Send + Sync>);
+
+impl Sentinel {
+    /// Creates a sentinel matching the switch values for which `f` is `true`.
+    pub fn new<F>(f: F) -> Sentinel
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        Sentinel(Arc::new(f))
+    }
+
+    /// Creates a sentinel matching every switch value.
+    pub fn any() -> Sentinel {
+        Sentinel::new(|_| true)
+    }
+
+    /// Creates a sentinel matching only a `null` or missing switch value.
+    pub fn none() -> Sentinel {
+        Sentinel::new(Value::is_null)
+    }
+
+    pub(crate) fn matches(&self, value: &Value) -> bool {
+        (self.0)(value)
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+
+/// The global helpers the arms of a switch shadow, by the name of the arm.
+///
+/// Inside a `{{#switch}}` block its arms are local helpers, which take
+/// priority over global helpers of the same name, so a `{{default name "-"}}`
+/// meant for a global `default` helper would reach the `{{#default}}` arm
+/// instead. Arms called without a block forward to the helper given for their
+/// name, or fail the render saying so, rather than rendering nothing.
+#[derive(Clone, Default)]
+pub(crate) struct Shadowed(Arc<HashMap<String, Arc<dyn HelperDef + Send + Sync>>>);
+
+impl Shadowed {
+    pub(crate) fn insert(&mut self, name: &str, helper: Arc<dyn HelperDef + Send + Sync>) {
+        Arc::make_mut(&mut self.0).insert(name.to_owned(), helper);
+    }
+
+    /// Renders the arm `name` called as `{{name ...}}`
+    pub(crate) fn call<'reg: 'rc, 'rc>(
+        &self,
+        name: &str,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        match self.0.get(name) {
+            Some(helper) => helper.call(h, r, ctx, rc, out),
+            None => Err(shadowing_error(name)),
+        }
+    }
+
+    /// Evaluates the arm `name` called as the subexpression `(name ...)`
+    pub(crate) fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        name: &str,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        match self.0.get(name) {
+            Some(helper) => helper.call_inner(h, r, ctx, rc),
+            None => Err(shadowing_error(name)),
+        }
+    }
+}
+
+fn shadowing_error(name: &str) -> RenderError {
+    RenderErrorReason::Other(format!(
+        "`{name}` is called without a block inside a switch, where it names the \
+        `{{{{#{name}}}}}` arm and shadows any helper registered as `{name}`; pass that \
+        helper to `SwitchHelper::with_shadowed_helper` to call it there"
+    ))
+    .into()
+}
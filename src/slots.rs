@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+    Renderable, StringOutput,
+};
+
+use serde_json::Value;
+
+use crate::vars::set_var;
+
+/// Slots
+///
+/// Collects the output that `{{#slot}}` blocks direct into named buffers, so
+/// the host can place it after the render, like `<head>` tags an arm needs
+/// in a page layout. The handle is cheap to clone and every clone shares the
+/// same buffers.
+#[derive(Clone, Debug, Default)]
+pub struct Slots {
+    buffers: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl Slots {
+    /// Creates a handle without any filled slots.
+    pub fn new() -> Slots {
+        Slots::default()
+    }
+
+    /// Returns the output directed into the slot `name` so far.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.buffers().get(name).cloned()
+    }
+
+    /// Returns every filled slot by name, leaving the handle empty.
+    pub fn take(&self) -> BTreeMap<String, String> {
+        mem::take(&mut self.buffers())
+    }
+
+    fn append(&self, name: &str, output: &str) {
+        self.buffers()
+            .entry(name.to_owned())
+            .or_default()
+            .push_str(output);
+    }
+
+    fn buffers(&self) -> MutexGuard<'_, BTreeMap<String, String>> {
+        self.buffers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Slot Helper
+///
+/// Provides the `{{#slot}}` helper to a Handlebars template. It renders its
+/// block into the slot named by its param instead of the output, appending
+/// to whatever earlier blocks put there. Filled slots are readable later in
+/// the template through the `@slots` variable, which is carried out of
+/// switches like the variables of [`SetHelper`](crate::SetHelper), and by
+/// the host through the [`Slots`] the helper was built with.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{SlotHelper, Slots, SwitchHelper};
+///
+/// let slots = Slots::new();
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars.register_helper("slot", Box::new(SlotHelper::with_slots(slots.clone())));
+///
+/// let tpl = "\
+///     {{#switch page}}\
+///         {{#case \"chart\"}}\
+///             {{#slot \"head\"}}<script src=\"chart.js\"></script>{{/slot}}\
+///             <canvas></canvas>\
+///         {{/case}}\
+///     {{/switch}}\
+///     <footer>{{{lookup @slots \"head\"}}}</footer>\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"page": "chart"})).unwrap(),
+///     "<canvas></canvas><footer><script src=\"chart.js\"></script></footer>"
+/// );
+/// assert_eq!(slots.get("head").unwrap(), "<script src=\"chart.js\"></script>");
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SlotHelper {
+    slots: Option<Slots>,
+}
+
+impl SlotHelper {
+    /// Creates a slot helper whose slots are only readable in the template.
+    pub fn new() -> SlotHelper {
+        SlotHelper::default()
+    }
+
+    /// Creates a slot helper that also collects its slots into `slots`.
+    pub fn with_slots(slots: Slots) -> SlotHelper {
+        SlotHelper { slots: Some(slots) }
+    }
+}
+
+impl HelperDef for SlotHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("slot", 0))?;
+        let name = name.value().as_str().ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName("slot", "0".to_owned(), "string".to_owned())
+        })?;
+
+        let mut buffer = StringOutput::new();
+        if let Some(t) = h.template() {
+            t.render(r, ctx, rc, &mut buffer)?;
+        }
+        let output = buffer.into_string()?;
+        if let Some(slots) = &self.slots {
+            slots.append(name, &output);
+        }
+
+        let mut filled = match rc.block().and_then(|block| block.get_local_var("slots")) {
+            Some(Value::Object(filled)) => filled.clone(),
+            _ => Default::default(),
+        };
+        let slot = filled.get(name).and_then(Value::as_str).unwrap_or_default();
+        filled.insert(name.to_owned(), Value::String(format!("{slot}{output}")));
+        set_var(rc, "slot", "slots", Value::Object(filled))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SlotHelper, Slots};
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_slots() {
+        let tpl = "\
+            {{#each items}}\
+                {{#switch this}}\
+                    {{#case \"a\"}}{{#slot \"head\"}}[a]{{/slot}}{{#slot \"foot\"}}(a){{/slot}}A{{/case}}\
+                    {{#default}}{{#slot \"head\"}}[{{this}}]{{/slot}}?{{/default}}\
+                {{/switch}}\
+                {{lookup @slots \"head\"}}\
+            {{/each}}\
+        ";
+
+        let slots = Slots::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("slot", Box::new(SlotHelper::with_slots(slots.clone())));
+
+        let data = json!({"items": ["a", "b"]});
+        assert_eq!(
+            handlebars.render_template(tpl, &data).unwrap(),
+            "A[a]?[a][b]"
+        );
+        assert_eq!(slots.get("head").unwrap(), "[a][b]");
+        let taken = slots.take();
+        assert_eq!(taken["foot"], "(a)");
+        assert!(slots.get("head").is_none());
+
+        let invalid = "{{#slot 1}}{{/slot}}";
+        assert!(handlebars.render_template(invalid, &data).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use handlebars::Template;
+use serde_json::Value;
+
+/// Sourced Arm
+///
+/// An arm of a named switch kept outside of the template, as returned by an
+/// [`ArmSource`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourcedArm {
+    /// The values the arm matches, like the params of a `{{#case}}`, or none
+    /// for the arm that renders when no other does, like a `{{#default}}`.
+    pub values: Vec<Value>,
+    /// The body of the arm, rendered as a template against the current
+    /// `this`.
+    pub body: String,
+}
+
+impl SourcedArm {
+    /// Creates an arm rendering `body` for any of `values`.
+    pub fn case<I, V>(values: I, body: &str) -> SourcedArm
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        SourcedArm {
+            values: values.into_iter().map(Into::into).collect(),
+            body: body.to_owned(),
+        }
+    }
+
+    /// Creates an arm rendering `body` when no other arm matches.
+    pub fn default_arm(body: &str) -> SourcedArm {
+        SourcedArm {
+            values: Vec::new(),
+            body: body.to_owned(),
+        }
+    }
+}
+
+/// Arm Source
+///
+/// Supplies the arms of a `{{#switch ... source="cms" name="..."}}` from
+/// outside of the template, such as a database or a CMS, so the copy of each
+/// branch can be edited without deploying templates. A source is registered
+/// under the name `source=` refers to with
+/// [`SwitchHelper::with_arm_source`](crate::SwitchHelper::with_arm_source)
+/// and asked for the arms of a switch by its `name="..."` on every render.
+///
+/// The first sourced arm listing a value that matches the switch value
+/// renders, compared like `{{#case}}` values, or else the sourced arm
+/// without values. When the source has no arms for the switch, or none of
+/// them applies, the arms in the template render instead, so a template
+/// keeps working while its source is unreachable. Wrap slow sources in a
+/// [`CachedArmSource`].
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{ArmSource, SourcedArm, SwitchHelper};
+///
+/// struct Cms;
+///
+/// impl ArmSource for Cms {
+///     fn arms(&self, switch: &str) -> Option<Vec<SourcedArm>> {
+///         match switch {
+///             "page-header" => Some(vec![SourcedArm::case(["blog"], "Latest from {{site}}")]),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(SwitchHelper::new().with_arm_source("cms", Cms)),
+/// );
+///
+/// let tpl = "\
+///     {{#switch page_type source=\"cms\" name=\"page-header\"}}\
+///         {{#default}}Welcome{{/default}}\
+///     {{/switch}}\
+/// ";
+/// let render = |page_type| {
+///     let data = json!({"page_type": page_type, "site": "ACME"});
+///     handlebars.render_template(tpl, &data).unwrap()
+/// };
+/// assert_eq!(render("blog"), "Latest from ACME");
+/// assert_eq!(render("shop"), "Welcome");
+/// # }
+/// ```
+pub trait ArmSource: Send + Sync {
+    /// Returns the arms of the switch named `switch`, in the order they are
+    /// checked, or `None` when the source has none for it or cannot be
+    /// reached.
+    fn arms(&self, switch: &str) -> Option<Vec<SourcedArm>>;
+}
+
+/// The arms fetched for a switch, along with when they were fetched.
+type Fetched = (Instant, Option<Vec<SourcedArm>>);
+
+/// Cached Arm Source
+///
+/// An [`ArmSource`] that asks another source for the arms of each switch at
+/// most once per `ttl`, reusing its answer in between. Answers of `None` are
+/// cached as well, so an unreachable source is not asked on every render.
+pub struct CachedArmSource<S> {
+    source: S,
+    ttl: Duration,
+    cached: Mutex<HashMap<String, Fetched>>,
+}
+
+impl<S: ArmSource> CachedArmSource<S> {
+    /// Caches the arms `source` returns for `ttl`.
+    pub fn new(source: S, ttl: Duration) -> CachedArmSource<S> {
+        CachedArmSource {
+            source,
+            ttl,
+            cached: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forgets every cached answer, for example after the content changed.
+    pub fn clear(&self) {
+        self.cached().clear();
+    }
+
+    fn cached(&self) -> MutexGuard<'_, HashMap<String, Fetched>> {
+        self.cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<S: ArmSource> ArmSource for CachedArmSource<S> {
+    fn arms(&self, switch: &str) -> Option<Vec<SourcedArm>> {
+        if let Some((fetched, arms)) = self.cached().get(switch) {
+            if fetched.elapsed() < self.ttl {
+                return arms.clone();
+            }
+        }
+        // the lock is not held while fetching, so slow sources only hold up
+        // the switches waiting on them
+        let arms = self.source.arms(switch);
+        self.cached()
+            .insert(switch.to_owned(), (Instant::now(), arms.clone()));
+        arms
+    }
+}
+
+/// The compiled bodies of the arms last fetched for each sourced switch, by
+/// source and switch name, so bodies only compile again when they change.
+#[derive(Clone, Default)]
+pub(crate) struct CompiledArms {
+    arms: Arc<Mutex<HashMap<(String, String), CompiledEntry>>>,
+}
+
+type CompiledEntry = (Vec<SourcedArm>, Arc<Vec<Template>>);
+
+/// The compiled arms of a sourced switch along with the index of the one
+/// picked to render.
+pub(crate) type PickedArm = (Arc<Vec<Template>>, usize);
+
+impl CompiledArms {
+    /// Compiles `arms`, reusing the templates compiled for the same arms.
+    /// Arms whose body fails to compile are reported as an error.
+    pub(crate) fn compile(
+        &self,
+        key: (String, String),
+        arms: Vec<SourcedArm>,
+    ) -> Result<Arc<Vec<Template>>, String> {
+        let mut compiled = self
+            .arms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((source, templates)) = compiled.get(&key) {
+            if *source == arms {
+                return Ok(templates.clone());
+            }
+        }
+        let templates = arms
+            .iter()
+            .map(|arm| Template::compile(&arm.body).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let templates = Arc::new(templates);
+        compiled.insert(key, (arms, templates.clone()));
+        Ok(templates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::{ArmSource, CachedArmSource, SourcedArm};
+
+    struct Counting(AtomicUsize);
+
+    impl ArmSource for Counting {
+        fn arms(&self, switch: &str) -> Option<Vec<SourcedArm>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            (switch == "a").then(|| vec![SourcedArm::default_arm("A")])
+        }
+    }
+
+    #[test]
+    fn test_cached_arm_source() {
+        let cached = CachedArmSource::new(Counting(AtomicUsize::new(0)), Duration::from_secs(60));
+        assert_eq!(cached.arms("a"), Some(vec![SourcedArm::default_arm("A")]));
+        assert_eq!(cached.arms("a"), Some(vec![SourcedArm::default_arm("A")]));
+        assert_eq!(cached.arms("b"), None);
+        assert_eq!(cached.arms("b"), None);
+        assert_eq!(cached.source.0.load(Ordering::SeqCst), 2);
+
+        cached.clear();
+        cached.arms("a");
+        assert_eq!(cached.source.0.load(Ordering::SeqCst), 3);
+
+        let uncached = CachedArmSource::new(Counting(AtomicUsize::new(0)), Duration::ZERO);
+        uncached.arms("a");
+        uncached.arms("a");
+        assert_eq!(uncached.source.0.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,50 +1,67 @@
+use handlebars::template::TemplateElement;
 use handlebars::{
-    BlockContext, Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext,
-    RenderErrorReason, Renderable,
+    BlockContext, BlockParams, Context, Handlebars, Helper, HelperDef, HelperResult, JsonTruthy,
+    Output, PathAndJson, RenderContext, RenderError, RenderErrorReason, Renderable, ScopedJson,
+    StringOutput, Template,
 };
 
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
 
-/// Switch Helper
-///
-/// Provides the `{{#switch}}` helper to a Handlebars template.
-///
-/// # Examples
-///
-/// ```
-/// # extern crate handlebars_switch;
-/// # extern crate handlebars;
-/// # #[macro_use] extern crate serde_json;
-/// # fn main() {
-/// use handlebars::Handlebars;
-/// use handlebars_switch::SwitchHelper;
-///
-/// let mut handlebars = Handlebars::new();
-/// handlebars.register_helper("switch", Box::new(SwitchHelper));
-///
-/// let tpl = "\
-///     {{#switch access}}\
-///         {{#case \"admin\"}}Admin{{/case}}\
-///         {{#default}}User{{/default}}\
-///     {{/switch}}\
-/// ";
-///
-/// assert_eq!(
-///     handlebars.render_template(tpl, &json!({"access": "admin"})).unwrap(),
-///     "Admin"
-/// );
-///
-/// assert_eq!(
-///     handlebars.render_template(tpl, &json!({"access": "nobody"})).unwrap(),
-///     "User"
-/// );
-/// # }
-///
+use crate::adaptive::{literal_arms, AdaptiveOrder};
+use crate::audit::{AuditSink, SwitchObservation};
+use crate::block::{arm_block, child_block};
+use crate::budget::Budget;
+use crate::compare::{ArrayEquality, CompareLimits, Comparison};
+use crate::compat::{loose_eq, CompatMode};
+use crate::containers::ContainerPolicy;
+use crate::decode::decode_value;
+use crate::fallback::{render_buffered, ArmErrorPolicy};
+use crate::fields::FieldComparator;
+use crate::forcing::ForcedArm;
+use crate::hash::{redacted, seeded_hash, stable_hash};
+use crate::javascript::{generate, UnsupportedOption};
+use crate::join::Joiner;
+use crate::matchers::{glob_matches, hash_matches, ValueParts};
+use crate::maxlen::MaxLen;
+use crate::memo::{MemoCache, MemoKey};
+use crate::normalize::Normalize;
+use crate::overrides::{render_replacement, ArmOverride, ArmOverrides, SwitchOverrides};
+use crate::postprocess::{render_post_processed, PostProcessors};
+use crate::raw::parse_at;
+use crate::regex::RegexCache;
+use crate::sentinel::Sentinel;
+use crate::shadow::Shadowed;
+use crate::source::{ArmSource, CompiledArms, PickedArm};
+use crate::trace::{ArmKind, ArmTrace, RenderTracer, SwitchTrace};
+use crate::vars::{restore_vars, saved_vars};
 
-#[derive(Clone, Copy)]
-pub struct DefaultHelper;
+#[derive(Clone)]
+pub struct DefaultHelper {
+    overrides: Option<Arc<SwitchOverrides>>,
+    tracer: Option<RenderTracer>,
+    budget: Option<Budget>,
+    compat: CompatMode,
+    post: Option<Arc<PostProcessors>>,
+    max_len: Option<MaxLen>,
+    joiner: Option<Joiner>,
+    multi: bool,
+    shadowed: Shadowed,
+}
 
 impl HelperDef for DefaultHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        self.shadowed.call_inner("default", h, r, ctx, rc)
+    }
+
     fn call<'reg: 'rc, 'rc>(
         &self,
         h: &Helper<'rc>,
@@ -53,15 +70,59 @@ impl HelperDef for DefaultHelper {
         rc: &mut RenderContext<'reg, 'rc>,
         out: &mut dyn Output,
     ) -> HelperResult {
+        if !h.is_block() {
+            return self.shadowed.call("default", h, r, ctx, rc, out);
+        }
+        if let Some(budget) = &self.budget {
+            budget.check()?;
+        }
         if let Some(block) = rc.block_mut() {
+            // loose and multi-match switches render every matching case, so
+            // only a case rendered before it skips the default, while falling
+            // through renders it after any case until a `{{break}}`
+            let settled_by = match self.compat {
+                _ if self.multi => "switch_break",
+                CompatMode::Native => "match",
+                CompatMode::Loose => "switch_break",
+                CompatMode::Fallthrough => "broken",
+            };
             let prev_found = block
-                .get_local_var("match")
+                .get_local_var(settled_by)
                 .and_then(Value::as_bool)
                 .unwrap_or_default();
-            if !prev_found {
-                // fallback to default if no match was found
-                match h.template() {
-                    Some(t) => t.render(r, ctx, rc, out),
+            let fell = take_fallthrough(block);
+            let arm = match &self.overrides {
+                Some(overrides) => overrides.for_default(),
+                None => ArmOverride::Keep,
+            };
+            let matched = (fell || !prev_found) && !matches!(arm, ArmOverride::Disable);
+            if let Some(tracer) = &self.tracer {
+                tracer.arm(ArmTrace {
+                    kind: ArmKind::Default,
+                    values: Vec::new(),
+                    label: arm_label(h),
+                    matched,
+                });
+            }
+            if matched {
+                // fallback to default if no match was found, and claim the
+                // match so no later arm renders as well
+                block.set_local_var("match", json!(true));
+                block.set_local_var("defaulted", json!(true));
+                if is_truthy_hash(h, "fallthrough") {
+                    block.set_local_var("fallthrough", json!(true));
+                }
+                render_post_processed(
+                    self.post.as_deref(),
+                    self.max_len,
+                    self.joiner.as_ref(),
+                    "default",
+                    h,
+                    out,
+                    |out| render_arm(arm, None, h, r, ctx, rc, out),
+                )?;
+                match &self.budget {
+                    Some(budget) => budget.check(),
                     None => Ok(()),
                 }
             } else {
@@ -77,9 +138,310 @@ impl HelperDef for DefaultHelper {
 #[derive(Clone)]
 pub struct CaseHelper {
     expression_value: serde_json::Value,
+    parts: ValueParts,
+    regexes: RegexCache,
+    /// The number of values a tuple switch switches on
+    tuple: Option<usize>,
+    /// The `{{#case}}` blocks directly inside the switch, see `direct_arms`
+    arms: Arc<[usize]>,
+    shard: Option<usize>,
+    adaptive: Option<usize>,
+    comparison: Comparison,
+    comparable: bool,
+    overrides: Option<Arc<SwitchOverrides>>,
+    tracer: Option<RenderTracer>,
+    on_arm_error: ArmErrorPolicy,
+    budget: Option<Budget>,
+    compat: CompatMode,
+    sentinels: Arc<HashMap<String, Sentinel>>,
+    normalizer: Option<Arc<dyn Normalize>>,
+    post: Option<Arc<PostProcessors>>,
+    max_len: Option<MaxLen>,
+    joiner: Option<Joiner>,
+    multi: bool,
+    limit: Option<u64>,
+    forced: Option<ForcedArm>,
+    redact: bool,
+    shadowed: Shadowed,
+}
+
+impl CaseHelper {
+    fn arm_override(&self, h: &Helper) -> ArmOverride<'_> {
+        match &self.overrides {
+            Some(overrides) => overrides.for_case(h.params().iter().map(|x| x.value())),
+            None => ArmOverride::Keep,
+        }
+    }
+
+    fn is_match(&self, h: &Helper) -> Result<bool, RenderError> {
+        match &self.forced {
+            Some(ForcedArm::Case(value)) => {
+                return Ok(h.params().iter().any(|x| x.value() == value))
+            }
+            Some(ForcedArm::Default) => return Ok(false),
+            None => {}
+        }
+        if !self.comparable {
+            return Ok(false);
+        }
+        // an arm picked up front is found by position alone
+        match self.shard.or(self.adaptive) {
+            Some(selected) => {
+                // only the arm at the selected position among those directly
+                // inside the switch matches, whatever else renders or skips
+                let arm = h.template().map(body_address);
+                Ok(arm.is_some() && arm == self.arms.get(selected).copied())
+            }
+            None => {
+                // like the matcher hash parameters, `in=` must match too
+                let hashed = match (
+                    hash_matches(h, &self.expression_value, &self.parts, &self.regexes)?,
+                    self.in_matches(h)?,
+                ) {
+                    (Some(a), Some(b)) => a && b,
+                    (a, b) => a.or(b).unwrap_or_default(),
+                };
+                let glob = is_truthy_hash(h, "glob");
+                let listed = match (self.tuple, &self.expression_value) {
+                    // the values of a tuple switch match position by position
+                    (Some(arity), Value::Array(values)) => {
+                        h.params().len() == arity
+                            && (h.params().iter().zip(values))
+                                .all(|(x, value)| self.listed_matches(x, value, glob))
+                    }
+                    _ => (h.params().iter())
+                        .any(|x| self.listed_matches(x, &self.expression_value, glob)),
+                };
+                let found = listed || hashed;
+                // a falsy `if=` guard skips the arm, leaving the switch open
+                // for the arms after it
+                let guarded = h.hash_get("if").is_none_or(|x| x.value().is_truthy(false));
+                // `not=true` inverts the arm, as `{{^case}}` is rewritten to
+                Ok(guarded && found != is_truthy_hash(h, "not"))
+            }
+        }
+    }
+
+    /// Checks a value a `{{#case}}` lists against `expected`, the switch
+    /// value or one of a tuple, as a wildcard pattern when `glob` is set
+    fn listed_matches(&self, x: &PathAndJson, expected: &Value, glob: bool) -> bool {
+        match (x.value(), expected) {
+            (Value::String(pattern), Value::String(s)) if glob => glob_matches(pattern, s),
+            (Value::String(_), _) if glob => false,
+            _ => self.param_matches_value(x, expected),
+        }
+    }
+
+    fn param_matches(&self, x: &PathAndJson) -> bool {
+        self.param_matches_value(x, &self.expression_value)
+    }
+
+    fn param_matches_value(&self, x: &PathAndJson, expected: &Value) -> bool {
+        let sentinel = x
+            .relative_path()
+            .and_then(|path| path.strip_prefix('@'))
+            .and_then(|name| self.sentinels.get(name));
+        match sentinel {
+            Some(sentinel) => sentinel.matches(expected),
+            None => self.value_matches_value(x.value(), expected),
+        }
+    }
+
+    fn value_matches(&self, value: &Value) -> bool {
+        self.value_matches_value(value, &self.expression_value)
+    }
+
+    fn value_matches_value(&self, value: &Value, expected: &Value) -> bool {
+        let value = match (&self.normalizer, value) {
+            (None, value) => Cow::Borrowed(value),
+            // a whole tuple, as given to `in=`, normalizes like the switch
+            // values did, one by one
+            (Some(normalizer), Value::Array(items)) if self.tuple.is_some() => {
+                Cow::Owned(Value::Array(
+                    items
+                        .iter()
+                        .map(|x| normalizer.normalize(x.clone()))
+                        .collect(),
+                ))
+            }
+            (Some(normalizer), value) => Cow::Owned(normalizer.normalize(value.clone())),
+        };
+        match self.compat {
+            CompatMode::Native | CompatMode::Fallthrough => self.comparison.eq(&value, expected),
+            CompatMode::Loose => loose_eq(&value, expected),
+        }
+    }
+
+    /// Checks the switch value against the array given as `in=`, if any
+    fn in_matches(&self, h: &Helper) -> Result<Option<bool>, RenderError> {
+        match h.hash_get("in").map(|x| x.value()) {
+            None => Ok(None),
+            Some(Value::Array(items)) => Ok(Some(items.iter().any(|x| self.value_matches(x)))),
+            Some(_) => Err(RenderErrorReason::HashTypeMismatchForName(
+                "case",
+                "in".to_owned(),
+                "array".to_owned(),
+            )
+            .into()),
+        }
+    }
+
+    /// The value to bind to `as |v|` in a matched arm: the value it lists
+    /// that matched, or else the switch value
+    fn binding(&self, h: &Helper) -> Option<Value> {
+        h.block_param()?;
+        let param = h
+            .params()
+            .iter()
+            .filter(|x| x.relative_path().is_none_or(|path| !path.starts_with('@')))
+            .find(|x| self.shard.is_none() && self.param_matches(x));
+        Some(param.map_or_else(|| self.expression_value.clone(), |x| x.value().clone()))
+    }
 }
 
 impl HelperDef for CaseHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        self.shadowed.call_inner("case", h, r, ctx, rc)
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if !h.is_block() {
+            return self.shadowed.call("case", h, r, ctx, rc, out);
+        }
+        if let Some(budget) = &self.budget {
+            budget.check()?;
+        }
+        if let Some(block) = rc.block_mut() {
+            // an arm that failed to render still settles the cases
+            let settled_by: &[&str] = match self.compat {
+                _ if self.multi => &[],
+                CompatMode::Native => &["match", "failed"],
+                CompatMode::Loose => &[],
+                CompatMode::Fallthrough => &["broken"],
+            };
+            let is_set = |block: &BlockContext, name: &str| {
+                block
+                    .get_local_var(name)
+                    .and_then(Value::as_bool)
+                    .unwrap_or_default()
+            };
+            let rendered = block
+                .get_local_var("rendered")
+                .and_then(Value::as_u64)
+                .unwrap_or_default();
+            let prev_found = settled_by.iter().any(|name| is_set(block, name));
+            let limited = self.limit.is_some_and(|limit| rendered >= limit);
+            // once an arm rendered, falling through renders the next one
+            let falling = self.compat == CompatMode::Fallthrough && is_set(block, "match");
+            // an arm asking to fall through renders the next one whatever
+            // settled the switch
+            let fell = take_fallthrough(block);
+            let arm = self.arm_override(h);
+            let matched = !limited
+                && !matches!(arm, ArmOverride::Disable)
+                && (fell || !prev_found && (falling || self.is_match(h)?));
+            if let Some(tracer) = &self.tracer {
+                tracer.arm(ArmTrace {
+                    kind: ArmKind::Case,
+                    values: h
+                        .params()
+                        .iter()
+                        .map(|x| shown(self.redact, x.value()))
+                        .collect(),
+                    label: arm_label(h),
+                    matched,
+                });
+            }
+            if !matched {
+                // did not find match, render the `{{else}}` placeholder if any
+                return match h.inverse() {
+                    Some(t) => t.render(r, ctx, rc, out),
+                    None => Ok(()),
+                };
+            }
+
+            // found match
+            block.set_local_var("match", json!(true));
+            block.set_local_var("switch_break", json!(true));
+            block.set_local_var("rendered", json!(rendered + 1));
+            if is_truthy_hash(h, "fallthrough") {
+                block.set_local_var("fallthrough", json!(true));
+            }
+            let binding = self.binding(h);
+            if self.on_arm_error == ArmErrorPolicy::Propagate {
+                render_post_processed(
+                    self.post.as_deref(),
+                    self.max_len,
+                    self.joiner.as_ref(),
+                    "case",
+                    h,
+                    out,
+                    |out| render_arm(arm, binding.clone(), h, r, ctx, rc, out),
+                )?;
+            } else if let Some(err) = render_buffered(out, |buffer| {
+                render_post_processed(
+                    self.post.as_deref(),
+                    self.max_len,
+                    self.joiner.as_ref(),
+                    "case",
+                    h,
+                    buffer,
+                    |out| render_arm(arm, binding.clone(), h, r, ctx, rc, out),
+                )
+            })? {
+                log::warn!(
+                    "`{{{{#case}}}}` failed to render, falling back to `{{{{#default}}}}`: {err}"
+                );
+                if let Some(block) = rc.block_mut() {
+                    block.set_local_var("match", json!(false));
+                    block.set_local_var("switch_break", json!(false));
+                    block.set_local_var("failed", json!(true));
+                }
+            }
+            match &self.budget {
+                Some(budget) => budget.check(),
+                None => Ok(()),
+            }
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FormatCaseHelper {
+    format: Option<String>,
+    tracer: Option<RenderTracer>,
+    post: Option<Arc<PostProcessors>>,
+    max_len: Option<MaxLen>,
+    joiner: Option<Joiner>,
+    shadowed: Shadowed,
+}
+
+impl HelperDef for FormatCaseHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        self.shadowed.call_inner("format_case", h, r, ctx, rc)
+    }
+
     fn call<'reg: 'rc, 'rc>(
         &self,
         h: &Helper<'rc>,
@@ -88,227 +450,4052 @@ impl HelperDef for CaseHelper {
         rc: &mut RenderContext<'reg, 'rc>,
         out: &mut dyn Output,
     ) -> HelperResult {
+        if !h.is_block() {
+            return self.shadowed.call("format_case", h, r, ctx, rc, out);
+        }
         if let Some(block) = rc.block_mut() {
             let prev_found = block
                 .get_local_var("match")
                 .and_then(Value::as_bool)
                 .unwrap_or_default();
-            if !prev_found
-                && h.params()
-                    .iter()
-                    .any(|x| *x.value() == self.expression_value)
-            {
-                // found match
+            let matched = !prev_found
+                && self.format.as_deref().is_some_and(|format| {
+                    h.params()
+                        .iter()
+                        .any(|x| x.value().as_str() == Some(format))
+                });
+            if let Some(tracer) = &self.tracer {
+                tracer.arm(ArmTrace {
+                    kind: ArmKind::FormatCase,
+                    values: h.params().iter().map(|x| x.value().clone()).collect(),
+                    label: arm_label(h),
+                    matched,
+                });
+            }
+            if matched {
+                // found the output format
                 block.set_local_var("match", json!(true));
-                match h.template() {
+                render_post_processed(
+                    self.post.as_deref(),
+                    self.max_len,
+                    self.joiner.as_ref(),
+                    "format_case",
+                    h,
+                    out,
+                    |out| render_arm(ArmOverride::Keep, None, h, r, ctx, rc, out),
+                )
+            } else {
+                // render the `{{else}}` placeholder if any
+                match h.inverse() {
                     Some(t) => t.render(r, ctx, rc, out),
                     None => Ok(()),
                 }
-            } else {
-                // did not find match
-                Ok(())
             }
         } else {
             Ok(())
         }
     }
-}
+}
+
+/// Stops a `{{#switch}}` in [`CompatMode::Fallthrough`] from rendering the
+/// arms after the one holding the `{{break}}`
+#[derive(Clone, Copy)]
+pub struct BreakHelper;
+
+impl HelperDef for BreakHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        _: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(block) = rc.block_mut() {
+            block.set_local_var("broken", json!(true));
+        }
+        Ok(())
+    }
+}
+
+/// Makes the arm holding the `{{fallthrough}}` render the arm after it as
+/// well, even though the switch is already settled
+#[derive(Clone, Copy)]
+pub struct FallthroughHelper;
+
+impl HelperDef for FallthroughHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        _: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(block) = rc.block_mut() {
+            block.set_local_var("fallthrough", json!(true));
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether the arm before asked to fall through into this one,
+/// consuming the request so that the arm after needs its own
+fn take_fallthrough(block: &mut BlockContext) -> bool {
+    let fell = block
+        .get_local_var("fallthrough")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+    if fell {
+        block.set_local_var("fallthrough", json!(false));
+    }
+    fell
+}
+
+/// Renders the body of a matched arm, or its replacement, against the
+/// `context=` of the arm if it has one, binding `binding` to its `as |v|`
+fn render_arm<'reg: 'rc, 'rc>(
+    arm: ArmOverride,
+    binding: Option<Value>,
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let context = h.hash_get("context");
+    let binding = binding.zip(h.block_param());
+    if context.is_none() && binding.is_none() {
+        return render_arm_body(arm, h, r, ctx, rc, out);
+    }
+
+    let mut block = arm_block(rc);
+    if let Some(context) = context {
+        block.set_base_value(context.value().clone());
+    }
+    if let Some((value, name)) = binding {
+        let mut params = BlockParams::new();
+        params.add_value(name, value)?;
+        block.set_block_params(params);
+    }
+    rc.push_block(block);
+    let result = render_arm_body(arm, h, r, ctx, rc, out);
+    // a `{{break}}` or `{{fallthrough}}` in the arm still applies to the
+    // switch
+    let flags = ["broken", "fallthrough"].map(|name| {
+        rc.block()
+            .and_then(|block| block.get_local_var(name))
+            .cloned()
+            .map(|value| (name, value))
+    });
+    let vars = saved_vars(rc);
+    rc.pop_block();
+    if let Some(block) = rc.block_mut() {
+        for (name, value) in flags.into_iter().flatten() {
+            block.set_local_var(name, value);
+        }
+    }
+    restore_vars(rc, vars);
+
+    result
+}
+
+fn render_arm_body<'reg: 'rc, 'rc>(
+    arm: ArmOverride,
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    match (arm, h.template()) {
+        (ArmOverride::Replace(t), _) => render_replacement(t, r, ctx, rc, out),
+        (_, Some(t)) => t.render(r, ctx, rc, out),
+        (_, None) => Ok(()),
+    }
+}
+
+/// Switch Helper
+///
+/// Provides the `{{#switch}}` helper to a Handlebars template.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+///
+/// let tpl = "\
+///     {{#switch access}}\
+///         {{#case \"admin\"}}Admin{{/case}}\
+///         {{#default}}User{{/default}}\
+///     {{/switch}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"access": "admin"})).unwrap(),
+///     "Admin"
+/// );
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"access": "nobody"})).unwrap(),
+///     "User"
+/// );
+/// # }
+/// ```
+///
+/// # Arrays
+///
+/// Arrays match when they hold equal elements in the same order. Passing
+/// `arrays="set"` instead ignores order and duplicates, which suits values
+/// like permission lists that arrive in no particular order. The default can
+/// be changed with [`SwitchHelper::with_array_equality`]. Switching on an
+/// array or object by mistake can be made to fail the render with
+/// [`SwitchHelper::with_container_policy`].
+///
+/// # Tuples
+///
+/// A `{{#switch}}` given several values, as in `{{#switch method path}}`,
+/// switches on all of them at once: a `{{#case "GET" "/users"}}` matches
+/// when each value it lists matches the switch value at the same position,
+/// rather than when any one of them does, and a case listing another number
+/// of values never matches. Sentinels like `@ANY` stand in for a position
+/// that does not matter. Hash parameters such as `ptr=` and normalizers
+/// apply to each value, while matchers like `in=` see the values as an
+/// array, which is also what `as |values|` binds.
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+///
+/// let tpl = "\
+///     {{#switch method path}}\
+///         {{#case \"GET\" \"/users\"}}List users{{/case}}\
+///         {{#case \"POST\" \"/users\"}}Create user{{/case}}\
+///         {{#default}}Not found{{/default}}\
+///     {{/switch}}\
+/// ";
+/// let render = |method, path| {
+///     let data = json!({ "method": method, "path": path });
+///     handlebars.render_template(tpl, &data).unwrap()
+/// };
+/// assert_eq!(render("POST", "/users"), "Create user");
+/// assert_eq!(render("GET", "/posts"), "Not found");
+/// # }
+/// ```
+///
+/// # Partial Matches
+///
+/// Besides the values it lists, a `{{#case}}` can match on a part of the
+/// switch value through a hash parameter. When several are given, all of
+/// them must match:
+///
+/// - `email_domain="example.com"` matches email addresses at that domain,
+///   and `email_domain="*.example.com"` at any of its subdomains.
+/// - `scheme="https"`, `host="docs.example.com"`, `port=8080`,
+///   `path="/about"`, and `path_prefix="/api/"` match components of an
+///   absolute URL, where `host` accepts the same wildcards as
+///   `email_domain`. The URL is parsed once per `{{#switch}}`.
+/// - `in=(arr "a" "b")` matches any value of the array, which can be built
+///   inline with [`ArrHelper`](crate::ArrHelper).
+/// - `hex="deadbeef"` matches binary data with those bytes, given as an
+///   array of byte values or as a base64 string.
+/// - `sha256="9f86d0…"` matches values whose SHA-256 digest it is, so long
+///   values such as license texts need not be inlined. Strings are hashed by
+///   their UTF-8 bytes and other values by their compact JSON, once per
+///   `{{#switch}}`.
+/// - `range="18..65"` matches numbers from 18 up to but excluding 65,
+///   `range="18..=65"` up to and including 65, and `range="65.."` or
+///   `range="..18"` every number past or short of a bound, so status classes
+///   are written as `range="500..600"`.
+/// - `glob="page-*"` matches strings by a shell-style wildcard pattern,
+///   where `*` stands for any run of characters, `?` for any single one,
+///   and `[a-z]` or `[!a-z]` for one in or out of a set. Passing
+///   `glob=true` instead makes the strings a `{{#case}}` lists patterns, as
+///   in `{{#case "error.*" "warn.*" glob=true}}`.
+/// - With the `regex` feature, `regex="^admin-.*"` matches strings the
+///   regular expression finds a match in, anywhere unless anchored with `^`
+///   and `$`. Patterns use the syntax of the `regex` crate, such as a
+///   leading `(?i)` to ignore case, and each compiles once per
+///   [`SwitchHelper`]. Without the feature, `regex=` fails the render.
+/// - With the `phones` feature, `country_code="+44"` matches phone numbers in
+///   international format with that country calling code.
+///
+/// # Sentinels
+///
+/// A `{{#case}}` may list a [`Sentinel`] registered by the host app, such as
+/// `{{#case @ANY}}`, which matches by its own test rather than by value.
+///
+/// # Integer Literals
+///
+/// A string written as a hexadecimal, octal, or binary literal, such as
+/// `"0xFF"`, `"0o755"`, or `"0b1010"`, matches the integer it stands for, so
+/// `{{#case "0o755"}}` matches a switch value of `493`. Decimal strings like
+/// `"42"` still only match strings.
+///
+/// # Placeholders
+///
+/// A `{{#case}}` may hold an `{{else}}` section, which renders in place of
+/// that arm whenever the arm itself does not render, whether or not another
+/// arm matches. This keeps positional markup such as table cells aligned.
+///
+/// # Inverse Arms
+///
+/// Passing `not=true` to a `{{#case}}` makes it match every value except the
+/// ones it lists. Since the handlebars parser does not accept inverse blocks
+/// like `{{^case "admin"}}...{{/case}}`, templates using them can be passed
+/// through [`expand_inverse_cases`](crate::expand_inverse_cases) first.
+///
+/// # Guards
+///
+/// Passing `if=user.active` to a `{{#case}}` makes it match only while the
+/// guard is truthy, by the same rules as `{{#if}}`. An arm whose guard fails
+/// is skipped like one that does not match, so a later arm listing the same
+/// value, or the `{{#default}}`, can still render.
+///
+/// # Shared Options
+///
+/// The hash parameters of a `{{#switch}}` are available to all of its arms
+/// as `@` variables, so `{{#switch kind theme="dark"}}` gives every arm
+/// `{{@theme}}` without repeating it or threading it through the context.
+///
+/// # Switched Values
+///
+/// The value a `{{#switch}}` switches on is available to its arms as
+/// `{{@switch_value}}`, or under a name of its own with
+/// `{{#switch user.role as |role|}}`, so an arm can show it without
+/// repeating the expression. This is the value after `ptr=`, decoding, and
+/// aliases, but before any normalizer, so the arms show it as it was given.
+///
+/// # Matched Values
+///
+/// A `{{#case "png" "jpg" as |ext|}}` binds the value it lists that matched
+/// to `ext`, or the switch value when the arm matched some other way, such
+/// as through a hash parameter or `not=true`.
+///
+/// # Arm Contexts
+///
+/// Passing `context=order.invoice` to a `{{#case}}` or `{{#default}}`
+/// renders its body against that value, like `{{#with order.invoice}}`
+/// would, so the body can use short paths such as `{{number}}` and arm
+/// partials can expect a specific shape.
+///
+/// # Output Formats
+///
+/// A switch helper created with [`SwitchHelper::for_format`] also provides
+/// `{{#format_case}}` arms, which match when the output flavor is among their
+/// values regardless of the switch value. Registering one helper per flavor
+/// renders both parts of a multipart email from the same template:
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchHelper;
+///
+/// let tpl = "\
+///     Hi {{name}}, \
+///     {{#switch link}}\
+///         {{#format_case \"html\"}}<a href=\"{{link}}\">sign in</a>{{/format_case}}\
+///         {{#default}}sign in at {{link}}{{/default}}\
+///     {{/switch}}\
+/// ";
+/// let data = json!({"name": "Jo", "link": "https://example.com"});
+///
+/// let mut html = Handlebars::new();
+/// html.register_helper("switch", Box::new(SwitchHelper::for_format("html")));
+/// assert_eq!(
+///     html.render_template(tpl, &data).unwrap(),
+///     "Hi Jo, <a href=\"https://example.com\">sign in</a>"
+/// );
+///
+/// let mut text = Handlebars::new();
+/// text.register_helper("switch", Box::new(SwitchHelper::for_format("text")));
+/// assert_eq!(
+///     text.render_template(tpl, &data).unwrap(),
+///     "Hi Jo, sign in at https://example.com"
+/// );
+/// # }
+/// ```
+///
+/// # Pointers
+///
+/// Passing `ptr="/payload/items/0/status"` switches on the part of the value
+/// at that [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901), which
+/// may come from config like any other expression. Nothing at the pointer
+/// switches on `null`. Only the part at the pointer is copied for the switch,
+/// so switching on a small field of a multi-megabyte payload this way stays
+/// cheap.
+///
+/// Handlebars turns the whole context into a `serde_json::Value` before
+/// rendering, parsing any `RawValue` in it. A payload kept as JSON text
+/// instead, such as the string from `RawValue::get`, can be switched on with
+/// `raw=true`, which reads a string value as a JSON document. Only the part
+/// at `ptr=` is then parsed into a value, while the rest of the document is
+/// merely checked on the way there. Text that is not valid JSON switches on
+/// `null`, and values that are not strings are used as they are.
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchHelper;
+/// use serde_json::value::RawValue;
+/// use serde_json::json;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+///
+/// let payload = RawValue::from_string(r#"{"order": {"status": "shipped"}}"#.to_owned()).unwrap();
+/// let tpl = "\
+///     {{#switch payload raw=true ptr=\"/order/status\"}}\
+///         {{#case \"shipped\"}}On its way{{/case}}\
+///         {{#default}}Processing{{/default}}\
+///     {{/switch}}\
+/// ";
+/// let data = json!({"payload": payload.get()});
+/// assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "On its way");
+/// # }
+/// ```
+///
+/// # Allowed Values
+///
+/// Passing `allowed=(arr "draft" "published" "archived")` fails the render
+/// when the value matches none of the array, compared like a `{{#case}}`
+/// would, so corrupt upstream data surfaces as an error instead of quietly
+/// rendering the `{{#default}}`.
+///
+/// # Missing Values
+///
+/// A value that is missing from the data, such as `config.tls.mode` when
+/// `config` has no `tls`, is switched on as `null`, in strict mode too.
+/// Nothing at the `ptr=` of a value counts as missing too. Passing
+/// `nil_safe=true` also lets a missing value past `allowed=`, which would
+/// otherwise fail the render on it, so config with sparse values falls
+/// through to the `{{#default}}`.
+///
+/// # Decoding
+///
+/// Passing `decode="url"` or `decode="base64"` decodes a string switch value
+/// before it is compared, for fields that arrive percent-encoded or base64
+/// encoded. A value that fails to decode matches no `{{#case}}`.
+///
+/// # Aliases
+///
+/// Messy inputs can be folded onto the canonical values the arms are written
+/// for with [`SwitchHelper::with_aliases`]:
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper(
+///     "switch",
+///     Box::new(SwitchHelper::new().with_aliases([("ts", "typescript"), ("js", "javascript")])),
+/// );
+///
+/// let tpl = "\
+///     {{#switch lang}}\
+///         {{#case \"typescript\"}}.ts{{/case}}\
+///         {{#case \"javascript\"}}.js{{/case}}\
+///     {{/switch}}\
+/// ";
+///
+/// assert_eq!(handlebars.render_template(tpl, &json!({"lang": "ts"})).unwrap(), ".ts");
+/// assert_eq!(handlebars.render_template(tpl, &json!({"lang": "javascript"})).unwrap(), ".js");
+/// # }
+/// ```
+///
+/// # Compatibility
+///
+/// Every `{{#switch}}` block sets `@switch_break` once a `{{#case}}` has
+/// rendered, like the switch helpers often copied between Node projects.
+/// Templates relying on the other habits of those helpers, such as
+/// rendering every matching case, or on arms falling through to the next
+/// until a `{{break}}` as in C, can select them with
+/// [`SwitchHelper::with_compat`]. See [`CompatMode`] for details.
+///
+/// A single arm can fall through on its own, in any mode: an arm given
+/// `fallthrough=true`, or whose body holds a `{{fallthrough}}`, renders
+/// the arm after it as well, whether that one matches or not. The arm it
+/// falls into needs its own `fallthrough` to carry on to the next.
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::SwitchHelper;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+///
+/// let tpl = "\
+///     {{#switch plan}}\
+///         {{#case \"team\" fallthrough=true}}Shared workspaces, {{/case}}\
+///         {{#case \"pro\"}}Unlimited projects{{/case}}\
+///         {{#default}}Three projects{{/default}}\
+///     {{/switch}}\
+/// ";
+/// let render = |plan| handlebars.render_template(tpl, &json!({ "plan": plan })).unwrap();
+/// assert_eq!(render("team"), "Shared workspaces, Unlimited projects");
+/// assert_eq!(render("pro"), "Unlimited projects");
+/// # }
+/// ```
+///
+/// # Normalization
+///
+/// A [`Normalize`](crate::Normalize) pipeline registered with
+/// [`SwitchHelper::with_normalizer`] brings the switch value and the
+/// `{{#case}}` values into a normal form before they are compared, such as
+/// trimmed and lower case. A `{{#switch}}` can pick a pipeline registered
+/// with [`SwitchHelper::with_named_normalizer`] through `normalize="name"`,
+/// or skip normalization with `normalize=false`.
+///
+/// # Overrides
+///
+/// A `{{#switch}}` given a `name="..."` can have its arms replaced or
+/// disabled at render time through [`SwitchHelper::with_overrides`]. See
+/// [`ArmOverrides`] for details.
+///
+/// # Arm Sources
+///
+/// Passing `source="cms"` to a named `{{#switch}}` renders the matching arm
+/// fetched from the source registered as `"cms"` through
+/// [`SwitchHelper::with_arm_source`] in place of its arms, falling back to
+/// them when the source has none that applies. See
+/// [`ArmSource`](crate::ArmSource) for details.
+///
+/// # Post-processing
+///
+/// Closures registered with [`SwitchHelper::with_post_processor`] rewrite the
+/// output of arms before it is written, for example to compact the JSON
+/// fragment of every arm marked `label="json"`.
+///
+/// # Multiple Matches
+///
+/// Passing `multi=true` renders every `{{#case}}` that matches instead of
+/// only the first, and a `{{#default}}` when no `{{#case}}` before it
+/// rendered. Passing `join=", "` writes the output of the rendered arms
+/// separated by `", "`, or by `last_join=" and "` between the last two,
+/// dropping the text between the arms. Passing `limit=3` renders at most
+/// the first three matching `{{#case}}` arms:
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// # use handlebars::Handlebars;
+/// # use handlebars_switch::SwitchHelper;
+/// # let mut handlebars = Handlebars::new();
+/// # handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// let tpl = "
+///     {{#switch true multi=true limit=3 join=\", \" last_join=\" and \"}}
+///         {{#case user.admin}}admin{{/case}}
+///         {{#case user.staff}}staff{{/case}}
+///         {{#case user.verified}}verified{{/case}}
+///         {{#case user.active}}active{{/case}}
+///     {{/switch}}
+/// ";
+///
+/// let user = json!({"admin": true, "staff": true, "verified": true, "active": true});
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({ "user": user })).unwrap().trim(),
+///     "admin, staff and verified"
+/// );
+/// # }
+/// ```
+///
+/// # Length Caps
+///
+/// Passing `max_len=160` to an arm fails the render when it renders more
+/// than 160 characters, for payloads like SMS or push notifications that
+/// have hard size limits. Passing `truncate=true` alongside cuts the output
+/// off at the limit instead. Given to the `{{#switch}}`, the cap applies to
+/// every arm without one of its own. It is checked after post-processing.
+///
+/// # Tracing
+///
+/// A [`RenderTracer`](crate::RenderTracer) passed to
+/// [`SwitchHelper::with_tracer`] records which arm every switch rendered,
+/// which helps when reporting why a template rendered the way it did. Arms
+/// are reported by their `label="..."` when they have one. Passing
+/// `silent=true` renders the switch without writing its output, so a
+/// template can probe a value and leave the arm it matched to the tracer
+/// without emitting any content. A helper built with
+/// [`SwitchHelper::with_redacted_values`] masks the values it reports.
+///
+/// # Auditing
+///
+/// An [`AuditSink`](crate::AuditSink) passed to
+/// [`SwitchHelper::with_audit_sink`] receives the value of every switch
+/// rendered along with the kind of arm it rendered, to find the values
+/// production data brings that end up in `{{#default}}` arms.
+///
+/// # Names
+///
+/// Passing `name="order-status-badge"` names the switch in its trace and at
+/// the start of the messages of errors raised while rendering it, so one
+/// switch can be told apart from the others in a large set of templates.
+///
+/// # Budgets
+///
+/// Passing `budget_ms=50` fails the render with a descriptive error once the
+/// `{{#switch}}` has spent more than 50 milliseconds evaluating and
+/// rendering its arms, as a circuit breaker for arms that embed
+/// user-supplied partials. An arm cannot be interrupted, so the budget is
+/// checked between arms.
+///
+/// # Memoization
+///
+/// Passing `memo=true` reuses the output of earlier renders of the same block
+/// with the same value when the helper was built with
+/// [`SwitchHelper::with_memo`]. See [`MemoCache`] for details.
+///
+/// # Adaptive Ordering
+///
+/// Passing `adaptive=true` checks the `{{#case}}` that matched most recently
+/// first when the helper was built with [`SwitchHelper::with_adaptive_order`],
+/// which saves comparisons when most values land on one arm. See
+/// [`AdaptiveOrder`] for the blocks it applies to.
+///
+/// # Shadowed Helpers
+///
+/// Inside a `{{#switch}}`, its arms `case`, `default`, and `format_case` take
+/// priority over global helpers registered under the same names, like the
+/// `default` helper of many helper collections. Calling one of them without
+/// a block there, as `{{default title "-"}}`, fails the render rather than
+/// reaching the arm, unless the global helper is also passed to
+/// [`SwitchHelper::with_shadowed_helper`], which calls it instead. Outside
+/// of switches the global helpers work as usual.
+///
+/// # Sharding
+///
+/// Passing `shard=true` ignores the `{{#case}}` params and instead picks one
+/// of the `{{#case}}` arms directly inside the block by
+/// [`stable_hash`](crate::stable_hash) of the value modulo the number of arms,
+/// so the same value always lands on the same arm. A helper built with
+/// [`SwitchHelper::with_shard_seed`] hashes with its seed instead:
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// # use handlebars::Handlebars;
+/// # use handlebars_switch::SwitchHelper;
+/// # let mut handlebars = Handlebars::new();
+/// # handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// let tpl = "\
+///     {{#switch user_id shard=true}}\
+///         {{#case}}red{{/case}}\
+///         {{#case}}green{{/case}}\
+///         {{#case}}blue{{/case}}\
+///     {{/switch}}\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"user_id": 7})).unwrap(),
+///     handlebars.render_template(tpl, &json!({"user_id": 7})).unwrap()
+/// );
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct SwitchHelper {
+    memo: Option<MemoCache>,
+    audit: Option<Arc<dyn AuditSink>>,
+    adaptive: Option<AdaptiveOrder>,
+    debug_assert_match: bool,
+    redact: bool,
+    containers: ContainerPolicy,
+    comparison: Comparison,
+    overrides: Option<ArmOverrides>,
+    aliases: HashMap<String, Value>,
+    tracer: Option<RenderTracer>,
+    format: Option<String>,
+    on_arm_error: ArmErrorPolicy,
+    compat: CompatMode,
+    post: Arc<PostProcessors>,
+    sentinels: Arc<HashMap<String, Sentinel>>,
+    normalizer: Option<Arc<dyn Normalize>>,
+    normalizers: Arc<HashMap<String, Arc<dyn Normalize>>>,
+    comparators: Arc<HashMap<String, FieldComparator>>,
+    shadowed: Shadowed,
+    sources: Arc<HashMap<String, Arc<dyn ArmSource>>>,
+    compiled: CompiledArms,
+    regexes: RegexCache,
+    shard_seed: Option<u64>,
+}
+
+impl SwitchHelper {
+    /// Creates a switch helper with the default behavior.
+    pub fn new() -> SwitchHelper {
+        SwitchHelper::default()
+    }
+
+    /// Creates a switch helper for rendering one output flavor, such as
+    /// `"text"` or `"html"`, whose `{{#format_case}}` arms match on it.
+    pub fn for_format(format: &str) -> SwitchHelper {
+        SwitchHelper {
+            format: Some(format.to_owned()),
+            ..SwitchHelper::default()
+        }
+    }
+
+    /// Decides what happens when the body of a matched `{{#case}}` fails to
+    /// render, see [`ArmErrorPolicy`].
+    pub fn with_arm_error_policy(mut self, policy: ArmErrorPolicy) -> SwitchHelper {
+        self.on_arm_error = policy;
+        self
+    }
+
+    /// Selects semantics that mimic a JavaScript switch helper, see
+    /// [`CompatMode`].
+    pub fn with_compat(mut self, compat: CompatMode) -> SwitchHelper {
+        self.compat = compat;
+        self
+    }
+
+    /// Passes the rendered output of every arm with `label="..."` set to
+    /// `label` through `f` before it is written, or of every arm without a
+    /// label when `label` is `None`. An error returned by `f` fails the
+    /// render. Later post-processors replace earlier ones for the same label.
+    pub fn with_post_processor<F>(mut self, label: Option<&str>, f: F) -> SwitchHelper
+    where
+        F: Fn(String) -> Result<String, RenderError> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.post).insert(label.map(str::to_owned), Arc::new(f));
+        self
+    }
+
+    /// Normalizes the switch value and every `{{#case}}` value with
+    /// `normalizer` before comparing them, unless a `{{#switch}}` picks
+    /// another with `normalize="name"` or none with `normalize=false`. See
+    /// [`Normalize`](crate::Normalize).
+    pub fn with_normalizer(mut self, normalizer: impl Normalize + 'static) -> SwitchHelper {
+        self.normalizer = Some(Arc::new(normalizer));
+        self
+    }
+
+    /// Registers a normalizer that a `{{#switch}}` picks with
+    /// `normalize="name"` in place of the one set by
+    /// [`SwitchHelper::with_normalizer`].
+    pub fn with_named_normalizer(
+        mut self,
+        name: &str,
+        normalizer: impl Normalize + 'static,
+    ) -> SwitchHelper {
+        Arc::make_mut(&mut self.normalizers).insert(name.to_owned(), Arc::new(normalizer));
+        self
+    }
+
+    /// Picks the normalizer of the `{{#switch}}` `h`, if any
+    fn normalizer_for(&self, h: &Helper) -> Result<Option<Arc<dyn Normalize>>, RenderError> {
+        match h.hash_get("normalize").map(|x| x.value()) {
+            None | Some(Value::Bool(true)) => Ok(self.normalizer.clone()),
+            Some(Value::Bool(false)) => Ok(None),
+            Some(Value::String(name)) => match self.normalizers.get(name) {
+                Some(normalizer) => Ok(Some(normalizer.clone())),
+                None => Err(RenderErrorReason::Other(format!(
+                    "`{{{{#switch}}}}` has no normalizer named {name:?}"
+                ))
+                .into()),
+            },
+            Some(_) => Err(RenderErrorReason::HashTypeMismatchForName(
+                "switch",
+                "normalize".to_owned(),
+                "string or boolean".to_owned(),
+            )
+            .into()),
+        }
+    }
+
+    /// Compares the switch value against every `{{#case}}` value with
+    /// `comparator` in place of the comparison options, for equality this
+    /// crate does not know, unless a `{{#switch}}` picks a comparator
+    /// registered with [`SwitchHelper::with_named_comparator`] through
+    /// `compare="name"`. The same as a [`with_field_comparator`] for the
+    /// pointer `""`.
+    ///
+    /// [`with_field_comparator`]: SwitchHelper::with_field_comparator
+    pub fn with_comparator(self, comparator: FieldComparator) -> SwitchHelper {
+        self.with_field_comparator("", comparator)
+    }
+
+    /// Registers a comparator of whole values that a `{{#switch}}` picks with
+    /// `compare="name"`, so a template can opt into a specialized equality
+    /// without changing it for every switch.
+    pub fn with_named_comparator(
+        mut self,
+        name: &str,
+        comparator: FieldComparator,
+    ) -> SwitchHelper {
+        Arc::make_mut(&mut self.comparators).insert(name.to_owned(), comparator);
+        self
+    }
+
+    /// Fetches the arms of the source the `{{#switch}}` `h` names with
+    /// `source=`, and picks the one to render in place of the arms in the
+    /// template, if any
+    fn sourced_arm(
+        &self,
+        h: &Helper,
+        name: Option<&str>,
+        matches: impl Fn(&Value) -> bool,
+    ) -> Result<Option<PickedArm>, RenderError> {
+        let Some(source) = h.hash_get("source").map(|x| x.value()) else {
+            return Ok(None);
+        };
+        let Some(source) = source.as_str() else {
+            return Err(RenderErrorReason::HashTypeMismatchForName(
+                "switch",
+                "source".to_owned(),
+                "string".to_owned(),
+            )
+            .into());
+        };
+        let Some(arms_source) = self.sources.get(source) else {
+            return Err(RenderErrorReason::Other(format!(
+                "`{{{{#switch}}}}` has no arm source registered as {source:?}"
+            ))
+            .into());
+        };
+        let Some(name) = name else {
+            return Err(RenderErrorReason::Other(
+                "`{{#switch}}` needs a `name=` to fetch its arms from a source".to_owned(),
+            )
+            .into());
+        };
+        let Some(arms) = arms_source.arms(name) else {
+            return Ok(None);
+        };
+        let picked = (arms.iter().position(|arm| arm.values.iter().any(&matches)))
+            .or_else(|| arms.iter().position(|arm| arm.values.is_empty()));
+        let Some(picked) = picked else {
+            return Ok(None);
+        };
+        match self
+            .compiled
+            .compile((source.to_owned(), name.to_owned()), arms)
+        {
+            Ok(templates) => Ok(Some((templates, picked))),
+            Err(err) => {
+                // a broken arm in the source leaves the template in charge
+                log::warn!(
+                    "`{{{{#switch}}}}` {name:?} ignores the arms of source {source:?}: {err}"
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Compares whole values with the comparator the `{{#switch}}` `h` picks,
+    /// if any
+    fn comparator_for(
+        &self,
+        mut comparison: Comparison,
+        h: &Helper,
+    ) -> Result<Comparison, RenderError> {
+        let name = match h.hash_get("compare").map(|x| x.value()) {
+            None => return Ok(comparison),
+            Some(Value::String(name)) => name,
+            Some(_) => {
+                return Err(RenderErrorReason::HashTypeMismatchForName(
+                    "switch",
+                    "compare".to_owned(),
+                    "string".to_owned(),
+                )
+                .into())
+            }
+        };
+        let comparator = self.comparators.get(name).ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "`{{{{#switch}}}}` has no comparator named {name:?}"
+            ))
+        })?;
+        Arc::make_mut(&mut comparison.fields).insert(String::new(), comparator.clone());
+        Ok(comparison)
+    }
+
+    /// Registers a [`Sentinel`] that a `{{#case}}` lists as `@name`, replacing
+    /// any registered before under the same name.
+    pub fn with_sentinel(mut self, name: &str, sentinel: Sentinel) -> SwitchHelper {
+        Arc::make_mut(&mut self.sentinels).insert(name.to_owned(), sentinel);
+        self
+    }
+
+    /// Picks the arm of `{{#switch ... shard=true}}` blocks by
+    /// [`seeded_hash`](crate::seeded_hash) with `seed`, so separate rollouts
+    /// split the same values differently yet reproducibly.
+    pub fn with_shard_seed(mut self, seed: u64) -> SwitchHelper {
+        self.shard_seed = Some(seed);
+        self
+    }
+
+    /// Forwards the arm named `name`, such as `default`, to `helper` when it
+    /// is called without a block inside a switch, as `{{default title "-"}}`
+    /// or `(default title "-")`, instead of failing the render. Give it the
+    /// helper registered globally under the same name so both keep working,
+    /// see [Shadowed Helpers](#shadowed-helpers).
+    pub fn with_shadowed_helper(
+        mut self,
+        name: &str,
+        helper: impl HelperDef + Send + Sync + 'static,
+    ) -> SwitchHelper {
+        self.shadowed.insert(name, Arc::new(helper));
+        self
+    }
+
+    /// Caches the output of `{{#switch ... memo=true}}` blocks in `memo`.
+    pub fn with_memo(mut self, memo: MemoCache) -> SwitchHelper {
+        self.memo = Some(memo);
+        self
+    }
+
+    /// Checks the most recently matched `{{#case}}` of
+    /// `{{#switch ... adaptive=true}}` blocks first, tracking the order in
+    /// `order`.
+    pub fn with_adaptive_order(mut self, order: AdaptiveOrder) -> SwitchHelper {
+        self.adaptive = Some(order);
+        self
+    }
+
+    /// Fetches the arms of `{{#switch ... source="name"}}` blocks from
+    /// `source`, see [`ArmSource`](crate::ArmSource).
+    pub fn with_arm_source(mut self, name: &str, source: impl ArmSource + 'static) -> SwitchHelper {
+        Arc::make_mut(&mut self.sources).insert(name.to_owned(), Arc::new(source));
+        self
+    }
+
+    /// Replaces or disables arms of named switches, see [`ArmOverrides`].
+    pub fn with_overrides(mut self, overrides: ArmOverrides) -> SwitchHelper {
+        self.overrides = Some(overrides);
+        self
+    }
+
+    /// Reports the value of every `{{#switch}}` rendered to `sink`, along
+    /// with the kind of arm it rendered, see [`AuditSink`].
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> SwitchHelper {
+        self.audit = Some(Arc::new(sink));
+        self
+    }
+
+    /// Records every `{{#switch}}` rendered into `tracer`, see
+    /// [`RenderTracer`].
+    pub fn with_tracer(mut self, tracer: RenderTracer) -> SwitchHelper {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Maps string switch values onto canonical ones before any `{{#case}}`
+    /// is compared, so `"ts"` can match `{{#case "typescript"}}`. Aliases are
+    /// not chained, and later entries replace earlier ones with the same key.
+    pub fn with_aliases<I, K, V>(mut self, aliases: I) -> SwitchHelper
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.aliases
+            .extend(aliases.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Sets how array values are compared, unless a `{{#switch}}` overrides
+    /// it with `arrays="positional"` or `arrays="set"`.
+    pub fn with_array_equality(mut self, arrays: ArrayEquality) -> SwitchHelper {
+        self.comparison.arrays = arrays;
+        self
+    }
+
+    /// Matches strings regardless of their naming convention, so
+    /// `"orderCreated"`, `"order_created"`, and `"OrderCreated"` all hit the
+    /// same `{{#case}}`, unless a `{{#switch}}` overrides it with
+    /// `ignore_case_style=false`. Folding ignores case as well as the `_`,
+    /// `-`, `.`, and space separators.
+    pub fn with_ignore_case_style(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.ignore_case_style = enabled;
+        self
+    }
+
+    /// Compares strings regardless of case, so `"ADMIN"` matches
+    /// `{{#case "admin"}}`, unless a `{{#switch}}` overrides it with
+    /// `insensitive=false` or opts in alone with `insensitive=true`. Case is
+    /// folded fully rather than lowercased, so `"STRASSE"` also matches
+    /// `"Straße"`, while accents and separators stay significant.
+    pub fn with_case_insensitive(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.insensitive = enabled;
+        self
+    }
+
+    /// Strips diacritics from Latin letters before comparing strings, so
+    /// `"José"` matches `{{#case "Jose"}}`, unless a `{{#switch}}` overrides
+    /// it with `ignore_diacritics=false`. Requires the `unicode` feature.
+    #[cfg(feature = "unicode")]
+    pub fn with_ignore_diacritics(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.ignore_diacritics = enabled;
+        self
+    }
+
+    /// Compares strings by the collation rules of `locale`, like `"de"` or
+    /// `"tr-TR"`, so `"MÜLLER"` matches `{{#case "Müller"}}` and case is
+    /// folded the way the locale does, unless a `{{#switch}}` picks another
+    /// locale with `collation="..."`. Accents stay significant. Strings are
+    /// compared by an ICU4X collator at secondary strength, so the dotted and
+    /// dotless `i` of Turkish and Azeri pair up as those locales expect. A
+    /// `locale` that is not a language tag falls back to the root collation.
+    /// Requires the `collation` feature.
+    #[cfg(feature = "collation")]
+    pub fn with_collation(mut self, locale: &str) -> SwitchHelper {
+        use crate::collation::Collation;
+
+        self.comparison.collation =
+            Collation::for_locale(locale).or_else(|| Collation::for_locale("und"));
+        self
+    }
+
+    /// Compares strings with a unit, like `"5MB"`, `"1500ms"`, or `"2h"`, by
+    /// the quantity they stand for, unless a `{{#switch}}` overrides it with
+    /// `units=false`. Sizes are read in bytes and durations in milliseconds,
+    /// so `"1.5s"` also matches `{{#case 1500}}`.
+    pub fn with_units(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.units = enabled;
+        self
+    }
+
+    /// Compares currency-formatted strings like `"1.234,56 €"` or
+    /// `"$1,234.56"` by their amount and currency, unless a `{{#switch}}`
+    /// overrides it with `currency=false`. A `{{#case}}` listing a bare
+    /// currency like `"EUR"` or `"€"` matches any amount in it. Requires the
+    /// `currency` feature.
+    #[cfg(feature = "currency")]
+    pub fn with_currency(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.currency = enabled;
+        self
+    }
+
+    /// Compares strings that are both RFC 3339 timestamps as instants, so
+    /// `"2024-01-01T00:00:00Z"` matches `{{#case "2024-01-01T01:00:00+01:00"}}`,
+    /// unless a `{{#switch}}` overrides it with `instants=false`. Requires the
+    /// `instants` feature.
+    #[cfg(feature = "instants")]
+    pub fn with_instants(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.instants = enabled;
+        self
+    }
+
+    /// Compares strings that are both UUIDs regardless of case, hyphens,
+    /// braces, or a `urn:uuid:` prefix, unless a `{{#switch}}` overrides it
+    /// with `uuids=false`.
+    pub fn with_uuids(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.uuids = enabled;
+        self
+    }
+
+    /// Compares strings that are both CSS colors by their channels, so
+    /// `"#fff"` matches `{{#case "rgb(255, 255, 255)"}}`, unless a
+    /// `{{#switch}}` overrides it with `colors=false`.
+    pub fn with_colors(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.colors = enabled;
+        self
+    }
+
+    /// Compares the field at the JSON Pointer `pointer`, such as `/version`,
+    /// of object and array values with `comparator`, see
+    /// [`FieldComparator`]. A pointer of `""` compares whole values.
+    pub fn with_field_comparator(
+        mut self,
+        pointer: &str,
+        comparator: FieldComparator,
+    ) -> SwitchHelper {
+        Arc::make_mut(&mut self.comparison.fields).insert(pointer.to_owned(), comparator);
+        self
+    }
+
+    /// Reads the strings `"yes"`, `"on"`, and `"true"` as `true`, and `"no"`,
+    /// `"off"`, and `"false"` as `false`, in any case, when compared against
+    /// a boolean, so values from YAML-derived config match
+    /// `{{#case true}}`, unless a `{{#switch}}` overrides it with
+    /// `yaml_bools=false`.
+    pub fn with_yaml_bools(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.yaml_bools = enabled;
+        self
+    }
+
+    /// Compares scalar values by the strings they render as, so `42` matches
+    /// `{{#case "42"}}` and `true` matches `{{#case "true"}}`, unless a
+    /// `{{#switch}}` overrides it with `stringify=false`. Arrays and objects
+    /// are still compared by their elements.
+    pub fn with_stringify(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.stringify = enabled;
+        self
+    }
+
+    /// Compares strings that are both phone numbers in international format
+    /// by their E.164 form, so `"+1 (415) 555-0100"` matches
+    /// `{{#case "0014155550100"}}`, unless a `{{#switch}}` overrides it with
+    /// `phones=false`. Numbers are parsed and validated with the metadata of
+    /// libphonenumber, and invalid ones compare as plain strings. Requires
+    /// the `phones` feature.
+    #[cfg(feature = "phones")]
+    pub fn with_phones(mut self, enabled: bool) -> SwitchHelper {
+        self.comparison.phones = enabled;
+        self
+    }
+
+    /// Bounds the size of values a `{{#switch}}` will compare, see
+    /// [`CompareLimits`].
+    pub fn with_compare_limits(mut self, limits: CompareLimits) -> SwitchHelper {
+        self.comparison.limits = Some(limits);
+        self
+    }
+
+    /// Decides what happens when a `{{#switch}}` value is an array or an
+    /// object, see [`ContainerPolicy`].
+    pub fn with_container_policy(mut self, policy: ContainerPolicy) -> SwitchHelper {
+        self.containers = policy;
+        self
+    }
+
+    /// Masks switch values and the values `{{#case}}` arms list in errors,
+    /// panics, and traces with a short digest of them, as they often hold
+    /// personal data such as emails or user ids. Equal values get the same
+    /// mask, so repeats still show, and arm labels are reported as is. The
+    /// digest is not salted, so short values can still be recovered by
+    /// guessing.
+    pub fn with_redacted_values(mut self, enabled: bool) -> SwitchHelper {
+        self.redact = enabled;
+        self
+    }
+
+    /// Panics in debug builds when a `{{#switch}}` renders neither a
+    /// `{{#case}}` nor a `{{#default}}`. Release builds are unaffected. This
+    /// is the only panic the helpers raise on purpose, so leave it off when
+    /// rendering untrusted templates.
+    pub fn with_debug_assert_match(mut self, enabled: bool) -> SwitchHelper {
+        self.debug_assert_match = enabled;
+        self
+    }
+
+    /// Generates JavaScript registering `switch`, `case`, and `default`
+    /// helpers on a global `Handlebars` with the same semantics and options
+    /// as this helper, so templates shared with a browser render the same
+    /// there. The generated helpers throw on hash parameters they do not
+    /// implement, such as the partial matches of `{{#case}}`.
+    ///
+    /// Options beyond array equality, case styles, UUIDs, and aliases have
+    /// no JavaScript equivalent and fail with [`UnsupportedOption`], while
+    /// those that do not change the output, such as memoization and tracing,
+    /// are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use handlebars_switch::SwitchHelper;
+    ///
+    /// let js = SwitchHelper::new().with_uuids(true).to_javascript().unwrap();
+    /// assert!(js.contains("Handlebars.registerHelper(\"switch\""));
+    ///
+    /// assert!(SwitchHelper::new().with_units(true).to_javascript().is_err());
+    /// ```
+    pub fn to_javascript(&self) -> Result<String, UnsupportedOption> {
+        let comparison = &self.comparison;
+        let unsupported = [
+            (comparison.limits.is_some(), "with_compare_limits"),
+            (comparison.units, "with_units"),
+            (comparison.colors, "with_colors"),
+            (comparison.yaml_bools, "with_yaml_bools"),
+            (comparison.stringify, "with_stringify"),
+            (comparison.insensitive, "with_case_insensitive"),
+            (!comparison.fields.is_empty(), "with_field_comparator"),
+            #[cfg(feature = "unicode")]
+            (comparison.ignore_diacritics, "with_ignore_diacritics"),
+            #[cfg(feature = "collation")]
+            (comparison.collation.is_some(), "with_collation"),
+            #[cfg(feature = "currency")]
+            (comparison.currency, "with_currency"),
+            #[cfg(feature = "instants")]
+            (comparison.instants, "with_instants"),
+            #[cfg(feature = "phones")]
+            (comparison.phones, "with_phones"),
+            (self.overrides.is_some(), "with_overrides"),
+            (!self.sources.is_empty(), "with_arm_source"),
+            (self.format.is_some(), "for_format"),
+            (
+                self.on_arm_error != ArmErrorPolicy::Propagate,
+                "with_arm_error_policy",
+            ),
+            (self.compat != CompatMode::Native, "with_compat"),
+            (
+                self.containers != ContainerPolicy::Compare,
+                "with_container_policy",
+            ),
+            (!self.post.is_empty(), "with_post_processor"),
+            (!self.sentinels.is_empty(), "with_sentinel"),
+            (self.normalizer.is_some(), "with_normalizer"),
+            (!self.normalizers.is_empty(), "with_named_normalizer"),
+        ];
+        if let Some((_, option)) = unsupported.iter().find(|(set, _)| *set) {
+            return Err(UnsupportedOption(option));
+        }
+
+        let arrays = match comparison.arrays {
+            ArrayEquality::Positional => "positional",
+            ArrayEquality::Set => "set",
+        };
+        Ok(generate(&json!({
+            "arrays": arrays,
+            "ignore_case_style": comparison.ignore_case_style,
+            "uuids": comparison.uuids,
+            "aliases": self.aliases,
+        })))
+    }
+}
+
+impl HelperDef for SwitchHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        // Read in the switch variable or expression
+        if h.params().is_empty() {
+            return Err(RenderErrorReason::ParamNotFoundForIndex("switch", 0).into());
+        }
+
+        // Borrow the values so only the parts `ptr=` picks out get cloned
+        let values = h.params().iter().map(|x| Cow::Borrowed(x.value()));
+        self.render_values(values.collect(), h, r, ctx, rc, out)
+    }
+}
+
+impl SwitchHelper {
+    /// Renders the block of `h` as a `{{#switch}}` on `expression_value`, so
+    /// companion helpers can switch on a value they computed themselves.
+    pub(crate) fn render_switch<'reg: 'rc, 'rc>(
+        &self,
+        expression_value: Cow<'_, Value>,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.render_values(vec![expression_value], h, r, ctx, rc, out)
+    }
+
+    /// Brings a value switched on into the form its arms compare against,
+    /// before normalization, or `None` when nothing is at its `ptr=`
+    fn prepare(&self, value: Cow<'_, Value>, h: &Helper) -> Result<Option<Value>, RenderError> {
+        let Some(value) = point_into(value, h)? else {
+            return Ok(None);
+        };
+        let value = self.containers.apply(value, |x| shown(self.redact, x))?;
+        Ok(Some(match decode_value(value, h)? {
+            Value::String(s) => self.aliases.get(&s).cloned().unwrap_or(Value::String(s)),
+            other => other,
+        }))
+    }
+
+    /// Renders the block of `h` as a `{{#switch}}` on `values`, which its
+    /// arms match as a tuple when there are several
+    fn render_values<'reg: 'rc, 'rc>(
+        &self,
+        values: Vec<Cow<'_, Value>>,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let tuple = (values.len() > 1).then_some(values.len());
+        let normalizer = self.normalizer_for(h)?;
+        // the arms see the values as they were before normalization
+        let mut switched = Vec::with_capacity(values.len());
+        let mut normalized = Vec::with_capacity(values.len());
+        let mut missing = h.params().iter().any(|x| x.is_value_missing());
+        for value in values {
+            let value = self.prepare(value, h)?.unwrap_or_else(|| {
+                missing = true;
+                Value::Null
+            });
+            normalized.push(match &normalizer {
+                Some(normalizer) => normalizer.normalize(value.clone()),
+                None => value.clone(),
+            });
+            switched.push(value);
+        }
+        let (switched, expression_value) = match tuple {
+            Some(_) => (Value::Array(switched), Value::Array(normalized)),
+            None => (
+                switched.pop().unwrap_or_default(),
+                normalized.pop().unwrap_or_default(),
+            ),
+        };
+
+        // Where the block begins, to tell apart switches in one template
+        let position = h
+            .template()
+            .and_then(|t| t.mapping.first())
+            .map(|m| (m.0, m.1));
+
+        let name = switch_name(h)?;
+        let forced = ForcedArm::for_switch(rc, name.as_deref());
+        let budget = Budget::from_hash(h)?;
+        let max_len = MaxLen::from_hash("switch", h)?;
+        let joiner = Joiner::from_hash(h)?;
+        let multi = is_truthy_hash(h, "multi");
+        let silent = is_truthy_hash(h, "silent");
+        let limit = h
+            .hash_get("limit")
+            .map(|x| {
+                x.value().as_u64().ok_or_else(|| {
+                    RenderErrorReason::HashTypeMismatchForName(
+                        "switch",
+                        "limit".to_owned(),
+                        "non-negative integer".to_owned(),
+                    )
+                })
+            })
+            .transpose()?;
+        let comparison = self.comparator_for(self.comparison.clone().with_hash(h)?, h)?;
+        let comparable = comparison.admits(&expression_value)?;
+
+        // Pick an arm up front when sharding on the value
+        let arms: Arc<[usize]> = h.template().map(direct_arms).unwrap_or_default().into();
+        let shard = Some(arms.len())
+            .filter(|arms| *arms > 0 && is_truthy_hash(h, "shard"))
+            .map(|arms| {
+                let hash = match self.shard_seed {
+                    Some(seed) => seeded_hash(seed, &expression_value),
+                    None => stable_hash(&expression_value),
+                };
+                (hash % arms as u64) as usize
+            });
+
+        // Note the value for the audit sink before it is handed to the arms
+        // or served from the memo
+        let observation = self.audit.as_ref().map(|_| SwitchObservation {
+            // blocks of other helpers leave the name of their own unnamed
+            // template behind
+            template: (rc.get_current_template_name())
+                .or(rc.get_root_template_name())
+                .cloned(),
+            name: name.clone(),
+            value: shown(self.redact, &expression_value),
+            arm: None,
+        });
+
+        // Look up earlier renders of this block when memoizing, unless a
+        // forced arm would end up cached for the value
+        let memo = self
+            .memo
+            .as_ref()
+            .filter(|_| {
+                is_truthy_hash(h, "memo")
+                    && h.template().is_some()
+                    && forced.is_none()
+                    && h.hash_get("source").is_none()
+            })
+            // blocks of unnamed templates would share keys with each other
+            .zip(rc.get_current_template_name())
+            .map(|(memo, template)| {
+                let key = MemoKey {
+                    template: template.clone(),
+                    position: position.unwrap_or_default(),
+                    value: expression_value.to_string(),
+                };
+                (memo, key)
+            });
+        let cached = memo.as_ref().and_then(|(memo, key)| memo.get(key));
+
+        if let Some(tracer) = &self.tracer {
+            tracer.enter(SwitchTrace {
+                template: rc.get_current_template_name().cloned(),
+                name: name.clone(),
+                position,
+                value: shown(self.redact, &expression_value),
+                arms: Vec::new(),
+                cached: cached.is_some(),
+                children: Vec::new(),
+            });
+        }
+        if let Some((rendered, arm)) = cached {
+            if let Some(tracer) = &self.tracer {
+                tracer.leave();
+            }
+            if let Some((sink, observation)) = self.audit.as_ref().zip(observation) {
+                sink.record(SwitchObservation { arm, ..observation });
+            }
+            if !silent {
+                out.write(&rendered)?;
+            }
+            return Ok(());
+        }
+
+        // Snapshot the overrides of a named switch for this render
+        let overrides = self
+            .overrides
+            .as_ref()
+            .zip(h.hash_get("name").and_then(|x| x.value().as_str()))
+            .and_then(|(overrides, name)| overrides.get(name));
+
+        let shown_value = self
+            .debug_assert_match
+            .then(|| shown(self.redact, &expression_value).to_string());
+
+        // Expose the hash parameters to the arms, then keep track of whether
+        // a match occurs within the block
+        let mut block_context = child_block(rc);
+        for (key, value) in h.hash() {
+            if !SWITCH_LOCALS.contains(key) {
+                block_context.set_local_var(key, value.value().clone());
+            }
+        }
+        block_context.set_local_var("match", json!(false));
+        block_context.set_local_var("switch_break", json!(false));
+        block_context.set_local_var("rendered", json!(0));
+        block_context.set_local_var("failed", json!(false));
+        block_context.set_local_var("broken", json!(false));
+        block_context.set_local_var("defaulted", json!(false));
+        block_context.set_local_var("fallthrough", json!(false));
+        if let Some(name) = h.block_param() {
+            let mut params = BlockParams::new();
+            params.add_value(name, switched.clone())?;
+            block_context.set_block_params(params);
+        }
+        block_context.set_local_var("switch_value", switched);
+        let mut local_rc = rc.clone();
+        local_rc.push_block(block_context);
+
+        let post = Some(self.post.clone()).filter(|x| !x.is_empty());
+
+        let mut case = CaseHelper {
+            expression_value,
+            parts: ValueParts::default(),
+            regexes: self.regexes.clone(),
+            tuple,
+            arms,
+            shard,
+            adaptive: None,
+            comparison,
+            comparable,
+            overrides: overrides.clone(),
+            tracer: self.tracer.clone(),
+            on_arm_error: self.on_arm_error,
+            budget,
+            compat: self.compat,
+            sentinels: self.sentinels.clone(),
+            normalizer,
+            post: post.clone(),
+            max_len,
+            joiner: joiner.clone(),
+            multi,
+            limit,
+            forced: forced.clone(),
+            redact: self.redact,
+            shadowed: self.shadowed.clone(),
+        };
+
+        // Pick the arm up front when its literals can be checked in any
+        // order, starting with the one that matched last
+        if let Some(order) = self.adaptive.as_ref().filter(|_| {
+            is_truthy_hash(h, "adaptive")
+                && tuple.is_none()
+                && shard.is_none()
+                && forced.is_none()
+                && overrides.is_none()
+                && comparable
+        }) {
+            let arms = h.template().zip(position).and_then(|(t, position)| {
+                let key = (rc.get_current_template_name().cloned(), position);
+                Some((key, literal_arms(t)?))
+            });
+            if let Some((key, arms)) = arms {
+                let eq = |x: &Value, y: &Value| {
+                    let case_of = |value: &Value| CaseHelper {
+                        expression_value: match &case.normalizer {
+                            Some(normalizer) => normalizer.normalize(value.clone()),
+                            None => value.clone(),
+                        },
+                        ..case.clone()
+                    };
+                    case_of(y).value_matches(x) || case_of(x).value_matches(y)
+                };
+                case.adaptive = order.select(key, &arms, eq, |x| case.value_matches(x));
+            }
+        }
+
+        // Reject values outside of `allowed=` before rendering any arm, though
+        // `nil_safe=true` lets missing values through
+        let nil_safe = missing && is_truthy_hash(h, "nil_safe");
+        let disallowed = match h.hash_get("allowed").map(|x| x.value()) {
+            None => None,
+            Some(Value::Array(allowed)) => {
+                (!nil_safe && !allowed.iter().any(|x| case.value_matches(x))).then(|| {
+                    RenderError::from(RenderErrorReason::Other(format!(
+                        "`{{{{#switch}}}}` value {} is not one of the allowed values",
+                        shown(case.redact, &case.expression_value)
+                    )))
+                })
+            }
+            Some(_) => Some(
+                RenderErrorReason::HashTypeMismatchForName(
+                    "switch",
+                    "allowed".to_owned(),
+                    "array".to_owned(),
+                )
+                .into(),
+            ),
+        };
+
+        // Fetch the arm rendering in place of the block from its source
+        let sourced = match disallowed {
+            Some(_) => None,
+            None => self.sourced_arm(h, name.as_deref(), |x| case.value_matches(x))?,
+        };
+
+        // Add the `{{#case}}` helper within the `{{#switch}}` block
+        local_rc.register_local_helper("case", Box::new(case));
+
+        // Add the `{{#default}}` helper within the `{{#switch}}` block
+        local_rc.register_local_helper(
+            "default",
+            Box::new(DefaultHelper {
+                overrides,
+                tracer: self.tracer.clone(),
+                budget,
+                compat: self.compat,
+                post: post.clone(),
+                max_len,
+                joiner: joiner.clone(),
+                multi,
+                shadowed: self.shadowed.clone(),
+            }),
+        );
+
+        // Add the `{{#format_case}}` helper within the `{{#switch}}` block
+        local_rc.register_local_helper(
+            "format_case",
+            Box::new(FormatCaseHelper {
+                format: self.format.clone(),
+                tracer: self.tracer.clone(),
+                post,
+                max_len,
+                joiner: joiner.clone(),
+                shadowed: self.shadowed.clone(),
+            }),
+        );
+
+        // Add the `{{break}}` helper when arms fall through
+        if self.compat == CompatMode::Fallthrough {
+            local_rc.register_local_helper("break", Box::new(BreakHelper));
+        }
+        local_rc.register_local_helper("fallthrough", Box::new(FallthroughHelper));
+
+        // Render the `{{#switch}}` block
+        let result = match (h.template(), memo, disallowed, sourced) {
+            (_, _, Some(err), _) => Err(err),
+            (_, _, None, Some(_)) if silent => Ok(()),
+            (_, _, None, Some((templates, picked))) => {
+                if let Some(block) = local_rc.block_mut() {
+                    block.set_local_var("match", json!(true));
+                }
+                render_replacement(&templates[picked], r, ctx, &mut local_rc, out)
+            }
+            (Some(t), None, None, None) if joiner.is_none() && !silent => {
+                t.render(r, ctx, &mut local_rc, out)
+            }
+            (Some(t), memo, None, None) => {
+                // only the arms are written when joining them, and nothing
+                // when silent
+                let mut buffer = StringOutput::new();
+                t.render(r, ctx, &mut local_rc, &mut buffer).and_then(|()| {
+                    let rendered = match &joiner {
+                        Some(joiner) => joiner.finish(),
+                        None => buffer.into_string()?,
+                    };
+                    if !silent {
+                        out.write(&rendered)?;
+                    }
+                    // a hit would skip the variables set by the arms, such
+                    // as those of `{{set}}`, `{{capture}}`, and `{{slot}}`
+                    let sets_vars = matches!(
+                        saved_vars(&local_rc),
+                        Some(Value::Object(vars)) if !vars.is_empty()
+                    );
+                    if let Some((memo, key)) = memo.filter(|_| !sets_vars) {
+                        let arm = local_rc.block().and_then(rendered_arm);
+                        memo.insert(key, (rendered, arm));
+                    }
+                    Ok(())
+                })
+            }
+            (None, _, None, None) => Ok(()),
+        };
+
+        let result = result
+            .and_then(|()| match &budget {
+                Some(budget) => budget.check(),
+                None => Ok(()),
+            })
+            .map_err(|err| match &name {
+                Some(name) => name_error(name, err),
+                None => err,
+            });
+
+        if let Some(shown_value) = shown_value.filter(|_| result.is_ok()) {
+            let rendered_arm = local_rc
+                .block()
+                .and_then(|block| block.get_local_var("match"))
+                .and_then(Value::as_bool)
+                .unwrap_or_default();
+            debug_assert!(
+                rendered_arm,
+                "`{{{{#switch}}}}`{} in template {:?} matched no arm for value {}",
+                name.as_ref().map(|x| format!(" {x:?}")).unwrap_or_default(),
+                rc.get_current_template_name(),
+                shown_value
+            );
+        }
+
+        if let Some((sink, observation)) = self.audit.as_ref().zip(observation) {
+            let arm = local_rc.block().and_then(rendered_arm);
+            sink.record(SwitchObservation { arm, ..observation });
+        }
+
+        // Carry the variables set by the arms out of the block
+        restore_vars(rc, saved_vars(&local_rc));
+        local_rc.pop_block();
+        if let Some(tracer) = &self.tracer {
+            tracer.leave();
+        }
+
+        result
+    }
+}
+
+/// The `@` variables a `{{#switch}}` keeps its own state in, which its hash
+/// parameters may not replace
+pub(crate) const SWITCH_LOCALS: &[&str] = &[
+    "match",
+    "switch_break",
+    "rendered",
+    "broken",
+    "failed",
+    "defaulted",
+    "fallthrough",
+    "switch_value",
+    "vars",
+];
+
+/// The kind of arm a `{{#switch}}` block rendered, judged by its `@` variables
+fn rendered_arm(block: &BlockContext) -> Option<ArmKind> {
+    let is_set = |name| {
+        block
+            .get_local_var(name)
+            .and_then(Value::as_bool)
+            .unwrap_or_default()
+    };
+    let cases = block
+        .get_local_var("rendered")
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+    if cases > 0 && !is_set("failed") {
+        Some(ArmKind::Case)
+    } else if is_set("defaulted") {
+        Some(ArmKind::Default)
+    } else if is_set("match") {
+        Some(ArmKind::FormatCase)
+    } else {
+        None
+    }
+}
+
+/// Reads the `name="..."` a switch is called by in diagnostics
+fn switch_name(h: &Helper) -> Result<Option<String>, RenderError> {
+    let Some(name) = h.hash_get("name") else {
+        return Ok(None);
+    };
+    match name.value().as_str() {
+        Some(name) => Ok(Some(name.to_owned())),
+        None => Err(RenderErrorReason::HashTypeMismatchForName(
+            "switch",
+            "name".to_owned(),
+            "string".to_owned(),
+        )
+        .into()),
+    }
+}
+
+/// Prefixes the message of a free-form error with the name of its switch,
+/// keeping where in the template it happened
+fn name_error(name: &str, err: RenderError) -> RenderError {
+    let RenderErrorReason::Other(message) = err.reason() else {
+        return err;
+    };
+    let mut named = RenderError::from(RenderErrorReason::Other(format!(
+        "`{{{{#switch}}}}` {name:?}: {message}"
+    )));
+    named.template_name = err.template_name;
+    named.line_no = err.line_no;
+    named.column_no = err.column_no;
+    named
+}
+
+/// The value to report in diagnostics, masked when redacting
+fn shown(redact: bool, value: &Value) -> Value {
+    if redact {
+        redacted(value)
+    } else {
+        value.clone()
+    }
+}
+
+/// Reads the `label="..."` an arm is reported by in traces
+fn arm_label(h: &Helper) -> Option<String> {
+    h.hash_get("label")
+        .and_then(|x| x.value().as_str())
+        .map(str::to_owned)
+}
+
+/// Reads a boolean hash parameter, treating anything else as `false`
+fn is_truthy_hash(h: &Helper, name: &str) -> bool {
+    h.hash_get(name)
+        .and_then(|x| x.value().as_bool())
+        .unwrap_or_default()
+}
+
+/// Picks out the part of the switch value named by the JSON Pointer in the
+/// `ptr=` hash parameter, or `None` when nothing is there. With `raw=true`, a
+/// string value is read as a JSON document first.
+fn point_into(value: Cow<'_, Value>, h: &Helper) -> Result<Option<Value>, RenderError> {
+    let ptr = match h.hash_get("ptr") {
+        Some(ptr) => Some(ptr.value().as_str().ok_or_else(|| {
+            RenderErrorReason::HashTypeMismatchForName(
+                "switch",
+                "ptr".to_owned(),
+                "string".to_owned(),
+            )
+        })?),
+        None => None,
+    };
+    if is_truthy_hash(h, "raw") {
+        if let Value::String(json) = &*value {
+            // text that is not JSON is there but no value
+            return Ok(parse_at(json, ptr.unwrap_or_default()).unwrap_or(Some(Value::Null)));
+        }
+    }
+    let Some(ptr) = ptr else {
+        return Ok(Some(value.into_owned()));
+    };
+    Ok(value.pointer(ptr).cloned())
+}
+
+/// Lists the `{{#case}}` arms directly inside a `{{#switch}}` block, by the
+/// address of their bodies, so an arm can tell its position when it renders
+/// without counting the arms rendered before it, which would also count those
+/// nested in other blocks and miss those skipped
+fn direct_arms(template: &Template) -> Vec<usize> {
+    template
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            TemplateElement::HelperBlock(ht) if ht.name.as_name() == Some("case") => {
+                ht.template.as_ref().map(body_address)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Identifies the body of a block for as long as its template is borrowed
+fn body_address(template: &Template) -> usize {
+    template as *const Template as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwitchHelper;
+    use crate::{
+        expand_inverse_cases, seeded_hash, stable_hash, AdaptiveOrder, ArmErrorPolicy, ArmKind,
+        ArmOverrides, ArmSource, ArrHelper, ArrayEquality, AuditLog, AuditSink, CompareLimits,
+        CompatMode, ContainerPolicy, FieldComparator, LimitExceeded, Lowercase, MemoCache,
+        Pipeline, RenderTracer, Sentinel, SetHelper, SourcedArm, SwitchObservation,
+        SwitchSomeHelper, Trim,
+    };
+    use handlebars::{Handlebars, RenderErrorReason};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_switch() {
+        let tpl = "\
+            {{#switch state}}\
+                {{#case \"page1\" \"page2\"}}\
+                    page 1 or 2\
+                    {{#switch s}}\
+                        {{#case 4}}s = 4{{/case}}\
+                    {{/switch}}\
+                {{/case}}\
+                {{#case \"page3\"}}page3{{/case}}\
+                {{#case \"page4\"}}page4{{/case}}\
+                {{#case \"page5\"}}\
+                    page5 - \
+                    {{#switch s}}\
+                        {{#case 3}}s = 3{{/case}}\
+                        {{#case 2}}s = 2{{/case}}\
+                        {{#case 1}}s = 1{{/case}}\
+                        {{#default}}unknown{{/default}}\
+                    {{/switch}}\
+                {{/case}}\
+                {{#default}}page0{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let r0 = handlebars.render("tpl", &json!({"state": "page2", "s": 1}));
+        assert_eq!(r0.ok().unwrap(), "page 1 or 2");
+
+        let r1 = handlebars.render("tpl", &json!({"state": "page5", "s": 1}));
+        assert_eq!(r1.ok().unwrap(), "page5 - s = 1");
+
+        let r2 = handlebars.render("tpl", &json!({"state": "page5", "s": 4}));
+        assert_eq!(r2.ok().unwrap(), "page5 - unknown");
+
+        let r3 = handlebars.render("tpl", &json!({"state": "page0", "s": 1}));
+        assert_eq!(r3.ok().unwrap(), "page0");
+    }
+
+    #[test]
+    fn test_arms_keep_enclosing_context() {
+        let tpl = "\
+            {{#each items}}\
+                {{#switch type}}\
+                    {{#case \"a\"}}{{@index}}:{{name}}{{/case}}\
+                    {{#default}}{{@index}}:?{{/default}}\
+                {{/switch}}\
+            {{/each}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let data = json!({"name": "root", "items": [{"type": "a", "name": "x"}, {"type": "b"}]});
+        assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "0:x1:?");
+    }
+
+    #[test]
+    fn test_missing_key_renders_default() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert_eq!(handlebars.render_template(tpl, &json!({})).unwrap(), "User");
+    }
+
+    #[test]
+    fn test_case_helper_not_defined() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+            {{#case \"test\"}}Check{{/case}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert!(handlebars
+            .render_template(tpl, &json!({"access": "admin"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_default_helper_not_defined() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+            {{#default \"test\"}}Check{{/default}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert!(handlebars
+            .render_template(tpl, &json!({"access": "admin"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_only_case_exists_with_match() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert_eq!(
+            handlebars
+                .render_template(tpl, &json!({"access": "admin"}))
+                .unwrap(),
+            "Admin"
+        );
+    }
+
+    #[test]
+    fn test_only_case_exists_with_no_match() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert_eq!(
+            handlebars
+                .render_template(tpl, &json!({"access": "unknown"}))
+                .unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_shard_is_stable() {
+        let tpl = "\
+            {{#switch id shard=true}}\
+                {{#case \"ignored\"}}a{{/case}}\
+                {{#case}}b{{/case}}\
+                {{#case}}c{{/case}}\
+                {{#default}}never{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        // arms nested in other blocks are neither counted nor selected
+        let nested = tpl.replace(
+            "{{#case}}b",
+            "{{#if true}}{{#case}}nested{{/case}}{{/if}}{{#case}}b",
+        );
+
+        let arms = ["a", "b", "c"];
+        for id in ["alice", "bob", "carol", "dave"] {
+            let expected = arms[(stable_hash(&json!(id)) % 3) as usize];
+            for tpl in [tpl, &nested] {
+                assert_eq!(
+                    handlebars
+                        .render_template(tpl, &json!({ "id": id }))
+                        .unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_shard_seed() {
+        let tpl = "\
+            {{#switch id shard=true}}\
+                {{#case}}a{{/case}}\
+                {{#case}}b{{/case}}\
+                {{#case}}c{{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_shard_seed(9)));
+
+        let arms = ["a", "b", "c"];
+        for id in ["alice", "bob", "carol", "dave"] {
+            let expected = arms[(seeded_hash(9, &json!(id)) % 3) as usize];
+            assert_eq!(
+                handlebars
+                    .render_template(tpl, &json!({ "id": id }))
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_adaptive_order() {
+        let tpl = "\
+            {{#switch status adaptive=true}}\
+                {{#case \"a\"}}A{{/case}}\
+                {{#case \"b\" \"c\"}}BC{{/case}}\
+                {{#case \"d\" label=\"last\"}}D{{/case}}\
+                {{#default}}?{{/default}}\
+            {{/switch}}\
+        ";
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let counting = FieldComparator::new(move |a, b| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            a == b
+        });
+        let order = AdaptiveOrder::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new()
+                    .with_field_comparator("", counting)
+                    .with_adaptive_order(order.clone()),
+            ),
+        );
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let render = |status| {
+            calls.store(0, Ordering::SeqCst);
+            let rendered = handlebars
+                .render("tpl", &json!({ "status": status }))
+                .unwrap();
+            (rendered, calls.load(Ordering::SeqCst))
+        };
+        assert_eq!(render("d").0, "D");
+        assert_eq!(render("d"), ("D".to_owned(), 1));
+        assert_eq!(render("c"), ("BC".to_owned(), 4));
+        assert_eq!(render("c"), ("BC".to_owned(), 2));
+        assert_eq!(render("z"), ("?".to_owned(), 4));
+        assert_eq!(order.len(), 1);
+
+        // arms listing the same literal keep the template order
+        let tpl =
+            "{{#switch status adaptive=true}}{{#case 1}}A{{/case}}{{#case 1}}B{{/case}}{{/switch}}";
+        for _ in 0..2 {
+            assert_eq!(
+                handlebars
+                    .render_template(tpl, &json!({"status": 1}))
+                    .unwrap(),
+                "A"
+            );
+        }
+    }
+
+    #[test]
+    fn test_memo_reuses_output() {
+        let tpl = "\
+            {{#switch status memo=true}}\
+                {{#case \"ok\"}}OK {{count}}{{/case}}\
+                {{#default}}??? {{count}}{{/default}}\
+            {{/switch}}\
+            {{#switch status}}{{#case \"ok\"}} {{count}}{{/case}}{{/switch}}\
+        ";
+
+        let memo = MemoCache::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_memo(memo.clone())),
+        );
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let r0 = handlebars.render("tpl", &json!({"status": "ok", "count": 1}));
+        assert_eq!(r0.unwrap(), "OK 1 1");
+
+        // the memoized switch ignores the changed count, the other one does not
+        let r1 = handlebars.render("tpl", &json!({"status": "ok", "count": 2}));
+        assert_eq!(r1.unwrap(), "OK 1 2");
+
+        let r2 = handlebars.render("tpl", &json!({"status": "bad", "count": 3}));
+        assert_eq!(r2.unwrap(), "??? 3");
+        assert_eq!(memo.len(), 2);
+
+        memo.invalidate_value(&json!("ok"));
+        let r3 = handlebars.render("tpl", &json!({"status": "ok", "count": 4}));
+        assert_eq!(r3.unwrap(), "OK 4 4");
+
+        // unnamed templates are never memoized, so they cannot share outputs
+        let unnamed = |tpl: &str| handlebars.render_template(tpl, &json!({"status": "ok"}));
+        let a = unnamed("{{#switch status memo=true}}{{#case \"ok\"}}A{{/case}}{{/switch}}");
+        let b = unnamed("{{#switch status memo=true}}{{#case \"ok\"}}B{{/case}}{{/switch}}");
+        assert_eq!((a.unwrap(), b.unwrap()), ("A".to_owned(), "B".to_owned()));
+        assert_eq!(memo.len(), 2);
+
+        // blocks that set variables render every time to set them again
+        handlebars.register_helper("set", Box::new(SetHelper));
+        let tpl = "\
+            {{#switch status memo=true}}\
+                {{#case \"paid\"}}P{{set class=\"is-paid\"}}{{/case}}\
+            {{/switch}}\
+            [{{@class}}]\
+        ";
+        assert!(handlebars.register_template_string("vars", tpl).is_ok());
+        for _ in 0..2 {
+            let rendered = handlebars.render("vars", &json!({"status": "paid"}));
+            assert_eq!(rendered.unwrap(), "P[is-paid]");
+        }
+        assert_eq!(memo.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "matched no arm for value \"nobody\"")]
+    fn test_debug_assert_match_panics() {
+        let tpl = "{{#switch access}}{{#case \"admin\"}}Admin{{/case}}{{/switch}}";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_debug_assert_match(true)),
+        );
+
+        assert_eq!(
+            handlebars
+                .render_template(tpl, &json!({"access": "admin"}))
+                .unwrap(),
+            "Admin"
+        );
+        let _ = handlebars.render_template(tpl, &json!({"access": "nobody"}));
+    }
+
+    #[test]
+    fn test_array_equality() {
+        let tpl = "\
+            {{#switch roles arrays=mode}}\
+                {{#case admin}}admin{{/case}}\
+                {{#default}}user{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let data = |mode| json!({"roles": ["b", "a", "b"], "admin": ["a", "b"], "mode": mode});
+        let r0 = handlebars.render_template(tpl, &data("positional"));
+        assert_eq!(r0.unwrap(), "user");
+        let r1 = handlebars.render_template(tpl, &data("set"));
+        assert_eq!(r1.unwrap(), "admin");
+        assert!(handlebars.render_template(tpl, &data("bag")).is_err());
+
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_array_equality(ArrayEquality::Set)),
+        );
+        let tpl = "{{#switch roles}}{{#case admin}}admin{{/case}}{{/switch}}";
+        let r2 = handlebars.render_template(tpl, &data("ignored"));
+        assert_eq!(r2.unwrap(), "admin");
+    }
+
+    #[test]
+    fn test_compare_limits() {
+        let tpl = "\
+            {{#switch this}}\
+                {{#case this}}same{{/case}}\
+                {{#default}}too big{{/default}}\
+            {{/switch}}\
+        ";
+
+        let limits = CompareLimits {
+            max_depth: 2,
+            ..CompareLimits::default()
+        };
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_compare_limits(limits)),
+        );
+
+        let r0 = handlebars.render_template(tpl, &json!({"a": {"b": 1}}));
+        assert_eq!(r0.unwrap(), "same");
+        let r1 = handlebars.render_template(tpl, &json!({"a": {"b": [1]}}));
+        assert_eq!(r1.unwrap(), "too big");
+
+        let limits = CompareLimits {
+            on_exceeded: LimitExceeded::Error,
+            ..limits
+        };
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_compare_limits(limits)),
+        );
+        assert!(handlebars
+            .render_template(tpl, &json!({"a": {"b": [1]}}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_inverse_case() {
+        let tpl = "\
+            {{#switch role}}\
+                {{#case \"guest\"}}Welcome{{/case}}\
+                {{^case \"admin\" \"owner\"}}Restricted{{/case}}\
+                {{#default}}Full access{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        assert!(handlebars
+            .register_template_string("tpl", expand_inverse_cases(tpl))
+            .is_ok());
+
+        let r0 = handlebars.render("tpl", &json!({"role": "guest"}));
+        assert_eq!(r0.unwrap(), "Welcome");
+        let r1 = handlebars.render("tpl", &json!({"role": "editor"}));
+        assert_eq!(r1.unwrap(), "Restricted");
+        let r2 = handlebars.render("tpl", &json!({"role": "owner"}));
+        assert_eq!(r2.unwrap(), "Full access");
+    }
+
+    #[test]
+    fn test_case_else_placeholder() {
+        let tpl = "\
+            {{#switch level}}\
+                <td>{{#case \"low\"}}L{{else}}-{{/case}}</td>\
+                <td>{{#case \"mid\"}}M{{else}}-{{/case}}</td>\
+                <td>{{#case \"high\"}}H{{else}}-{{/case}}</td>\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let r0 = handlebars.render_template(tpl, &json!({"level": "mid"}));
+        assert_eq!(r0.unwrap(), "<td>-</td><td>M</td><td>-</td>");
+        let r1 = handlebars.render_template(tpl, &json!({"level": "none"}));
+        assert_eq!(r1.unwrap(), "<td>-</td><td>-</td><td>-</td>");
+    }
+
+    #[test]
+    fn test_default_before_case_renders_once() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#default}}User{{/default}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert_eq!(
+            handlebars
+                .render_template(tpl, &json!({"access": "admin"}))
+                .unwrap(),
+            "User"
+        );
+    }
+
+    #[test]
+    fn test_only_default_exists() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        assert_eq!(
+            handlebars
+                .render_template(tpl, &json!({"access": "admin"}))
+                .unwrap(),
+            "User"
+        );
+    }
+
+    #[test]
+    fn test_arm_overrides() {
+        let tpl = "\
+            {{#each users}}\
+                {{#switch access name=\"access\"}}\
+                    {{#case \"admin\"}}Admin{{/case}}\
+                    {{#case \"owner\"}}Owner{{/case}}\
+                    {{#default}}User{{/default}}\
+                {{/switch}}|\
+            {{/each}}\
+            {{#switch 1}}{{#case 1}}Unnamed{{/case}}{{/switch}}\
+        ";
+
+        let overrides = ArmOverrides::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_overrides(overrides.clone())),
+        );
+        let data = json!({"users": [
+            {"access": "admin", "name": "Al"},
+            {"access": "owner", "name": "Bo"},
+            {"access": "guest", "name": "Cy"},
+        ]});
+
+        let r0 = handlebars.render_template(tpl, &data);
+        assert_eq!(r0.unwrap(), "Admin|Owner|User|Unnamed");
+
+        let document = json!({
+            "access": {"cases": {"admin": "Admin {{name}}", "owner": null}, "default": "-"},
+            "other": {"cases": {"1": "Other"}},
+        });
+        assert!(overrides.set(&document).is_ok());
+        let r1 = handlebars.render_template(tpl, &data);
+        assert_eq!(r1.unwrap(), "Admin Al|-|-|Unnamed");
+
+        assert!(overrides.set(&json!({"access": {"default": null}})).is_ok());
+        let r2 = handlebars.render_template(tpl, &data);
+        assert_eq!(r2.unwrap(), "Admin|Owner||Unnamed");
+
+        // replacements render where the arm would, with the same context
+        let document = json!({"access": {"cases": {
+            "admin": "{{@index}} {{name}} of {{../../team}}/{{@root.team}} {{@switch_value}}",
+        }}});
+        assert!(overrides.set(&document).is_ok());
+        let data = json!({"team": "Ops", "users": [{"access": "admin", "name": "Al"}]});
+        let r3 = handlebars.render_template(tpl, &data);
+        assert_eq!(r3.unwrap(), "0 Al of Ops/Ops admin|Unnamed");
+    }
+
+    #[test]
+    fn test_aliases() {
+        let tpl = "\
+            {{#switch lang}}\
+                {{#case \"typescript\"}}TS{{/case}}\
+                {{#case 1}}One{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        let helper = SwitchHelper::new()
+            .with_aliases([("ts", json!("typescript")), ("one", json!(1))])
+            .with_aliases([("tsx", "typescript"), ("typescript", "ts")]);
+        handlebars.register_helper("switch", Box::new(helper));
+
+        let render = |lang| handlebars.render_template(tpl, &json!({ "lang": lang }));
+        assert_eq!(render(json!("ts")).unwrap(), "TS");
+        assert_eq!(render(json!("tsx")).unwrap(), "TS");
+        assert_eq!(render(json!("one")).unwrap(), "One");
+        // aliases are not chained
+        assert_eq!(render(json!("typescript")).unwrap(), "Other");
+        assert_eq!(render(json!(["ts"])).unwrap(), "Other");
+    }
+
+    #[test]
+    fn test_render_trace() {
+        let tpl = "\
+            {{#switch access}}\
+                {{#case \"admin\" \"owner\"}}Admin{{/case}}\
+                {{#case \"user\" label=\"member\"}}\
+                    {{#switch plan}}{{#case \"pro\"}}Pro{{/case}}{{/switch}}\
+                {{/case}}\
+                {{#default}}Nobody{{/default}}\
+            {{/switch}}\
+        ";
+
+        let tracer = RenderTracer::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_tracer(tracer.clone())),
+        );
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        let r0 = handlebars.render("tpl", &json!({"access": "user", "plan": "free"}));
+        assert_eq!(r0.unwrap(), "");
+
+        let trace = tracer.take();
+        assert_eq!(trace.len(), 1);
+        let outer = &trace[0];
+        assert_eq!(outer.template.as_deref(), Some("tpl"));
+        assert_eq!(outer.value, json!("user"));
+        let arms: Vec<_> = outer.arms.iter().map(|x| (x.kind, x.matched)).collect();
+        assert_eq!(
+            arms,
+            [
+                (ArmKind::Case, false),
+                (ArmKind::Case, true),
+                (ArmKind::Default, false)
+            ]
+        );
+        assert_eq!(outer.arms[0].values, [json!("admin"), json!("owner")]);
+        assert_eq!(outer.arms[1].label.as_deref(), Some("member"));
+
+        assert_eq!(outer.children.len(), 1);
+        assert_eq!(outer.children[0].value, json!("free"));
+        assert!(outer.children[0].chosen().is_none());
+    }
+
+    #[test]
+    fn test_format_case() {
+        let tpl = "\
+            {{#switch kind}}\
+                {{#case \"alert\"}}Alert{{/case}}\
+                {{#format_case \"html\" \"amp\"}}<p>{{kind}}</p>{{else}}-{{/format_case}}\
+                {{#default}}{{kind}}{{/default}}\
+            {{/switch}}\
+        ";
+        let render = |helper| {
+            let mut handlebars = Handlebars::new();
+            handlebars.register_helper("switch", Box::new(helper));
+            let r0 = handlebars.render_template(tpl, &json!({"kind": "alert"}));
+            let r1 = handlebars.render_template(tpl, &json!({"kind": "note"}));
+            (r0.unwrap(), r1.unwrap())
+        };
+
+        assert_eq!(
+            render(SwitchHelper::for_format("html")),
+            ("Alert-".to_owned(), "<p>note</p>".to_owned())
+        );
+        assert_eq!(
+            render(SwitchHelper::for_format("amp")),
+            ("Alert-".to_owned(), "<p>note</p>".to_owned())
+        );
+        assert_eq!(
+            render(SwitchHelper::for_format("text")),
+            ("Alert-".to_owned(), "-note".to_owned())
+        );
+        assert_eq!(
+            render(SwitchHelper::new()),
+            ("Alert-".to_owned(), "-note".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_ignore_case_style() {
+        let tpl = "\
+            {{#switch event}}\
+                {{#case \"order_created\"}}Created{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_ignore_case_style(true)),
+        );
+
+        let render = |tpl: &str, event| handlebars.render_template(tpl, &json!({ "event": event }));
+        assert_eq!(render(tpl, "orderCreated").unwrap(), "Created");
+        assert_eq!(render(tpl, "OrderCreated").unwrap(), "Created");
+        assert_eq!(render(tpl, "order_created").unwrap(), "Created");
+        assert_eq!(render(tpl, "orderCancelled").unwrap(), "Other");
+
+        let strict = tpl.replace(
+            "{{#switch event}}",
+            "{{#switch event ignore_case_style=false}}",
+        );
+        assert_eq!(render(&strict, "orderCreated").unwrap(), "Other");
+        let invalid = tpl.replace("{{#switch event}}", "{{#switch event ignore_case_style=1}}");
+        assert!(render(&invalid, "orderCreated").is_err());
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let tpl = "\
+            {{#switch role}}\
+                {{#case \"admin\"}}Admin{{/case}}\
+                {{#case \"Straße\"}}Street{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper(
+            "iswitch",
+            Box::new(SwitchHelper::new().with_case_insensitive(true)),
+        );
+
+        let render = |tpl: &str, role| handlebars.render_template(tpl, &json!({ "role": role }));
+        assert_eq!(render(tpl, "ADMIN").unwrap(), "Other");
+        let opted = tpl.replace("{{#switch role}}", "{{#switch role insensitive=true}}");
+        assert_eq!(render(&opted, "ADMIN").unwrap(), "Admin");
+        assert_eq!(render(&opted, "STRASSE").unwrap(), "Street");
+
+        let global = tpl.replace("switch", "iswitch");
+        assert_eq!(render(&global, "Admin").unwrap(), "Admin");
+        let strict = global.replace("{{#iswitch role}}", "{{#iswitch role insensitive=false}}");
+        assert_eq!(render(&strict, "Admin").unwrap(), "Other");
+        let invalid = tpl.replace("{{#switch role}}", "{{#switch role insensitive=\"yes\"}}");
+        assert!(render(&invalid, "admin").is_err());
+    }
+
+    #[cfg(feature = "collation")]
+    #[test]
+    fn test_collation() {
+        let tpl = "\
+            {{#switch street}}\
+                {{#case \"Müller\"}}Street{{/case}}\
+                {{#case \"ışık\"}}Light{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_collation("de")));
+
+        let render = |tpl: &str, street| {
+            handlebars
+                .render_template(tpl, &json!({ "street": street }))
+                .unwrap()
+        };
+        assert_eq!(render(tpl, "MÜLLER"), "Street");
+        assert_eq!(render(tpl, "müller"), "Street");
+        assert_eq!(render(tpl, "Muller"), "Other");
+        assert_eq!(render(tpl, "IŞIK"), "Other");
+        let turkish = tpl.replace("{{#switch street}}", "{{#switch street collation=\"tr\"}}");
+        assert_eq!(render(&turkish, "IŞIK"), "Light");
+        let invalid = tpl.replace("{{#switch street}}", "{{#switch street collation=1}}");
+        assert!(handlebars
+            .render_template(&invalid, &json!({"street": ""}))
+            .is_err());
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn test_ignore_diacritics() {
+        let tpl = "\
+            {{#switch city}}\
+                {{#case \"Sao Paulo\"}}SP{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_ignore_diacritics(true)),
+        );
+
+        let r0 = handlebars.render_template(tpl, &json!({"city": "São Paulo"}));
+        assert_eq!(r0.unwrap(), "SP");
+        let strict = tpl.replace(
+            "{{#switch city}}",
+            "{{#switch city ignore_diacritics=false}}",
+        );
+        let r1 = handlebars.render_template(&strict, &json!({"city": "São Paulo"}));
+        assert_eq!(r1.unwrap(), "Other");
+    }
+
+    #[test]
+    fn test_units() {
+        let tpl = "\
+            {{#switch quota}}\
+                {{#case \"1GB\"}}Small{{/case}}\
+                {{#case \"1TB\"}}Large{{/case}}\
+                {{#default}}Custom{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_units(true)));
+
+        let render = |tpl: &str, quota| handlebars.render_template(tpl, &json!({ "quota": quota }));
+        assert_eq!(render(tpl, json!("1000MB")).unwrap(), "Small");
+        assert_eq!(render(tpl, json!("1000 GB")).unwrap(), "Large");
+        assert_eq!(render(tpl, json!(1e9)).unwrap(), "Small");
+        assert_eq!(render(tpl, json!("1GiB")).unwrap(), "Custom");
+
+        let strict = tpl.replace("{{#switch quota}}", "{{#switch quota units=false}}");
+        assert_eq!(render(&strict, json!("1000MB")).unwrap(), "Custom");
+    }
+
+    #[cfg(feature = "currency")]
+    #[test]
+    fn test_currency() {
+        let tpl = "\
+            {{#switch total}}\
+                {{#case \"0 €\"}}Free{{/case}}\
+                {{#case \"EUR\"}}Euro invoice{{/case}}\
+                {{#case \"$\"}}Dollar invoice{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_currency(true)));
+
+        let render = |total| handlebars.render_template(tpl, &json!({ "total": total }));
+        assert_eq!(render("EUR 0,00").unwrap(), "Free");
+        assert_eq!(render("1.234,56 €").unwrap(), "Euro invoice");
+        assert_eq!(render("$1,234.56").unwrap(), "Dollar invoice");
+        assert_eq!(render("£3").unwrap(), "Other");
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        let tpl = "\
+            {{#switch mode}}\
+                {{#case \"0o755\"}}rwxr-xr-x{{/case}}\
+                {{#case \"0o644\" \"0x1A4\"}}rw-r--r--{{/case}}\
+                {{#default}}other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render = |mode| handlebars.render_template(tpl, &json!({ "mode": mode }));
+        assert_eq!(render(json!(493)).unwrap(), "rwxr-xr-x");
+        assert_eq!(render(json!(420)).unwrap(), "rw-r--r--");
+        assert_eq!(render(json!("420")).unwrap(), "other");
+    }
+
+    #[cfg(feature = "instants")]
+    #[test]
+    fn test_instants() {
+        let tpl = "\
+            {{#switch at}}\
+                {{#case \"2024-01-01T00:00:00Z\"}}New year{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_instants(true)));
+
+        let render = |tpl: &str, at| handlebars.render_template(tpl, &json!({ "at": at }));
+        assert_eq!(
+            render(tpl, "2024-01-01T09:00:00+09:00").unwrap(),
+            "New year"
+        );
+        assert_eq!(render(tpl, "2024-01-01T09:00:00Z").unwrap(), "Other");
+
+        let strict = tpl.replace("{{#switch at}}", "{{#switch at instants=false}}");
+        assert_eq!(
+            render(&strict, "2024-01-01T09:00:00+09:00").unwrap(),
+            "Other"
+        );
+    }
+
+    #[test]
+    fn test_uuids() {
+        let tpl = "\
+            {{#switch tenant}}\
+                {{#case \"00000000-0000-0000-0000-00000000000a\"}}Internal{{/case}}\
+                {{#default}}Customer{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render =
+            |tpl: &str, tenant| handlebars.render_template(tpl, &json!({ "tenant": tenant }));
+        let tenant = "{00000000-0000-0000-0000-00000000000A}";
+        assert_eq!(render(tpl, tenant).unwrap(), "Customer");
+        let uuids = tpl.replace("{{#switch tenant}}", "{{#switch tenant uuids=true}}");
+        assert_eq!(render(&uuids, tenant).unwrap(), "Internal");
+    }
+
+    #[test]
+    fn test_decode() {
+        let tpl = "\
+            {{#switch source decode=decode}}\
+                {{#case \"new york\"}}NY{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render = |source, decode| {
+            handlebars.render_template(tpl, &json!({"source": source, "decode": decode}))
+        };
+        assert_eq!(render("new%20york", "url").unwrap(), "NY");
+        assert_eq!(render("new+york", "url").unwrap(), "NY");
+        assert_eq!(render("bmV3IHlvcms=", "base64").unwrap(), "NY");
+        assert_eq!(render("bmV3IHlvcms", "base64").unwrap(), "NY");
+        assert_eq!(render("new york", "base64").unwrap(), "Other");
+        assert_eq!(render("%FF", "url").unwrap(), "Other");
+        assert!(render("new york", "hex").is_err());
+    }
+
+    #[test]
+    fn test_allowed() {
+        let tpl = "\
+            {{#switch status allowed=(arr \"draft\" \"published\" \"archived\") name=\"status\"}}\
+                {{#case \"draft\"}}Draft{{/case}}\
+                {{#default}}Live{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+
+        let render = |status| handlebars.render_template(tpl, &json!({ "status": status }));
+        assert_eq!(render(json!("draft")).unwrap(), "Draft");
+        assert_eq!(render(json!("archived")).unwrap(), "Live");
+        let err = render(json!("dratf")).unwrap_err();
+        assert_eq!(
+            err.reason().to_string(),
+            "`{{#switch}}` \"status\": `{{#switch}}` value \"dratf\" is not one of the allowed values"
+        );
+        assert!(render(json!(null)).is_err());
+
+        let invalid = "{{#switch 1 allowed=1}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_redacted_values() {
+        let tpl = "\
+            {{#switch email allowed=(arr \"jo@example.com\")}}\
+                {{#case \"jo@example.com\"}}Jo{{/case}}\
+                {{#case other label=\"other\"}}Other{{/case}}\
+            {{/switch}}\
+        ";
+
+        let tracer = RenderTracer::new();
+        let helper = SwitchHelper::new()
+            .with_redacted_values(true)
+            .with_tracer(tracer.clone());
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(helper));
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+
+        let data = json!({"email": "jo@example.com", "other": "al@example.com"});
+        assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "Jo");
+        let trace = serde_json::to_string(&tracer.take()).unwrap();
+        assert!(!trace.contains("@example.com"));
+        assert!(trace.contains("\"label\":\"other\""));
+
+        let data = json!({"email": "al@example.com"});
+        let err = handlebars.render_template(tpl, &data).unwrap_err();
+        assert!(!err.to_string().contains("al@example.com"));
+        assert!(err.to_string().contains("<redacted "));
+    }
+
+    #[test]
+    fn test_container_policy() {
+        let tpl = "\
+            {{#switch order}}\
+                {{#case \"[1,2]\"}}string{{/case}}\
+                {{#case (arr 1 2)}}array{{/case}}\
+                {{#default}}other{{/default}}\
+            {{/switch}}\
+        ";
+        let render = |policy, order| {
+            let mut handlebars = Handlebars::new();
+            let helper = SwitchHelper::new().with_container_policy(policy);
+            handlebars.register_helper("switch", Box::new(helper));
+            handlebars.register_helper("arr", Box::new(ArrHelper));
+            handlebars.render_template(tpl, &json!({ "order": order }))
+        };
+
+        assert_eq!(
+            render(ContainerPolicy::Compare, json!([1, 2])).unwrap(),
+            "array"
+        );
+        assert_eq!(
+            render(ContainerPolicy::Stringify, json!([1, 2])).unwrap(),
+            "string"
+        );
+        let err = render(ContainerPolicy::Error, json!({"status": 1})).unwrap_err();
+        assert_eq!(
+            err.reason().to_string(),
+            "`{{#switch}}` value {\"status\":1} is an object, not a scalar"
+        );
+        assert_eq!(render(ContainerPolicy::Error, json!(1)).unwrap(), "other");
+    }
+
+    #[test]
+    fn test_email_domain() {
+        let tpl = "\
+            {{#switch email}}\
+                {{#case email_domain=\"*.acme.test\"}}ACME team{{/case}}\
+                {{#case \"root@localhost\" email_domain=\"acme.test\"}}ACME{{/case}}\
+                {{#default}}Guest{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render = |email: &str| handlebars.render_template(tpl, &json!({ "email": email }));
+        assert_eq!(render("jo@eu.ACME.test").unwrap(), "ACME team");
+        assert_eq!(render("jo@acme.test").unwrap(), "ACME");
+        assert_eq!(render("root@localhost").unwrap(), "ACME");
+        assert_eq!(render("jo@notacme.test").unwrap(), "Guest");
+
+        let invalid = "{{#switch 1}}{{#case email_domain=1}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_url_components() {
+        let tpl = "\
+            {{#switch link}}\
+                {{#case host=\"docs.example.com\" path_prefix=\"/api/\"}}API docs{{/case}}\
+                {{#case host=\"*.example.com\" port=8080}}Staging{{/case}}\
+                {{#case scheme=\"mailto\"}}Mail{{/case}}\
+                {{#case path=\"/\"}}Home{{/case}}\
+                {{#default}}Link{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render = |link: &str| handlebars.render_template(tpl, &json!({ "link": link }));
+        assert_eq!(
+            render("https://docs.example.com/api/users").unwrap(),
+            "API docs"
+        );
+        assert_eq!(render("https://docs.example.com/guide").unwrap(), "Link");
+        assert_eq!(
+            render("http://eu.example.com:8080/api/").unwrap(),
+            "Staging"
+        );
+        assert_eq!(render("https://example.org").unwrap(), "Home");
+        assert_eq!(render("not a url").unwrap(), "Link");
+
+        let invalid = "{{#switch 1}}{{#case port=\"80\"}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_hex() {
+        let tpl = "\
+            {{#switch magic}}\
+                {{#case hex=\"CAFEBABE\"}}class{{/case}}\
+                {{#case hex=\"0x7f454c46\"}}elf{{/case}}\
+                {{#default}}unknown{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render = |magic| handlebars.render_template(tpl, &json!({ "magic": magic }));
+        assert_eq!(render(json!([202, 254, 186, 190])).unwrap(), "class");
+        assert_eq!(render(json!("f0VMRg==")).unwrap(), "elf");
+        assert_eq!(render(json!([127, 69, 76])).unwrap(), "unknown");
+        assert_eq!(render(json!("7f454c46")).unwrap(), "unknown");
+
+        let invalid = "{{#switch 1}}{{#case hex=\"abc\"}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let tpl = "\
+            {{#switch age}}\
+                {{#case range=\"..18\"}}minor{{/case}}\
+                {{#case range=\"18..65\"}}adult{{/case}}\
+                {{#case range=\"65..=120\"}}senior{{/case}}\
+                {{#default}}unknown{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render =
+            |age: serde_json::Value| handlebars.render_template(tpl, &json!({ "age": age }));
+        assert_eq!(render(json!(17.5)).unwrap(), "minor");
+        assert_eq!(render(json!(18)).unwrap(), "adult");
+        assert_eq!(render(json!(65)).unwrap(), "senior");
+        assert_eq!(render(json!(120)).unwrap(), "senior");
+        assert_eq!(render(json!(121)).unwrap(), "unknown");
+        assert_eq!(render(json!("30")).unwrap(), "unknown");
+
+        let invalid = "{{#switch 1}}{{#case range=\"1-2\"}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_glob() {
+        let tpl = "\
+            {{#switch key}}\
+                {{#case glob=\"page-*\"}}Page{{/case}}\
+                {{#case \"error.*\" \"warn.?\" glob=true}}Problem{{/case}}\
+                {{#case \"debug.*\"}}Debug{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render =
+            |key: serde_json::Value| handlebars.render_template(tpl, &json!({ "key": key }));
+        assert_eq!(render(json!("page-about")).unwrap(), "Page");
+        assert_eq!(render(json!("error.not_found")).unwrap(), "Problem");
+        assert_eq!(render(json!("warn.1")).unwrap(), "Problem");
+        assert_eq!(render(json!("warn.10")).unwrap(), "Other");
+        assert_eq!(render(json!("debug.x")).unwrap(), "Other");
+        assert_eq!(render(json!("debug.*")).unwrap(), "Debug");
+        assert_eq!(render(json!(1)).unwrap(), "Other");
+
+        let invalid = "{{#switch 1}}{{#case glob=1}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        let tpl = "\
+            {{#switch user}}\
+                {{#case \"root\" regex=\"^admin-\"}}Admin{{/case}}\
+                {{#case regex=\"(?i)^[a-z]+-\\\\d{2,}$\"}}Staff{{/case}}\
+                {{#default}}Guest{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render =
+            |user: serde_json::Value| handlebars.render_template(tpl, &json!({ "user": user }));
+        assert_eq!(render(json!("admin-jo")).unwrap(), "Admin");
+        assert_eq!(render(json!("root")).unwrap(), "Admin");
+        assert_eq!(render(json!("OPS-042")).unwrap(), "Staff");
+        assert_eq!(render(json!("ops-4")).unwrap(), "Guest");
+        assert_eq!(render(json!(42)).unwrap(), "Guest");
+
+        let invalid = "{{#switch \"a\"}}{{#case regex=\"(a\"}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+        let invalid = "{{#switch \"a\"}}{{#case regex=1}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_sha256() {
+        let tpl = "\
+            {{#switch license}}\
+                {{#case sha256=\"9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08\"}}test{{/case}}\
+                {{#case sha256=\"6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b\"}}one{{/case}}\
+                {{#default}}unknown{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let render = |license| handlebars.render_template(tpl, &json!({ "license": license }));
+        assert_eq!(render(json!("test")).unwrap(), "test");
+        assert_eq!(render(json!(1)).unwrap(), "one");
+        assert_eq!(render(json!("Test")).unwrap(), "unknown");
+
+        let invalid = "{{#switch 1}}{{#case sha256=\"abcd\"}}{{/case}}{{/switch}}";
+        assert!(handlebars.render_template(invalid, &json!({})).is_err());
+    }
+
+    #[cfg(feature = "phones")]
+    #[test]
+    fn test_phones() {
+        let tpl = "\
+            {{#switch phone}}\
+                {{#case \"+1 415 555 0100\"}}Support line{{/case}}\
+                {{#case country_code=\"+44\"}}UK{{/case}}\
+                {{#case country_code=\"1\"}}NANP{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_phones(true)));
+
+        let render = |phone: &str| handlebars.render_template(tpl, &json!({ "phone": phone }));
+        assert_eq!(render("001 (415) 555-0100").unwrap(), "Support line");
+        assert_eq!(render("+44 (0)20 7946 0958").unwrap(), "UK");
+        assert_eq!(render("+1 212 555 0199").unwrap(), "NANP");
+        assert_eq!(render("212 555 0199").unwrap(), "Other");
+    }
+
+    #[test]
+    fn test_colors() {
+        let tpl = "\
+            {{#switch accent}}\
+                {{#case \"#0055ff\"}}Brand{{/case}}\
+                {{#default}}Custom{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_colors(true)));
+
+        let render = |accent: &str| handlebars.render_template(tpl, &json!({ "accent": accent }));
+        assert_eq!(render("#05F").unwrap(), "Brand");
+        assert_eq!(render("rgb(0, 85, 255)").unwrap(), "Brand");
+        assert_eq!(render("#0055fe").unwrap(), "Custom");
+        let off = tpl.replace("{{#switch accent}}", "{{#switch accent colors=false}}");
+        assert_eq!(
+            handlebars
+                .render_template(&off, &json!({"accent": "#05f"}))
+                .unwrap(),
+            "Custom"
+        );
+    }
+
+    #[test]
+    fn test_comparator() {
+        let tpl = "\
+            {{#switch version}}\
+                {{#case \"2.1.0\"}}Current{{/case}}\
+                {{#case \"1.0.0\" in=(arr \"v1.9\")}}Legacy{{/case}}\
+                {{#default}}Old{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new()
+                    .with_comparator(FieldComparator::semver())
+                    .with_named_comparator("exact", FieldComparator::new(|a, b| a == b)),
+            ),
+        );
+        let render = |tpl: &str, version| {
+            let data = json!({ "version": version });
+            handlebars.render_template(tpl, &data).unwrap()
+        };
+        assert_eq!(render(tpl, "v2.1"), "Current");
+        assert_eq!(render(tpl, "1.9"), "Legacy");
+        assert_eq!(render(tpl, "v2.0"), "Old");
+        let exact = tpl.replace(
+            "{{#switch version}}",
+            "{{#switch version compare=\"exact\"}}",
+        );
+        assert_eq!(render(&exact, "v2.1"), "Old");
+    }
+
+    #[test]
+    fn test_named_comparator() {
+        let tpl = "\
+            {{#switch version compare=compare}}\
+                {{#case \"2.1.0\"}}Current{{/case}}\
+                {{#default}}Old{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new().with_named_comparator("semver", FieldComparator::semver()),
+            ),
+        );
+        let render = |compare| {
+            let data = json!({"version": "v2.1", "compare": compare});
+            handlebars.render_template(tpl, &data)
+        };
+        assert_eq!(render(json!("semver")).unwrap(), "Current");
+        assert!(render(json!("unknown")).is_err());
+        assert!(render(json!(1)).is_err());
+        assert_eq!(
+            handlebars
+                .render_template(
+                    tpl.replace(" compare=compare", "").as_str(),
+                    &json!({"version": "v2.1"})
+                )
+                .unwrap(),
+            "Old"
+        );
+    }
+
+    #[test]
+    fn test_stringify() {
+        let tpl = "\
+            {{#switch code}}\
+                {{#case \"200\"}}OK{{/case}}\
+                {{#case false}}Off{{/case}}\
+                {{#case (arr 404 \"410\")}}Gone{{/case}}\
+                {{#default}}Other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_stringify(true)));
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+
+        let render = |code| handlebars.render_template(tpl, &json!({ "code": code }));
+        assert_eq!(render(json!(200)).unwrap(), "OK");
+        assert_eq!(render(json!("200")).unwrap(), "OK");
+        assert_eq!(render(json!("false")).unwrap(), "Off");
+        assert_eq!(render(json!(["404", 410])).unwrap(), "Gone");
+        assert_eq!(render(json!(200.5)).unwrap(), "Other");
+        let off = tpl.replace("{{#switch code}}", "{{#switch code stringify=false}}");
+        assert_eq!(
+            handlebars
+                .render_template(&off, &json!({"code": 200}))
+                .unwrap(),
+            "Other"
+        );
+    }
+
+    #[test]
+    fn test_yaml_bools() {
+        let tpl = "\
+            {{#switch enabled}}\
+                {{#case true}}On{{/case}}\
+                {{#case false}}Off{{/case}}\
+                {{#default}}Unset{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_yaml_bools(true)),
+        );
+
+        let render = |enabled| handlebars.render_template(tpl, &json!({ "enabled": enabled }));
+        assert_eq!(render(json!("Yes")).unwrap(), "On");
+        assert_eq!(render(json!("OFF")).unwrap(), "Off");
+        assert_eq!(render(json!(true)).unwrap(), "On");
+        assert_eq!(render(json!("y")).unwrap(), "Unset");
+        let off = tpl.replace(
+            "{{#switch enabled}}",
+            "{{#switch enabled yaml_bools=false}}",
+        );
+        assert_eq!(
+            handlebars
+                .render_template(&off, &json!({"enabled": "on"}))
+                .unwrap(),
+            "Unset"
+        );
+    }
+
+    #[test]
+    fn test_nil_safe() {
+        let tpl = "\
+            {{#switch config.tls.mode allowed=(arr \"strict\" \"permissive\")}}\
+                {{#case \"strict\"}}Strict{{/case}}\
+                {{#default}}Off{{/default}}\
+            {{/switch}}\
+        ";
+        let nil_safe = tpl.replace("config.tls.mode", "config.tls.mode nil_safe=true");
+        let data = json!({"config": {"port": 443}});
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+        assert!(handlebars.render_template(tpl, &data).is_err());
+        assert_eq!(handlebars.render_template(&nil_safe, &data).unwrap(), "Off");
+
+        // strict mode switches on missing values all the same
+        handlebars.set_strict_mode(true);
+        let unchecked = "{{#switch config.tls.mode}}{{#default}}Off{{/default}}{{/switch}}";
+        assert_eq!(handlebars.render_template(unchecked, &data).unwrap(), "Off");
+        assert_eq!(handlebars.render_template(&nil_safe, &data).unwrap(), "Off");
+
+        // values that are present are still checked
+        let data = json!({"config": {"tls": {"mode": "strict"}}});
+        assert_eq!(
+            handlebars.render_template(&nil_safe, &data).unwrap(),
+            "Strict"
+        );
+        let data = json!({"config": {"tls": {"mode": "off"}}});
+        assert!(handlebars.render_template(&nil_safe, &data).is_err());
+
+        // so is nothing at the pointer, as opposed to a `null` there
+        let pointed = nil_safe.replace("config.tls.mode", "config ptr=\"/tls/mode\"");
+        let data = json!({"config": {"port": 443}});
+        assert_eq!(handlebars.render_template(&pointed, &data).unwrap(), "Off");
+        let raw = pointed.replace("ptr=", "raw=true ptr=");
+        let data = json!({"config": "{\"port\": 443}"});
+        assert_eq!(handlebars.render_template(&raw, &data).unwrap(), "Off");
+        let data = json!({"config": "{\"tls\":"});
+        assert!(handlebars.render_template(&raw, &data).is_err());
+        let data = json!({"config": {"tls": {"mode": null}}});
+        assert!(handlebars.render_template(&pointed, &data).is_err());
+        let data = json!({"config": {"tls": {"mode": "off"}}});
+        assert!(handlebars.render_template(&pointed, &data).is_err());
+    }
+
+    #[test]
+    fn test_sentinels() {
+        let tpl = "\
+            {{#switch plan}}\
+                {{#case @NONE}}No plan{{/case}}\
+                {{#case \"pro\"}}Pro{{/case}}\
+                {{#case @ANY}}Other{{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new()
+                    .with_sentinel("ANY", Sentinel::any())
+                    .with_sentinel("NONE", Sentinel::none()),
+            ),
+        );
+        let render = |data| handlebars.render_template(tpl, &data).unwrap();
+        assert_eq!(render(json!({})), "No plan");
+        assert_eq!(render(json!({"plan": null})), "No plan");
+        assert_eq!(render(json!({"plan": "pro"})), "Pro");
+        assert_eq!(render(json!({"plan": "free"})), "Other");
+
+        // unregistered names are looked up like any other data variable
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let rendered = handlebars.render_template(tpl, &json!({"plan": "free"}));
+        assert_eq!(rendered.unwrap(), "");
+    }
+
+    #[test]
+    fn test_normalizer() {
+        let tpl = "\
+            {{#switch role normalize=normalize}}\
+                {{#case \"Admin\"}}Admin{{/case}}\
+                {{#default}}User{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new()
+                    .with_normalizer(Pipeline::new().then(Trim).then(Lowercase))
+                    .with_named_normalizer("trim", Trim),
+            ),
+        );
+        let render = |role, normalize| {
+            let data = json!({ "role": role, "normalize": normalize });
+            handlebars.render_template(tpl, &data)
+        };
+        assert_eq!(render(" ADMIN ", json!(true)).unwrap(), "Admin");
+        assert_eq!(render(" ADMIN ", json!("trim")).unwrap(), "User");
+        assert_eq!(render(" Admin ", json!("trim")).unwrap(), "Admin");
+        assert_eq!(render("admin", json!(false)).unwrap(), "User");
+        assert!(render("admin", json!("nfc")).is_err());
+    }
+
+    #[test]
+    fn test_multi() {
+        let tpl = |hash| {
+            format!(
+                "\
+                {{{{#switch n {hash}}}}}\
+                    {{{{#case 1 2}}}}small{{{{/case}}}} \
+                    {{{{#case 2 3}}}}prime{{{{/case}}}} \
+                    {{{{#default}}}}other{{{{/default}}}}\
+                {{{{/switch}}}}\
+                "
+            )
+        };
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |hash, n| {
+            handlebars
+                .render_template(&tpl(hash), &json!({ "n": n }))
+                .unwrap()
+        };
+        assert_eq!(render("", 2), "small  ");
+        assert_eq!(render("multi=true", 2), "small prime ");
+        assert_eq!(render("multi=true", 3), " prime ");
+        assert_eq!(render("multi=true", 4), "  other");
+        assert_eq!(render("multi=true join=\"/\"", 2), "small/prime");
+        assert_eq!(render("join=\"/\"", 2), "small");
+        assert_eq!(render("join=\"/\"", 4), "other");
+        assert_eq!(
+            render("multi=true join=\", \" last_join=\" or \"", 2),
+            "small or prime"
+        );
+        assert_eq!(render("multi=true limit=1", 2), "small  ");
+        assert_eq!(render("multi=true limit=1", 3), " prime ");
+        assert_eq!(render("multi=true limit=0", 2), "  other");
+        assert_eq!(
+            handlebars
+                .render_template(
+                    "{{#switch 1 multi=true limit=1}}{{#case 1}}\
+                        {{#switch 2 multi=true limit=1}}{{#case 2}}a{{/case}}{{/switch}}\
+                    {{/case}}{{/switch}}",
+                    &json!({})
+                )
+                .unwrap(),
+            "a"
+        );
+        assert!(handlebars
+            .render_template(&tpl("join=1"), &json!({ "n": 2 }))
+            .is_err());
+        assert!(handlebars
+            .render_template(&tpl("multi=true limit=-1"), &json!({ "n": 2 }))
+            .is_err());
+    }
+
+    #[test]
+    fn test_pointer() {
+        let tpl = "\
+            {{#switch data ptr=ptr}}\
+                {{#case \"shipped\"}}On its way{{/case}}\
+                {{#case null}}Unknown{{/case}}\
+                {{#default}}Processing{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+
+        let data = json!({"payload": {"items": [{"status": "shipped"}, {"a/b": "new"}]}});
+        let render =
+            |ptr: &str| handlebars.render_template(tpl, &json!({"data": data, "ptr": ptr}));
+        assert_eq!(render("/payload/items/0/status").unwrap(), "On its way");
+        assert_eq!(render("/payload/items/1/a~1b").unwrap(), "Processing");
+        assert_eq!(render("/payload/items/2/status").unwrap(), "Unknown");
+        assert_eq!(render("payload").unwrap(), "Unknown");
+        assert!(handlebars
+            .render_template("{{#switch 1 ptr=2}}{{/switch}}", &json!({}))
+            .is_err());
+
+        let raw_tpl = tpl.replace("ptr=ptr", "raw=true ptr=ptr");
+        let render_raw = |data: &serde_json::Value, ptr: &str| {
+            handlebars.render_template(&raw_tpl, &json!({"data": data, "ptr": ptr}))
+        };
+        let raw = json!(data.to_string());
+        assert_eq!(
+            render_raw(&raw, "/payload/items/0/status").unwrap(),
+            "On its way"
+        );
+        assert_eq!(
+            render_raw(&raw, "/payload/items/1/a~1b").unwrap(),
+            "Processing"
+        );
+        assert_eq!(
+            render_raw(&raw, "/payload/items/2/status").unwrap(),
+            "Unknown"
+        );
+        assert_eq!(
+            render_raw(&json!("{\"payload\":"), "/payload").unwrap(),
+            "Unknown"
+        );
+        assert_eq!(
+            render_raw(&data, "/payload/items/0/status").unwrap(),
+            "On its way"
+        );
+        assert_eq!(render_raw(&json!("\"shipped\""), "").unwrap(), "On its way");
+    }
 
-#[derive(Clone, Copy)]
-pub struct SwitchHelper;
+    #[test]
+    fn test_arm_error_policy() {
+        let tpl = "\
+            {{#switch plan}}\
+                {{#case \"pro\"}}Pro: {{> pro_features}}{{/case}}\
+                {{#case \"pro\" \"free\"}}Free{{/case}}\
+                {{#default}}Standard{{/default}}\
+            {{/switch}}\
+        ";
 
-impl HelperDef for SwitchHelper {
-    fn call<'reg: 'rc, 'rc>(
-        &self,
-        h: &Helper<'rc>,
-        r: &'reg Handlebars<'reg>,
-        ctx: &'rc Context,
-        rc: &mut RenderContext<'reg, 'rc>,
-        out: &mut dyn Output,
-    ) -> HelperResult {
-        // Read in the switch variable or expression
-        let param = h
-            .param(0)
-            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("switch", 0))?;
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        assert!(handlebars
+            .render_template(tpl, &json!({"plan": "pro"}))
+            .is_err());
 
-        let expression_value = param.value().clone();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_arm_error_policy(ArmErrorPolicy::FallBackToDefault)),
+        );
+        let render = |plan: &str| handlebars.render_template(tpl, &json!({ "plan": plan }));
+        assert_eq!(render("pro").unwrap(), "Standard");
+        assert_eq!(render("free").unwrap(), "Free");
 
-        // Keep track of whether a match occurs within the block
-        let mut block_context = BlockContext::new();
-        block_context.set_local_var("match", json!(false));
-        let mut local_rc = rc.clone();
-        local_rc.push_block(block_context);
+        handlebars.register_partial("pro_features", "all").unwrap();
+        let rendered = handlebars.render_template(tpl, &json!({"plan": "pro"}));
+        assert_eq!(rendered.unwrap(), "Pro: all");
+    }
 
-        // Add the `{{#case}}` helper within the `{{#switch}}` block
-        local_rc.register_local_helper("case", Box::new(CaseHelper { expression_value }));
+    #[test]
+    fn test_arm_error_policy_nested_in_default() {
+        let tpl = "\
+            {{#switch plan}}\
+                {{#case \"pro\"}}Pro: {{> pro_features}}{{/case}}\
+                {{#default}}\
+                    D:{{#switch tier}}\
+                        {{#case 1}}inner-one{{/case}}\
+                        {{#case 2}}inner-two{{/case}}\
+                        {{#default}}inner-default{{/default}}\
+                    {{/switch}}\
+                {{/default}}\
+            {{/switch}}\
+        ";
 
-        // Add the `{{#default}}` helper within the `{{#switch}}` block
-        local_rc.register_local_helper("default", Box::new(DefaultHelper));
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_arm_error_policy(ArmErrorPolicy::FallBackToDefault)),
+        );
+        let rendered = handlebars.render_template(tpl, &json!({"plan": "pro", "tier": 2}));
+        assert_eq!(rendered.unwrap(), "D:inner-two");
+    }
 
-        // Render the `{{#switch}}` block
-        let result = match h.template() {
-            Some(t) => t.render(r, ctx, &mut local_rc, out),
-            None => Ok(()),
+    #[test]
+    fn test_budget() {
+        let tpl = "\
+            {{#switch speed budget_ms=budget}}\
+                {{#case \"slow\"}}{{sleep}}Slow{{/case}}\
+                {{#default}}Fast{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper(
+            "sleep",
+            Box::new(
+                |_: &handlebars::Helper,
+                 _: &Handlebars,
+                 _: &handlebars::Context,
+                 _: &mut handlebars::RenderContext,
+                 _: &mut dyn handlebars::Output| {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    Ok(())
+                },
+            ),
+        );
+
+        let render = |speed: &str, budget| {
+            handlebars.render_template(tpl, &json!({"speed": speed, "budget": budget}))
         };
+        assert_eq!(render("fast", json!(5)).unwrap(), "Fast");
+        assert_eq!(render("slow", json!(1000)).unwrap(), "Slow");
+        let err = render("slow", json!(5)).unwrap_err();
+        assert!(err.to_string().contains("exceeding its budget of 5ms"));
+        assert!(render("fast", json!("5ms")).is_err());
+    }
 
-        local_rc.pop_block();
+    #[test]
+    fn test_to_javascript() {
+        let js = SwitchHelper::new()
+            .with_array_equality(ArrayEquality::Set)
+            .with_aliases([("legacy".to_owned(), json!("current"))])
+            .with_memo(MemoCache::new())
+            .to_javascript()
+            .unwrap();
+        assert!(js.contains(
+            r#"var config = {"aliases":{"legacy":"current"},"arrays":"set","ignore_case_style":false,"uuids":false};"#
+        ));
+        assert!(js.contains("Handlebars.registerHelper(\"case\""));
+        assert!(js.contains("Handlebars.registerHelper(\"default\""));
 
-        result
+        let err = SwitchHelper::new()
+            .with_arm_error_policy(ArmErrorPolicy::FallBackToDefault)
+            .to_javascript()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`with_arm_error_policy` has no JavaScript equivalent"
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::SwitchHelper;
-    use handlebars::Handlebars;
+    #[test]
+    fn test_compat() {
+        let tpl = "\
+            {{#switch n}}\
+                {{#case 1}}one {{/case}}\
+                {{#case \"1\"}}[{{@switch_break}}] {{/case}}\
+                {{#default}}other{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render =
+            |handlebars: &Handlebars, n| handlebars.render_template(tpl, &json!({ "n": n }));
+        assert_eq!(render(&handlebars, json!(1)).unwrap(), "one ");
+        assert_eq!(render(&handlebars, json!("1")).unwrap(), "[true] ");
+        assert_eq!(render(&handlebars, json!(true)).unwrap(), "other");
+
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_compat(CompatMode::Loose)),
+        );
+        assert_eq!(render(&handlebars, json!(1)).unwrap(), "one [true] ");
+        assert_eq!(render(&handlebars, json!(" 1 ")).unwrap(), "one ");
+        assert_eq!(render(&handlebars, json!(true)).unwrap(), "one [true] ");
+        assert_eq!(render(&handlebars, json!(2)).unwrap(), "other");
+        assert!(SwitchHelper::new()
+            .with_compat(CompatMode::Loose)
+            .to_javascript()
+            .is_err());
+    }
 
     #[test]
-    fn test_switch() {
+    fn test_fallthrough() {
         let tpl = "\
-            {{#switch state}}\
-                {{#case \"page1\" \"page2\"}}\
-                    page 1 or 2\
-                    {{#switch s}}\
-                        {{#case 4}}s = 4{{/case}}\
-                    {{/switch}}\
-                {{/case}}\
-                {{#case \"page3\"}}page3{{/case}}\
-                {{#case \"page4\"}}page4{{/case}}\
-                {{#case \"page5\"}}\
-                    page5 - \
-                    {{#switch s}}\
-                        {{#case 3}}s = 3{{/case}}\
-                        {{#case 2}}s = 2{{/case}}\
-                        {{#case 1}}s = 1{{/case}}\
-                        {{#default}}unknown{{/default}}\
-                    {{/switch}}\
-                {{/case}}\
-                {{#default}}page0{{/default}}\
+            {{#switch n}}\
+                {{#case 1}}one {{/case}}\
+                {{#case 2}}two {{#if stop}}{{break}}{{/if}}{{/case}}\
+                {{#case 3}}three {{break}}{{/case}}\
+                {{#default}}other{{/default}}\
             {{/switch}}\
         ";
 
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
-        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_compat(CompatMode::Fallthrough)),
+        );
+        let render = |data| handlebars.render_template(tpl, &data).unwrap();
+        assert_eq!(render(json!({"n": 1})), "one two three ");
+        assert_eq!(render(json!({"n": 1, "stop": true})), "one two ");
+        assert_eq!(render(json!({"n": 3})), "three ");
+        assert_eq!(render(json!({"n": 4})), "other");
 
-        let r0 = handlebars.render("tpl", &json!({"state": "page2", "s": 1}));
-        assert_eq!(r0.ok().unwrap(), "page 1 or 2");
+        // a nested switch matches on its own after its arm broke out
+        let nested = "\
+            {{#switch 1}}\
+                {{#case 1}}one{{break}}[{{#switch 2}}{{#case 2}}two{{/case}}{{/switch}}]{{/case}}\
+                {{#case 3}}three{{/case}}\
+            {{/switch}}\
+        ";
+        assert_eq!(
+            handlebars.render_template(nested, &json!({})).unwrap(),
+            "one[two]"
+        );
 
-        let r1 = handlebars.render("tpl", &json!({"state": "page5", "s": 1}));
-        assert_eq!(r1.ok().unwrap(), "page5 - s = 1");
+        // `{{break}}` renders as a missing variable elsewhere
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let rendered = handlebars.render_template(tpl, &json!({"n": 1, "stop": true}));
+        assert_eq!(rendered.unwrap(), "one ");
+    }
 
-        let r2 = handlebars.render("tpl", &json!({"state": "page5", "s": 4}));
-        assert_eq!(r2.ok().unwrap(), "page5 - unknown");
+    #[test]
+    fn test_tuple() {
+        let tpl = "\
+            {{#switch method path as |request|}}\
+                {{#case \"GET\" \"/users\"}}list{{/case}}\
+                {{#case \"GET\"}}one{{/case}}\
+                {{#case \"delete\" \"/users/*\" glob=true}}delete{{/case}}\
+                {{#case \"head\" @ANY}}head{{/case}}\
+                {{#case in=(arr (arr \"PUT\" \"/a\") (arr \"PATCH\" \"/a\"))}}update{{/case}}\
+                {{#default}}{{request.[0]}} {{request.[1]}}{{/default}}\
+            {{/switch}}\
+        ";
 
-        let r3 = handlebars.render("tpl", &json!({"state": "page0", "s": 1}));
-        assert_eq!(r3.ok().unwrap(), "page0");
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("arr", Box::new(ArrHelper));
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new()
+                    .with_normalizer(Lowercase)
+                    .with_sentinel("ANY", Sentinel::any()),
+            ),
+        );
+        let render = |method, path| {
+            let data = json!({ "method": method, "path": path });
+            handlebars.render_template(tpl, &data).unwrap()
+        };
+        assert_eq!(render("GET", "/users"), "list");
+        assert_eq!(render("get", "/Users"), "list");
+        assert_eq!(render("GET", "/posts"), "GET /posts");
+        assert_eq!(render("DELETE", "/users/7"), "delete");
+        assert_eq!(render("HEAD", "/anything"), "head");
+        assert_eq!(render("PATCH", "/a"), "update");
+
+        // a single value still matches any value a case lists
+        let single = "{{#switch method}}{{#case \"GET\" \"HEAD\"}}read{{/case}}{{/switch}}";
+        let rendered = handlebars.render_template(single, &json!({"method": "HEAD"}));
+        assert_eq!(rendered.unwrap(), "read");
     }
 
     #[test]
-    fn test_missing_key_renders_default() {
+    fn test_switched_value() {
         let tpl = "\
-            {{#switch access}}\
-                {{#case \"admin\"}}Admin{{/case}}\
-                {{#default}}User{{/default}}\
+            {{#switch user.role as |role|}}\
+                {{#case \"admin\"}}Hello {{role}}{{/case}}\
+                {{#case \"editor\" context=user}}{{name}} the {{role}}{{/case}}\
+                {{#default}}{{#each tags}}{{role}}:{{this}} {{/each}}{{@switch_value}}{{/default}}\
             {{/switch}}\
         ";
 
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_normalizer(Lowercase)),
+        );
+        let render = |role| {
+            let data = json!({"user": {"role": role, "name": "Jo"}, "tags": ["a"]});
+            handlebars.render_template(tpl, &data).unwrap()
+        };
+        assert_eq!(render("ADMIN"), "Hello ADMIN");
+        assert_eq!(render("editor"), "Jo the editor");
+        assert_eq!(render("guest"), "guest:a guest");
 
-        assert_eq!(handlebars.render_template(tpl, &json!({})).unwrap(), "User");
+        let nested = "\
+            {{#switch 1 as |outer|}}\
+                {{#case 1}}{{#switch 2}}{{#case 2}}{{outer}}{{@switch_value}}{{/case}}{{/switch}}{{/case}}\
+            {{/switch}}\
+        ";
+        assert_eq!(
+            handlebars.render_template(nested, &json!({})).unwrap(),
+            "12"
+        );
     }
 
     #[test]
-    fn test_case_helper_not_defined() {
+    fn test_guard() {
         let tpl = "\
-            {{#switch access}}\
-                {{#case \"admin\"}}Admin{{/case}}\
-                {{#default}}User{{/default}}\
+            {{#switch user.role}}\
+                {{#case \"admin\" if=user.active}}Admin{{/case}}\
+                {{#case \"admin\"}}Suspended admin{{/case}}\
+                {{#case \"guest\" not=true if=user.active}}Member{{/case}}\
+                {{#default}}Guest{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |role, active: serde_json::Value| {
+            let data = json!({"user": {"role": role, "active": active}});
+            handlebars.render_template(tpl, &data).unwrap()
+        };
+        assert_eq!(render("admin", json!(true)), "Admin");
+        assert_eq!(render("admin", json!(false)), "Suspended admin");
+        assert_eq!(render("admin", json!(null)), "Suspended admin");
+        assert_eq!(render("editor", json!(1)), "Member");
+        assert_eq!(render("editor", json!(0)), "Guest");
+        assert_eq!(render("guest", json!(true)), "Guest");
+    }
+
+    #[test]
+    fn test_explicit_fallthrough() {
+        let tpl = "\
+            {{#switch n}}\
+                {{#case 1 fallthrough=true}}one {{/case}}\
+                {{#case 2}}two {{#if more}}{{fallthrough}}{{/if}}{{/case}}\
+                {{#case 3}}three {{/case}}\
+                {{#default fallthrough=true}}other {{/default}}\
+                {{#case 4}}four{{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |data| handlebars.render_template(tpl, &data).unwrap();
+        assert_eq!(render(json!({"n": 1})), "one two ");
+        assert_eq!(render(json!({"n": 1, "more": true})), "one two three ");
+        assert_eq!(render(json!({"n": 3})), "three ");
+        assert_eq!(render(json!({"n": 5})), "other four");
+
+        // a nested switch does not fall through on behalf of its parent
+        let nested = "\
+            {{#switch 1}}\
+                {{#case 1}}{{#switch 2}}{{#case 2}}{{fallthrough}}a{{/case}}{{/switch}}{{/case}}\
+                {{#case 3}}b{{/case}}\
+            {{/switch}}\
+        ";
+        assert_eq!(handlebars.render_template(nested, &json!({})).unwrap(), "a");
+        let bound = "\
+            {{#switch 1}}\
+                {{#case 1 as |v|}}{{v}}{{fallthrough}}{{/case}}\
+                {{#case 3}}b{{/case}}\
+            {{/switch}}\
+        ";
+        assert_eq!(handlebars.render_template(bound, &json!({})).unwrap(), "1b");
+    }
+
+    #[test]
+    fn test_post_processor() {
+        let tpl = "\
+            {{#switch kind}}\
+                {{#case \"push\" label=\"json\"}}{ \"title\": \"{{title}}\" }{{/case}}\
+                {{#case \"sms\"}} {{title}} {{/case}}\
+                {{#default label=\"plain\"}} {{title}} {{/default}}\
             {{/switch}}\
-            {{#case \"test\"}}Check{{/case}}\
         ";
 
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
+        handlebars.register_helper(
+            "switch",
+            Box::new(
+                SwitchHelper::new()
+                    .with_post_processor(Some("json"), |s| {
+                        let json: serde_json::Value = serde_json::from_str(&s)
+                            .map_err(|e| RenderErrorReason::Other(e.to_string()))?;
+                        Ok(json.to_string())
+                    })
+                    .with_post_processor(None, |s| Ok(s.trim().to_owned())),
+            ),
+        );
+        let render =
+            |kind, title| handlebars.render_template(tpl, &json!({ "kind": kind, "title": title }));
+        assert_eq!(render("push", "Hi").unwrap(), "{\"title\":\"Hi\"}");
+        assert_eq!(render("sms", "Hi").unwrap(), "Hi");
+        assert_eq!(render("email", "Hi").unwrap(), " Hi ");
+        assert!(render("push", "\\").is_err());
+        assert!(SwitchHelper::new()
+            .with_post_processor(None, Ok)
+            .to_javascript()
+            .is_err());
+    }
+
+    #[test]
+    fn test_max_len() {
+        let tpl = "\
+            {{#switch kind max_len=5}}\
+                {{#case \"sms\" max_len=8 truncate=true}}{{text}}{{/case}}\
+                {{#default}}{{text}}{{/default}}\
+            {{/switch}}\
+        ";
 
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render =
+            |kind, text| handlebars.render_template(tpl, &json!({ "kind": kind, "text": text }));
+        assert_eq!(render("sms", "Your code is 1234").unwrap(), "Your cod");
+        assert_eq!(render("push", "Hello").unwrap(), "Hello");
+        assert!(render("push", "Hello!").is_err());
         assert!(handlebars
-            .render_template(tpl, &json!({"access": "admin"}))
+            .render_template("{{#switch 1 max_len=\"5\"}}{{/switch}}", &json!({}))
             .is_err());
     }
 
     #[test]
-    fn test_default_helper_not_defined() {
+    fn test_in() {
         let tpl = "\
-            {{#switch access}}\
-                {{#case \"admin\"}}Admin{{/case}}\
+            {{#switch role}}\
+                {{#case in=staff}}Staff{{/case}}\
+                {{#case \"guest\" in=bots}}Guest{{/case}}\
                 {{#default}}User{{/default}}\
             {{/switch}}\
-            {{#default \"test\"}}Check{{/default}}\
         ";
 
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
-
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |role| {
+            let data = json!({"role": role, "staff": ["admin", "editor"], "bots": ["crawler"]});
+            handlebars.render_template(tpl, &data)
+        };
+        assert_eq!(render("editor").unwrap(), "Staff");
+        assert_eq!(render("guest").unwrap(), "Guest");
+        assert_eq!(render("crawler").unwrap(), "Guest");
+        assert_eq!(render("ann").unwrap(), "User");
         assert!(handlebars
-            .render_template(tpl, &json!({"access": "admin"}))
+            .render_template(
+                "{{#switch 1}}{{#case in=1}}{{/case}}{{/switch}}",
+                &json!({})
+            )
             .is_err());
     }
 
     #[test]
-    fn test_only_case_exists_with_match() {
+    fn test_case_binding() {
         let tpl = "\
-            {{#switch access}}\
-                {{#case \"admin\"}}Admin{{/case}}\
+            {{#switch ext}}\
+                {{#case \"png\" \"jpg\" as |matched|}}image/{{matched}}{{/case}}\
+                {{#case \"txt\" not=true as |matched|}}other/{{matched}}{{/case}}\
             {{/switch}}\
         ";
 
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
-
-        assert_eq!(
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |ext| {
             handlebars
-                .render_template(tpl, &json!({"access": "admin"}))
-                .unwrap(),
-            "Admin"
-        );
+                .render_template(tpl, &json!({ "ext": ext }))
+                .unwrap()
+        };
+        assert_eq!(render("jpg"), "image/jpg");
+        assert_eq!(render("gif"), "other/gif");
+        assert_eq!(render("txt"), "");
     }
 
     #[test]
-    fn test_only_case_exists_with_no_match() {
+    fn test_hash_locals() {
         let tpl = "\
-            {{#switch access}}\
-                {{#case \"admin\"}}Admin{{/case}}\
+            {{#switch kind theme=\"dark\" compact=true match=true}}\
+                {{#case \"a\"}}{{@theme}} {{@compact}}{{/case}}\
+                {{#default}}{{#if @compact}}-{{/if}}{{/default}}\
             {{/switch}}\
         ";
 
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |kind| {
+            handlebars
+                .render_template(tpl, &json!({ "kind": kind }))
+                .unwrap()
+        };
+        assert_eq!(render("a"), "dark true");
+        assert_eq!(render("b"), "-");
+    }
 
-        assert_eq!(
+    #[test]
+    fn test_arm_context() {
+        let tpl = "\
+            {{#switch order.kind}}\
+                {{#case \"invoice\" context=order.invoice}}#{{number}} {{@root.user}}{{/case}}\
+                {{#default context=order}}{{kind}}{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        let render = |kind| {
+            let order = json!({"kind": kind, "invoice": {"number": 42}});
             handlebars
-                .render_template(tpl, &json!({"access": "unknown"}))
-                .unwrap(),
-            ""
+                .render_template(tpl, &json!({"order": order, "user": "ann"}))
+                .unwrap()
+        };
+        assert_eq!(render("invoice"), "#42 ann");
+        assert_eq!(render("refund"), "refund");
+    }
+
+    #[test]
+    fn test_nested_blocks_leave_switch_state() {
+        let tpl = "\
+            {{#switch order.kind}}\
+                {{#case \"invoice\" context=order.invoice}}\
+                    {{@switch_value}}:{{@match}}:\
+                    {{#switch_some number}}{{#some}}[{{@switch_value}}{{@match}}]{{/some}}{{/switch_some}}\
+                {{/case}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("switch_some", Box::new(SwitchSomeHelper));
+        let data = json!({"order": {"kind": "invoice", "invoice": {"number": 42}}});
+        let rendered = handlebars.render_template(tpl, &data);
+        assert_eq!(rendered.unwrap(), "invoice:true:[]");
+    }
+
+    #[test]
+    fn test_silent() {
+        let tpl = "\
+            {{#switch status silent=true}}\
+                {{#case \"ok\"}}OK{{/case}}\
+            {{/switch}}|\
+            {{#switch status silent=false}}\
+                {{#case \"ok\"}}OK{{/case}}\
+            {{/switch}}\
+        ";
+
+        let tracer = RenderTracer::new();
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_tracer(tracer.clone())),
         );
+        let r0 = handlebars.render_template(tpl, &json!({"status": "ok"}));
+        assert_eq!(r0.unwrap(), "|OK");
+        let trace = tracer.take();
+        assert!(trace[0].chosen().is_some());
+        assert!(trace[1].chosen().is_some());
     }
 
     #[test]
-    fn test_only_default_exists() {
+    fn test_name() {
         let tpl = "\
-            {{#switch access}}\
-                {{#default}}User{{/default}}\
+            {{#switch kind name=\"notice-body\" max_len=5}}\
+                {{#default}}{{text}}{{/default}}\
             {{/switch}}\
         ";
 
+        let tracer = RenderTracer::new();
         let mut handlebars = Handlebars::new();
-        handlebars.register_helper("switch", Box::new(SwitchHelper));
+        handlebars.register_helper(
+            "switch",
+            Box::new(SwitchHelper::new().with_tracer(tracer.clone())),
+        );
+        let render =
+            |text| handlebars.render_template(tpl, &json!({ "kind": "push", "text": text }));
+        assert_eq!(render("Hello").unwrap(), "Hello");
+        assert_eq!(tracer.take()[0].name.as_deref(), Some("notice-body"));
+        let err = render("Hello!").unwrap_err();
+        assert!(err.to_string().contains("`{{#switch}}` \"notice-body\": "));
+        assert_eq!(err.line_no, Some(1));
+        assert!(handlebars
+            .render_template("{{#switch 1 name=2}}{{/switch}}", &json!({}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_shadowed_helper() {
+        handlebars::handlebars_helper!(or_default: |v: Json, d: Json| {
+            if v.is_null() { d.clone() } else { v.clone() }
+        });
+        let tpl = "\
+            {{default title \"-\"}}\
+            {{#switch kind}}\
+                {{#case 1}}{{default title \"?\"}}{{#if (default title \"\")}}!{{/if}}{{/case}}\
+                {{#default}}D{{/default}}\
+            {{/switch}}\
+        ";
 
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("default", Box::new(or_default));
+        let err = handlebars
+            .render_template(tpl, &json!({"kind": 1}))
+            .unwrap_err();
+        assert!(err.to_string().contains("with_shadowed_helper"));
         assert_eq!(
             handlebars
-                .render_template(tpl, &json!({"access": "admin"}))
+                .render_template(tpl, &json!({"kind": 2}))
                 .unwrap(),
-            "User"
+            "-D"
         );
+
+        let helper = SwitchHelper::new().with_shadowed_helper("default", or_default);
+        handlebars.register_helper("switch", Box::new(helper));
+        let render = |data| handlebars.render_template(tpl, &data).unwrap();
+        assert_eq!(render(json!({"kind": 1})), "-?");
+        assert_eq!(render(json!({"kind": 1, "title": "T"})), "TT!");
+        assert_eq!(render(json!({"kind": 2})), "-D");
+    }
+
+    #[test]
+    fn test_audit_sink() {
+        let tpl = "\
+            {{#switch plan name=\"plan\" memo=true}}\
+                {{#case \"pro\"}}Pro{{/case}}\
+                {{#default}}{{#switch seats}}{{#case 1}}Solo{{/case}}{{/switch}}{{/default}}\
+            {{/switch}}\
+        ";
+
+        let log = AuditLog::new();
+        let memo = MemoCache::new();
+        let helper = SwitchHelper::new()
+            .with_audit_sink(log.clone())
+            .with_memo(memo)
+            .with_redacted_values(true);
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(helper));
+        assert!(handlebars.register_template_string("tpl", tpl).is_ok());
+
+        for data in [
+            json!({"plan": "pro"}),
+            json!({"plan": "free", "seats": 1}),
+            json!({"plan": "pro"}),
+            json!({"plan": "team", "seats": 5}),
+        ] {
+            handlebars.render("tpl", &data).unwrap();
+        }
+        assert_eq!(log.len(), 5);
+        let observed: Vec<_> = log
+            .take()
+            .into_iter()
+            .map(|x| (x.template, x.name, x.arm))
+            .collect();
+        let tpl = Some("tpl".to_owned());
+        let plan = Some("plan".to_owned());
+        assert_eq!(observed.iter().filter(|x| x.1.is_none()).count(), 2);
+        assert!(observed.contains(&(tpl.clone(), None, Some(ArmKind::Case))));
+        assert!(observed.contains(&(tpl.clone(), None, None)));
+        assert!(observed.contains(&(tpl.clone(), plan.clone(), Some(ArmKind::Case))));
+        assert!(observed.contains(&(tpl, plan, Some(ArmKind::Default))));
+        assert!(log.is_empty());
+
+        // renders served from the memo are observed too
+        struct Counter(Arc<AtomicUsize>);
+        impl AuditSink for Counter {
+            fn record(&self, observation: SwitchObservation) {
+                assert_eq!(observation.arm, Some(ArmKind::Case));
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let count = Arc::new(AtomicUsize::new(0));
+        let memo = MemoCache::new();
+        let helper = SwitchHelper::new()
+            .with_audit_sink(Counter(count.clone()))
+            .with_memo(memo.clone());
+        handlebars.register_helper("switch", Box::new(helper));
+        for _ in 0..3 {
+            assert_eq!(
+                handlebars.render("tpl", &json!({"plan": "pro"})).unwrap(),
+                "Pro"
+            );
+        }
+        assert_eq!(memo.len(), 1);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_arm_source() {
+        struct Cms;
+
+        impl ArmSource for Cms {
+            fn arms(&self, switch: &str) -> Option<Vec<SourcedArm>> {
+                match switch {
+                    "greeting" => Some(vec![
+                        SourcedArm::case(["en", "EN"], "Hello {{who}}"),
+                        SourcedArm::default_arm("Hi"),
+                    ]),
+                    "broken" => Some(vec![SourcedArm::default_arm("{{#if}}")]),
+                    "partial" => Some(vec![SourcedArm::case(["en"], "Hello")]),
+                    _ => None,
+                }
+            }
+        }
+
+        let mut handlebars = Handlebars::new();
+        let helper = SwitchHelper::new().with_arm_source("cms", Cms);
+        handlebars.register_helper("switch", Box::new(helper));
+        let render = |name: &str, lang: &str| {
+            let tpl = format!(
+                "{{{{#switch lang source=\"cms\" name=\"{name}\"}}}}\
+                    {{{{#case \"fr\"}}}}Bonjour{{{{/case}}}}\
+                    {{{{#default}}}}?{{{{/default}}}}\
+                {{{{/switch}}}}"
+            );
+            let data = json!({"lang": lang, "who": "Jo"});
+            handlebars.render_template(&tpl, &data)
+        };
+        assert_eq!(render("greeting", "EN").unwrap(), "Hello Jo");
+        assert_eq!(render("greeting", "fr").unwrap(), "Hi");
+        assert_eq!(render("partial", "fr").unwrap(), "Bonjour");
+        assert_eq!(render("partial", "de").unwrap(), "?");
+        assert_eq!(render("broken", "en").unwrap(), "?");
+        assert_eq!(render("missing", "fr").unwrap(), "Bonjour");
+
+        for tpl in [
+            "{{#switch 1 source=\"cms\"}}{{/switch}}",
+            "{{#switch 1 source=\"db\" name=\"a\"}}{{/switch}}",
+            "{{#switch 1 source=1 name=\"a\"}}{{/switch}}",
+        ] {
+            assert!(handlebars.render_template(tpl, &json!({})).is_err());
+        }
     }
 }
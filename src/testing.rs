@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use handlebars::Handlebars;
+use proptest::collection::btree_map;
+use proptest::prelude::*;
+use proptest::strategy::{BoxedStrategy, Just, Union};
+use serde_json::Value;
+
+use crate::golden_contexts;
+
+/// Arm Strategies
+///
+/// Builds a proptest strategy for every arm of the switches in the template
+/// registered as `name`, each generating contexts that render that arm,
+/// returned as `(name, strategy)` pairs named like [`golden_contexts`]. The
+/// contexts start from the golden context of the arm and add unrelated
+/// top-level fields with arbitrary values, so properties see varied data
+/// while every arm stays reachable. The same switches are covered as by
+/// [`golden_contexts`]. Returns `None` when no template is registered as
+/// `name`.
+///
+/// Requires the `testing` feature.
+pub fn arm_strategies(
+    registry: &Handlebars,
+    name: &str,
+) -> Option<Vec<(String, BoxedStrategy<Value>)>> {
+    let contexts = golden_contexts(registry, name)?;
+    Some(
+        contexts
+            .into_iter()
+            .map(|(arm, context)| (arm, with_noise(context)))
+            .collect(),
+    )
+}
+
+/// Arm Contexts
+///
+/// Combines the [`arm_strategies`] of the template registered as `name` into
+/// one strategy that picks an arm uniformly before generating a context for
+/// it, so a property test spends as many cases on each arm as on any other
+/// instead of mostly hitting the `{{#default}}`. A template without
+/// enumerable arms gets arbitrary contexts. Returns `None` when no template
+/// is registered as `name`.
+///
+/// Requires the `testing` feature.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # extern crate proptest;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{arm_contexts, SwitchHelper};
+/// use proptest::test_runner::TestRunner;
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars
+///     .register_template_string(
+///         "badge",
+///         "{{#switch status}}{{#case \"paid\"}}Paid{{/case}}{{#default}}Due{{/default}}{{/switch}}",
+///     )
+///     .unwrap();
+///
+/// let strategy = arm_contexts(&handlebars, "badge").unwrap();
+/// TestRunner::default()
+///     .run(&strategy, |context| {
+///         let rendered = handlebars.render("badge", &context).unwrap();
+///         assert!(rendered == "Paid" || rendered == "Due");
+///         Ok(())
+///     })
+///     .unwrap();
+/// # }
+/// ```
+pub fn arm_contexts(registry: &Handlebars, name: &str) -> Option<BoxedStrategy<Value>> {
+    let strategies: Vec<_> = arm_strategies(registry, name)?
+        .into_iter()
+        .map(|(_, strategy)| strategy)
+        .collect();
+    if strategies.is_empty() {
+        return Some(with_noise(Value::Object(Default::default())));
+    }
+    Some(Union::new(strategies).boxed())
+}
+
+/// Adds top-level fields `context` does not have, with arbitrary scalars
+fn with_noise(context: Value) -> BoxedStrategy<Value> {
+    let noise = btree_map("[a-z]{1,8}", scalar(), 0..4);
+    (Just(context), noise)
+        .prop_map(|(mut context, noise): (Value, BTreeMap<String, Value>)| {
+            if let Value::Object(map) = &mut context {
+                for (key, value) in noise {
+                    map.entry(key).or_insert(value);
+                }
+            }
+            context
+        })
+        .boxed()
+}
+
+fn scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        "[ -~]{0,12}".prop_map(Value::String),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arm_contexts, arm_strategies};
+    use crate::SwitchHelper;
+    use handlebars::Handlebars;
+    use proptest::strategy::{Strategy, ValueTree};
+    use proptest::test_runner::TestRunner;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_strategies_hit_each_arm() {
+        let tpl = "\
+            {{#switch user.role}}\
+                {{#case \"admin\"}}A{{/case}}\
+                {{#case \"owner\"}}O{{/case}}\
+                {{#default}}U{{/default}}\
+            {{/switch}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        assert!(handlebars.register_template_string("t", tpl).is_ok());
+
+        let mut runner = TestRunner::deterministic();
+        let strategies = arm_strategies(&handlebars, "t").unwrap();
+        let expected = ["A", "O", "U"];
+        for ((_, strategy), expected) in strategies.iter().zip(expected) {
+            for _ in 0..16 {
+                let context = strategy.new_tree(&mut runner).unwrap().current();
+                assert_eq!(handlebars.render("t", &context).unwrap(), expected);
+            }
+        }
+
+        let strategy = arm_contexts(&handlebars, "t").unwrap();
+        let rendered: HashSet<_> = (0..64)
+            .map(|_| {
+                let context = strategy.new_tree(&mut runner).unwrap().current();
+                handlebars.render("t", &context).unwrap()
+            })
+            .collect();
+        assert_eq!(rendered.len(), 3);
+        assert!(arm_contexts(&handlebars, "missing").is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::Value;
+
+/// Arm Kind
+///
+/// Which kind of arm a [`ArmTrace`] records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArmKind {
+    /// A `{{#case}}` arm.
+    Case,
+    /// A `{{#default}}` arm.
+    Default,
+    /// A `{{#format_case}}` arm.
+    FormatCase,
+}
+
+impl Serialize for ArmKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            ArmKind::Case => "case",
+            ArmKind::Default => "default",
+            ArmKind::FormatCase => "format_case",
+        })
+    }
+}
+
+/// Arm Trace
+///
+/// A single arm of a traced `{{#switch}}`, in the order it was reached.
+/// Serializing an arm with a label leaves out its values, so arms can be
+/// reported by a stable name rather than by literals that may hold personal
+/// data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArmTrace {
+    /// Whether the arm is a `{{#case}}` or a `{{#default}}`.
+    pub kind: ArmKind,
+    /// The values listed by a `{{#case}}`, empty for a `{{#default}}`.
+    pub values: Vec<Value>,
+    /// The `label="..."` of the arm, if any.
+    pub label: Option<String>,
+    /// Whether the arm rendered.
+    pub matched: bool,
+}
+
+impl Serialize for ArmTrace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ArmTrace", 4)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("label", &self.label)?;
+        // a label stands in for values that may hold personal data
+        match self.label {
+            Some(_) => state.serialize_field("values", &None::<Vec<Value>>)?,
+            None => state.serialize_field("values", &self.values)?,
+        }
+        state.serialize_field("matched", &self.matched)?;
+        state.end()
+    }
+}
+
+/// Switch Trace
+///
+/// A single `{{#switch}}` encountered while rendering, along with the
+/// switches rendered inside its arms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchTrace {
+    /// The name of the template being rendered, if any.
+    pub template: Option<String>,
+    /// The name given to the switch by `name="..."`, if any.
+    pub name: Option<String>,
+    /// The 1-based line and column where the block of the switch begins.
+    pub position: Option<(usize, usize)>,
+    /// The value switched on.
+    pub value: Value,
+    /// The arms reached, in template order.
+    pub arms: Vec<ArmTrace>,
+    /// Whether the output came from a [`MemoCache`](crate::MemoCache), in
+    /// which case no arms were reached.
+    pub cached: bool,
+    /// The switches rendered inside the arms of this one.
+    pub children: Vec<SwitchTrace>,
+}
+
+impl SwitchTrace {
+    /// Returns the arm that rendered, if any.
+    pub fn chosen(&self) -> Option<&ArmTrace> {
+        self.arms.iter().find(|arm| arm.matched)
+    }
+}
+
+impl Serialize for SwitchTrace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let chosen = self.arms.iter().position(|arm| arm.matched);
+        let mut state = serializer.serialize_struct("SwitchTrace", 9)?;
+        state.serialize_field("template", &self.template)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("line", &self.position.map(|p| p.0))?;
+        state.serialize_field("column", &self.position.map(|p| p.1))?;
+        state.serialize_field("value", &self.value)?;
+        state.serialize_field("arms", &self.arms)?;
+        state.serialize_field("chosen", &chosen)?;
+        state.serialize_field("cached", &self.cached)?;
+        state.serialize_field("children", &self.children)?;
+        state.end()
+    }
+}
+
+#[derive(Debug, Default)]
+struct TraceState {
+    roots: Vec<SwitchTrace>,
+    open: Vec<SwitchTrace>,
+}
+
+/// Render Tracer
+///
+/// Records a tree of every `{{#switch}}` rendered by a
+/// [`SwitchHelper`](crate::SwitchHelper) set up with
+/// [`with_tracer`](crate::SwitchHelper::with_tracer). The tracer is cheap to
+/// clone and every clone shares the same trace, so keep a handle around and
+/// [`take`](RenderTracer::take) the trace after each render. Renders running
+/// at the same time on the same tracer interleave their traces, so give each
+/// concurrent render its own registry when tracing.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{RenderTracer, SwitchHelper};
+///
+/// let tracer = RenderTracer::new();
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new().with_tracer(tracer.clone())));
+///
+/// let tpl = "\
+///     {{#switch access}}\
+///         {{#case \"admin\"}}Admin{{/case}}\
+///         {{#default}}User{{/default}}\
+///     {{/switch}}\
+/// ";
+/// handlebars.render_template(tpl, &json!({"access": "guest"})).unwrap();
+///
+/// let trace = serde_json::to_value(tracer.take()).unwrap();
+/// assert_eq!(trace[0]["value"], json!("guest"));
+/// assert_eq!(trace[0]["chosen"], json!(1));
+/// assert_eq!(trace[0]["arms"][1]["kind"], json!("default"));
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RenderTracer {
+    state: Arc<Mutex<TraceState>>,
+}
+
+impl RenderTracer {
+    /// Creates an empty tracer.
+    pub fn new() -> RenderTracer {
+        RenderTracer::default()
+    }
+
+    /// Returns the switches traced so far, leaving the tracer empty.
+    pub fn take(&self) -> Vec<SwitchTrace> {
+        let mut state = self.state();
+        // switches left open by a failed render are kept as well
+        while !state.open.is_empty() {
+            state.close();
+        }
+        mem::take(&mut state.roots)
+    }
+
+    /// Starts tracing a switch, nested in the switch currently open.
+    pub(crate) fn enter(&self, switch: SwitchTrace) {
+        self.state().open.push(switch);
+    }
+
+    /// Records an arm of the switch currently open.
+    pub(crate) fn arm(&self, arm: ArmTrace) {
+        if let Some(switch) = self.state().open.last_mut() {
+            switch.arms.push(arm);
+        }
+    }
+
+    /// Finishes tracing the switch currently open.
+    pub(crate) fn leave(&self) {
+        self.state().close();
+    }
+
+    fn state(&self) -> MutexGuard<'_, TraceState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl TraceState {
+    fn close(&mut self) {
+        if let Some(switch) = self.open.pop() {
+            match self.open.last_mut() {
+                Some(parent) => parent.children.push(switch),
+                None => self.roots.push(switch),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArmKind, ArmTrace, RenderTracer, SwitchTrace};
+
+    fn switch(value: &str) -> SwitchTrace {
+        SwitchTrace {
+            template: None,
+            name: Some(format!("{value}-switch")),
+            position: Some((1, 2)),
+            value: json!(value),
+            arms: Vec::new(),
+            cached: false,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_trace_tree() {
+        let tracer = RenderTracer::new();
+        tracer.enter(switch("outer"));
+        tracer.arm(ArmTrace {
+            kind: ArmKind::Case,
+            values: vec![json!("outer")],
+            label: None,
+            matched: true,
+        });
+        tracer.arm(ArmTrace {
+            kind: ArmKind::Case,
+            values: vec![json!("jane@example.com")],
+            label: Some("vip".to_owned()),
+            matched: false,
+        });
+        tracer.enter(switch("inner"));
+        tracer.leave();
+        tracer.leave();
+        tracer.enter(switch("unclosed"));
+
+        let trace = tracer.take();
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].children, vec![switch("inner")]);
+        assert_eq!(trace[0].chosen().map(|x| x.kind), Some(ArmKind::Case));
+        assert!(tracer.take().is_empty());
+
+        assert_eq!(
+            serde_json::to_value(&trace[0]).unwrap(),
+            json!({
+                "template": null,
+                "name": "outer-switch",
+                "line": 1,
+                "column": 2,
+                "value": "outer",
+                "arms": [
+                    {"kind": "case", "label": null, "values": ["outer"], "matched": true},
+                    {"kind": "case", "label": "vip", "values": null, "matched": false},
+                ],
+                "chosen": 0,
+                "cached": false,
+                "children": [{
+                    "template": null,
+                    "name": "inner-switch",
+                    "line": 1,
+                    "column": 2,
+                    "value": "inner",
+                    "arms": [],
+                    "chosen": null,
+                    "cached": false,
+                    "children": [],
+                }],
+            })
+        );
+    }
+}
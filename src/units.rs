@@ -0,0 +1,128 @@
+use serde_json::Value;
+
+/// What a unit measures
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Dimension {
+    Bytes,
+    Duration,
+}
+
+/// Units with the factor converting them to bytes or milliseconds, given as
+/// a multiplier and a divisor so common conversions stay exact
+const UNITS: &[(&str, Dimension, f64, f64)] = &[
+    ("B", Dimension::Bytes, 1.0, 1.0),
+    ("KB", Dimension::Bytes, 1e3, 1.0),
+    ("MB", Dimension::Bytes, 1e6, 1.0),
+    ("GB", Dimension::Bytes, 1e9, 1.0),
+    ("TB", Dimension::Bytes, 1e12, 1.0),
+    ("KiB", Dimension::Bytes, 1024.0, 1.0),
+    ("MiB", Dimension::Bytes, 1048576.0, 1.0),
+    ("GiB", Dimension::Bytes, 1073741824.0, 1.0),
+    ("TiB", Dimension::Bytes, 1099511627776.0, 1.0),
+    ("ns", Dimension::Duration, 1.0, 1e6),
+    ("us", Dimension::Duration, 1.0, 1e3),
+    ("µs", Dimension::Duration, 1.0, 1e3),
+    ("ms", Dimension::Duration, 1.0, 1.0),
+    ("s", Dimension::Duration, 1e3, 1.0),
+    ("m", Dimension::Duration, 6e4, 1.0),
+    ("min", Dimension::Duration, 6e4, 1.0),
+    ("h", Dimension::Duration, 3.6e6, 1.0),
+    ("d", Dimension::Duration, 8.64e7, 1.0),
+];
+
+/// A number with an optional unit, in canonical bytes or milliseconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Quantity {
+    value: f64,
+    dimension: Option<Dimension>,
+}
+
+impl Quantity {
+    fn of(value: &Value) -> Option<Quantity> {
+        match value {
+            Value::Number(n) => n.as_f64().map(|value| Quantity {
+                value,
+                dimension: None,
+            }),
+            Value::String(s) => parse(s),
+            _ => None,
+        }
+    }
+}
+
+/// Parses strings like `"5MB"`, `"1500 ms"`, or `"2h"`. Byte units also
+/// match in lower case, while duration units are case-sensitive so `m` stays
+/// minutes.
+fn parse(s: &str) -> Option<Quantity> {
+    let s = s.trim();
+    let split = s
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && matches!(c, '-' | '+'))))
+        .map_or(s.len(), |(i, _)| i);
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let unit = unit.trim_start();
+    let (_, dimension, multiplier, divisor) = UNITS.iter().find(|(name, dimension, ..)| {
+        *name == unit || (*dimension == Dimension::Bytes && name.eq_ignore_ascii_case(unit))
+    })?;
+    Some(Quantity {
+        value: number * multiplier / divisor,
+        dimension: Some(*dimension),
+    })
+}
+
+/// Compares two values as quantities when at least one of them is a string
+/// with a unit, returning `None` when they are not both quantities. A unit
+/// string equals a plain number in its canonical unit, bytes or milliseconds.
+pub(crate) fn same_quantity(a: &Value, b: &Value) -> Option<bool> {
+    if !(a.is_string() || b.is_string()) {
+        return None;
+    }
+    let (a, b) = (Quantity::of(a)?, Quantity::of(b)?);
+    if a.dimension.is_none() && b.dimension.is_none() {
+        return None;
+    }
+    let compatible = a.dimension.is_none() || b.dimension.is_none() || a.dimension == b.dimension;
+    Some(compatible && a.value == b.value)
+}
+
+/// Reads a number or a string with a unit as a number in its canonical
+/// unit, bytes or milliseconds.
+pub(crate) fn as_canonical_number(value: &Value) -> Option<f64> {
+    Quantity::of(value)
+        .filter(|q| value.is_number() || q.dimension.is_some())
+        .map(|q| q.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{as_canonical_number, same_quantity};
+
+    #[test]
+    fn test_same_quantity() {
+        assert_eq!(same_quantity(&json!("5MB"), &json!("5000 kb")), Some(true));
+        assert_eq!(same_quantity(&json!("1500ms"), &json!("1.5s")), Some(true));
+        assert_eq!(same_quantity(&json!("2h"), &json!("120m")), Some(true));
+        assert_eq!(
+            same_quantity(&json!("1500000ns"), &json!("1.5ms")),
+            Some(true)
+        );
+        assert_eq!(same_quantity(&json!("1KiB"), &json!(1024)), Some(true));
+        assert_eq!(same_quantity(&json!("1s"), &json!("1000B")), Some(false));
+        assert_eq!(same_quantity(&json!("1s"), &json!("2s")), Some(false));
+
+        assert_eq!(same_quantity(&json!("1"), &json!(1)), None);
+        assert_eq!(same_quantity(&json!("1M"), &json!("1M")), None);
+        assert_eq!(same_quantity(&json!("fast"), &json!("1s")), None);
+        assert_eq!(same_quantity(&json!(1), &json!(1)), None);
+    }
+
+    #[test]
+    fn test_as_canonical_number() {
+        assert_eq!(as_canonical_number(&json!("-2.5 s")), Some(-2500.0));
+        assert_eq!(as_canonical_number(&json!(7)), Some(7.0));
+        assert_eq!(as_canonical_number(&json!("7")), None);
+        assert_eq!(as_canonical_number(&json!("ms")), None);
+        assert_eq!(as_canonical_number(&json!(null)), None);
+    }
+}
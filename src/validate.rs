@@ -0,0 +1,512 @@
+use std::fmt;
+
+use handlebars::template::{HelperTemplate, Parameter, TemplateElement};
+use handlebars::{Handlebars, Template};
+use serde_json::Value;
+
+use crate::compat::CompatMode;
+
+/// The arm helpers of this crate and the blocks that provide them.
+pub(crate) const ARMS: &[(&str, &[&str])] = &[
+    (
+        "case",
+        &[
+            "switch",
+            "switch_flags",
+            "switch_entries",
+            "switch_ordinal",
+            "bins",
+            "cmp",
+            "fsm",
+        ],
+    ),
+    (
+        "default",
+        &[
+            "switch",
+            "switch_flags",
+            "switch_entries",
+            "switch_ordinal",
+            "bins",
+            "cmp",
+            "fsm",
+        ],
+    ),
+    (
+        "format_case",
+        &[
+            "switch",
+            "switch_flags",
+            "switch_entries",
+            "switch_ordinal",
+            "bins",
+            "cmp",
+            "fsm",
+        ],
+    ),
+    ("some", &["switch_some"]),
+    ("none", &["switch_some"]),
+    ("ok", &["switch_result"]),
+    ("err", &["switch_result"]),
+];
+
+/// Switch Issue
+///
+/// A misuse of the switch helper family found by [`validate_template`] or
+/// [`validate_registry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwitchIssue {
+    /// The name of the template, if it has one.
+    pub template: Option<String>,
+    /// The line and column of the offending block, if known.
+    pub position: Option<(usize, usize)>,
+    /// What is wrong.
+    pub kind: SwitchIssueKind,
+}
+
+/// Switch Issue Kind
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SwitchIssueKind {
+    /// An arm such as `{{#case}}` appears outside of every block providing it.
+    ArmOutsideSwitch {
+        /// The name of the arm helper.
+        arm: String,
+    },
+    /// A block such as `{{#switch}}` is missing the value to switch on.
+    MissingValue {
+        /// The name of the block helper.
+        helper: String,
+    },
+    /// A block has more than one `{{#default}}` arm.
+    MultipleDefaults,
+    /// A `{{#case}}` lists a value that an earlier `{{#case}}` of the same
+    /// block already matches, so it never matches it.
+    DuplicateCase {
+        /// The value listed twice.
+        value: Value,
+    },
+    /// A block such as `{{#switch}}` has no `{{#default}}` arm although one
+    /// is required, see [`ValidateOptions::with_require_default`].
+    MissingDefault {
+        /// The name of the block helper.
+        helper: String,
+    },
+}
+
+impl fmt::Display for SwitchIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            SwitchIssueKind::ArmOutsideSwitch { arm } => {
+                let parents = ARMS
+                    .iter()
+                    .find(|(name, _)| name == arm)
+                    .map(|(_, parents)| parents.join("`/`#"))
+                    .unwrap_or_default();
+                write!(f, "`#{arm}` must appear inside `#{parents}`")?;
+            }
+            SwitchIssueKind::MissingValue { helper } => {
+                write!(f, "`#{helper}` requires a value")?;
+            }
+            SwitchIssueKind::MultipleDefaults => {
+                write!(f, "more than one `#default` in the same block")?;
+            }
+            SwitchIssueKind::DuplicateCase { value } => {
+                write!(
+                    f,
+                    "`#case` value {value} is already matched by an earlier `#case`"
+                )?;
+            }
+            SwitchIssueKind::MissingDefault { helper } => {
+                write!(f, "`#{helper}` requires a `#default`")?;
+            }
+        }
+        if let Some(template) = &self.template {
+            write!(f, " in template {template:?}")?;
+        }
+        if let Some((line, column)) = self.position {
+            write!(f, " at line {line}, column {column}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate Options
+///
+/// How the helpers that [`validate_template_with`] and
+/// [`validate_registry_with`] check against are configured, so issues that
+/// depend on the options of the `{{#switch}}` are judged the same way it
+/// renders.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValidateOptions {
+    compat: CompatMode,
+    require_default: bool,
+}
+
+impl ValidateOptions {
+    /// Creates the options of a `{{#switch}}` built with
+    /// [`SwitchHelper::new`](crate::SwitchHelper::new).
+    pub fn new() -> ValidateOptions {
+        ValidateOptions::default()
+    }
+
+    /// Checks against switches in the compat mode `compat`, see
+    /// [`SwitchHelper::with_compat`](crate::SwitchHelper::with_compat).
+    /// Every matching `{{#case}}` renders in [`CompatMode::Loose`], so
+    /// duplicate values are not reported there.
+    pub fn with_compat(mut self, compat: CompatMode) -> ValidateOptions {
+        self.compat = compat;
+        self
+    }
+
+    /// Requires every block with `{{#case}}` arms to also have a
+    /// `{{#default}}`, since a value that matches none of them would
+    /// otherwise render nothing without notice. Not required by default.
+    pub fn with_require_default(mut self, require: bool) -> ValidateOptions {
+        self.require_default = require;
+        self
+    }
+}
+
+/// Validate Template
+///
+/// Walks a compiled template and reports every misuse of the switch helper
+/// family, assuming the helpers are registered under their documented names
+/// with their default options.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars::Template;
+/// use handlebars_switch::{validate_template, SwitchIssueKind};
+///
+/// let tpl = Template::compile("{{#switch}}{{/switch}}{{#case 1}}{{/case}}").unwrap();
+/// let issues = validate_template(&tpl);
+///
+/// assert_eq!(issues.len(), 2);
+/// assert_eq!(
+///     issues[1].kind,
+///     SwitchIssueKind::ArmOutsideSwitch { arm: "case".to_owned() }
+/// );
+/// ```
+pub fn validate_template(template: &Template) -> Vec<SwitchIssue> {
+    validate_template_with(template, &ValidateOptions::new())
+}
+
+/// Validate Template With
+///
+/// Reports the issues [`validate_template`] would, with the helpers
+/// configured as described by `options`.
+pub fn validate_template_with(template: &Template, options: &ValidateOptions) -> Vec<SwitchIssue> {
+    let mut validator = Validator {
+        template: template.name.clone(),
+        options: *options,
+        issues: Vec::new(),
+    };
+    validator.walk(template, &[]);
+    validator.issues
+}
+
+/// Validate Registry
+///
+/// Walks every template registered with `registry`, in the order of their
+/// names, and reports the issues [`validate_template`] would, which suits a
+/// self-check at startup.
+///
+/// # Examples
+///
+/// ```
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{
+///     validate_registry, validate_registry_with, SwitchIssueKind, ValidateOptions,
+/// };
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars
+///     .register_template_string("plan", "{{#switch plan}}{{#case 1 1}}{{/case}}{{/switch}}")
+///     .unwrap();
+/// assert_eq!(
+///     validate_registry(&handlebars)[0].kind,
+///     SwitchIssueKind::DuplicateCase { value: 1.into() }
+/// );
+///
+/// let options = ValidateOptions::new().with_require_default(true);
+/// assert_eq!(validate_registry_with(&handlebars, &options).len(), 2);
+/// ```
+pub fn validate_registry(registry: &Handlebars) -> Vec<SwitchIssue> {
+    validate_registry_with(registry, &ValidateOptions::new())
+}
+
+/// Validate Registry With
+///
+/// Reports the issues [`validate_registry`] would, with the helpers
+/// configured as described by `options`.
+pub fn validate_registry_with(
+    registry: &Handlebars,
+    options: &ValidateOptions,
+) -> Vec<SwitchIssue> {
+    let mut templates: Vec<_> = registry.get_templates().iter().collect();
+    templates.sort_by_key(|(name, _)| *name);
+
+    let mut validator = Validator {
+        template: None,
+        options: *options,
+        issues: Vec::new(),
+    };
+    for (name, template) in templates {
+        validator.template = Some(name.clone());
+        validator.walk(template, &[]);
+    }
+    validator.issues
+}
+
+struct Validator {
+    template: Option<String>,
+    options: ValidateOptions,
+    issues: Vec<SwitchIssue>,
+}
+
+impl Validator {
+    fn walk(&mut self, template: &Template, parents: &[&str]) {
+        for (index, element) in template.elements.iter().enumerate() {
+            let position = template.mapping.get(index).map(|m| (m.0, m.1));
+            match element {
+                TemplateElement::HelperBlock(ht) => self.visit(ht, position, parents),
+                TemplateElement::DecoratorBlock(dt) | TemplateElement::PartialBlock(dt) => {
+                    if let Some(t) = &dt.template {
+                        self.walk(t, parents);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn visit(&mut self, ht: &HelperTemplate, position: Option<(usize, usize)>, parents: &[&str]) {
+        let name = ht.name.as_name().unwrap_or_default();
+
+        if let Some((_, providers)) = ARMS.iter().find(|(arm, _)| *arm == name) {
+            if !parents.iter().any(|parent| providers.contains(parent)) {
+                self.report(
+                    position,
+                    SwitchIssueKind::ArmOutsideSwitch {
+                        arm: name.to_owned(),
+                    },
+                );
+            }
+        }
+
+        let is_provider = ARMS.iter().any(|(_, providers)| providers.contains(&name));
+        let missing_value = match name {
+            "switch_flags" => false,
+            "fsm" => !ht.hash.contains_key("to"),
+            _ => ht.params.is_empty(),
+        };
+        if is_provider && missing_value {
+            self.report(
+                position,
+                SwitchIssueKind::MissingValue {
+                    helper: name.to_owned(),
+                },
+            );
+        }
+        if is_provider {
+            let defaults = ht
+                .template
+                .iter()
+                .flat_map(|t| &t.elements)
+                .filter(|e| {
+                    matches!(e, TemplateElement::HelperBlock(arm) if arm.name.as_name() == Some("default"))
+                })
+                .count();
+            if defaults > 1 {
+                self.report(position, SwitchIssueKind::MultipleDefaults);
+            }
+            let has_cases = ARMS
+                .iter()
+                .any(|(arm, providers)| *arm == "case" && providers.contains(&name));
+            if has_cases && defaults == 0 && self.options.require_default {
+                self.report(
+                    position,
+                    SwitchIssueKind::MissingDefault {
+                        helper: name.to_owned(),
+                    },
+                );
+            }
+            // sharded blocks pick arms without looking at their values, and
+            // multi-match ones render every arm that matches
+            let renders_all = self.options.compat == CompatMode::Loose
+                || (ht.hash.get("multi"))
+                    .is_some_and(|x| *x != Parameter::Literal(Value::Bool(false)));
+            if has_cases && !ht.hash.contains_key("shard") && !renders_all {
+                if let Some(t) = &ht.template {
+                    self.find_duplicate_cases(t);
+                }
+            }
+        }
+
+        let mut nested = parents.to_vec();
+        nested.push(name);
+        for t in ht.template.iter().chain(&ht.inverse) {
+            self.walk(t, &nested);
+        }
+    }
+
+    /// Reports the values of the `{{#case}}` arms of a block that earlier
+    /// arms already list
+    fn find_duplicate_cases(&mut self, template: &Template) {
+        let mut seen: Vec<&Value> = Vec::new();
+        for (index, element) in template.elements.iter().enumerate() {
+            let TemplateElement::HelperBlock(arm) = element else {
+                continue;
+            };
+            // an inverse arm matches everything but its values, a guarded one
+            // may pass its values on to a later arm, and the values of a glob
+            // one are patterns
+            if arm.name.as_name() != Some("case")
+                || arm.hash.contains_key("not")
+                || arm.hash.contains_key("if")
+                || arm.hash.contains_key("glob")
+            {
+                continue;
+            }
+            let position = template.mapping.get(index).map(|m| (m.0, m.1));
+            for param in &arm.params {
+                let Parameter::Literal(value) = param else {
+                    continue;
+                };
+                if seen.contains(&value) {
+                    self.report(
+                        position,
+                        SwitchIssueKind::DuplicateCase {
+                            value: value.clone(),
+                        },
+                    );
+                } else {
+                    seen.push(value);
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, position: Option<(usize, usize)>, kind: SwitchIssueKind) {
+        self.issues.push(SwitchIssue {
+            template: self.template.clone(),
+            position,
+            kind,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        validate_registry, validate_registry_with, validate_template, SwitchIssueKind,
+        ValidateOptions,
+    };
+    use crate::CompatMode;
+    use handlebars::{Handlebars, Template};
+
+    #[test]
+    fn test_validate_template() {
+        let tpl = Template::compile(
+            "\
+            {{#switch a}}\
+                {{#if b}}{{#case 1}}one{{/case}}{{/if}}\
+                {{#default}}x{{/default}}{{#default}}y{{/default}}\
+            {{/switch}}\n\
+            {{#switch_some b}}{{#case 1}}{{/case}}{{/switch_some}}",
+        )
+        .unwrap();
+
+        let issues = validate_template(&tpl);
+        let kinds: Vec<_> = issues.iter().map(|x| &x.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &SwitchIssueKind::MultipleDefaults,
+                &SwitchIssueKind::ArmOutsideSwitch {
+                    arm: "case".to_owned()
+                }
+            ]
+        );
+        assert_eq!(issues[1].position, Some((2, 19)));
+        assert_eq!(
+            issues[1].to_string(),
+            "`#case` must appear inside `#switch`/`#switch_flags`/`#switch_entries`/`#switch_ordinal`/`#bins`/`#cmp`/`#fsm` at line 2, column 19"
+        );
+    }
+
+    #[test]
+    fn test_valid_template() {
+        let tpl = Template::compile(
+            "{{#switch_result r}}{{#ok}}{{#switch x}}{{#case 1}}{{/case}}{{/switch}}{{/ok}}{{/switch_result}}\
+            {{#fsm from=a to=b}}{{#default}}{{/default}}{{/fsm}}",
+        )
+        .unwrap();
+
+        assert!(validate_template(&tpl).is_empty());
+    }
+
+    #[test]
+    fn test_validate_registry() {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string(
+                "b",
+                "{{#switch x}}{{#case 1 \"a\"}}{{/case}}{{#case \"a\" x}}{{/case}}{{/switch}}",
+            )
+            .unwrap();
+        handlebars
+            .register_template_string(
+                "a",
+                "{{#case 1}}{{/case}}\
+                {{#switch x shard=true}}{{#case 1}}{{/case}}{{#case 1}}{{/case}}{{/switch}}\
+                {{#switch x}}{{#case 1}}{{/case}}{{#case 1 not=true}}{{/case}}{{#default}}{{/default}}{{/switch}}\
+                {{#switch x}}{{#case 1 if=y}}{{/case}}{{#case 1}}{{/case}}{{#default}}{{/default}}{{/switch}}\
+                {{#switch x multi=true}}{{#case 1}}{{/case}}{{#case 1}}{{/case}}{{#default}}{{/default}}{{/switch}}\
+                {{#switch x}}{{#case \"a*\" glob=true}}{{/case}}{{#case \"a*\"}}{{/case}}{{#default}}{{/default}}{{/switch}}",
+            )
+            .unwrap();
+
+        let issues = validate_registry(&handlebars);
+        let found: Vec<_> = issues
+            .iter()
+            .map(|x| (x.template.as_deref().unwrap(), &x.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "a",
+                    &SwitchIssueKind::ArmOutsideSwitch {
+                        arm: "case".to_owned()
+                    }
+                ),
+                ("b", &SwitchIssueKind::DuplicateCase { value: json!("a") }),
+            ]
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "`#case` value \"a\" is already matched by an earlier `#case` in template \"b\" at line 1, column 38"
+        );
+
+        let loose = ValidateOptions::new().with_compat(CompatMode::Loose);
+        let issues = validate_registry_with(&handlebars, &loose);
+        assert!(issues
+            .iter()
+            .all(|x| !matches!(x.kind, SwitchIssueKind::DuplicateCase { .. })));
+
+        handlebars.set_strict_mode(true);
+        assert_eq!(validate_registry(&handlebars).len(), 2);
+
+        let require_default = ValidateOptions::new().with_require_default(true);
+        let issues = validate_registry_with(&handlebars, &require_default);
+        let missing: Vec<_> = issues
+            .iter()
+            .filter(|x| matches!(x.kind, SwitchIssueKind::MissingDefault { .. }))
+            .map(|x| x.template.as_deref().unwrap())
+            .collect();
+        assert_eq!(missing, ["a", "b"]);
+    }
+}
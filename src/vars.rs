@@ -0,0 +1,226 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+    Renderable, StringOutput,
+};
+
+use serde_json::{Map, Value};
+
+use crate::switch::SWITCH_LOCALS;
+
+/// The `@` variable holding every variable set so far, by name
+const VARS: &str = "vars";
+
+/// Set Helper
+///
+/// Provides the `{{set}}` helper to a Handlebars template. It sets every
+/// hash parameter as an `@` variable, so `{{set class="is-paid"}}` inside a
+/// `{{#case}}` makes `{{@class}}` available for the rest of the arm and,
+/// once the `{{#switch}}` is done, for the rest of the scope around it. A
+/// switch can then work out a value once that several later sections reuse.
+///
+/// Variables are carried out of the blocks of the switch helper family,
+/// nested ones included, up to the enclosing block of any other helper, such
+/// as an `{{#each}}`, or the template itself. The
+/// `@` variables the switch keeps its own state in, like `@match`, cannot be
+/// set.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{SetHelper, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars.register_helper("set", Box::new(SetHelper));
+///
+/// let tpl = "\
+///     {{#switch status}}\
+///         {{#case \"paid\"}}{{set class=\"is-paid\"}}{{/case}}\
+///         {{#default}}{{set class=\"is-due\"}}{{/default}}\
+///     {{/switch}}\
+///     <h1 class=\"{{@class}}\">{{title}}</h1><p class=\"{{@class}}\"></p>\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"status": "paid", "title": "Invoice"})).unwrap(),
+///     "<h1 class=\"is-paid\">Invoice</h1><p class=\"is-paid\"></p>"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SetHelper;
+
+impl HelperDef for SetHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        for (name, value) in h.hash() {
+            set_var(rc, "set", name, value.value().clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Capture Helper
+///
+/// Provides the `{{#capture}}` helper to a Handlebars template. It renders
+/// its block into the `@` variable named by its param instead of the output,
+/// so `{{#capture "badge"}}...{{/capture}}` can build markup in an arm for
+/// `{{{@badge}}}` to place later. The variable is carried out of switches
+/// like the ones of [`SetHelper`].
+///
+/// # Examples
+///
+/// ```
+/// # extern crate handlebars_switch;
+/// # extern crate handlebars;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use handlebars::Handlebars;
+/// use handlebars_switch::{CaptureHelper, SwitchHelper};
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+/// handlebars.register_helper("capture", Box::new(CaptureHelper));
+///
+/// let tpl = "\
+///     {{#switch role}}\
+///         {{#case \"admin\"}}{{#capture \"badge\"}}<b>{{name}}</b>{{/capture}}{{/case}}\
+///     {{/switch}}\
+///     Hi {{{@badge}}}!\
+/// ";
+///
+/// assert_eq!(
+///     handlebars.render_template(tpl, &json!({"role": "admin", "name": "Al"})).unwrap(),
+///     "Hi <b>Al</b>!"
+/// );
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct CaptureHelper;
+
+impl HelperDef for CaptureHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("capture", 0))?;
+        let name = name.value().as_str().ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "capture",
+                "0".to_owned(),
+                "string".to_owned(),
+            )
+        })?;
+
+        let mut buffer = StringOutput::new();
+        if let Some(t) = h.template() {
+            t.render(r, ctx, rc, &mut buffer)?;
+        }
+        set_var(rc, "capture", name, Value::String(buffer.into_string()?))
+    }
+}
+
+/// Sets the `@` variable `name` in the current block and records it to be
+/// carried out of the enclosing switches
+pub(crate) fn set_var(
+    rc: &mut RenderContext,
+    helper: &'static str,
+    name: &str,
+    value: Value,
+) -> HelperResult {
+    if SWITCH_LOCALS.contains(&name) {
+        return Err(RenderErrorReason::Other(format!(
+            "`{{{{{helper}}}}}` cannot set the reserved variable `@{name}`"
+        ))
+        .into());
+    }
+    if let Some(block) = rc.block_mut() {
+        let mut vars = take_vars(block.get_local_var(VARS));
+        vars.insert(name.to_owned(), value.clone());
+        block.set_local_var(name, value);
+        block.set_local_var(VARS, Value::Object(vars));
+    }
+    Ok(())
+}
+
+fn take_vars(vars: Option<&Value>) -> Map<String, Value> {
+    match vars {
+        Some(Value::Object(vars)) => vars.clone(),
+        _ => Map::new(),
+    }
+}
+
+/// Reads the variables set so far in the current block, to carry them into
+/// another block with [`restore_vars`]
+pub(crate) fn saved_vars(rc: &RenderContext) -> Option<Value> {
+    rc.block()?.get_local_var(VARS).cloned()
+}
+
+/// Sets the variables read by [`saved_vars`] in the current block
+pub(crate) fn restore_vars(rc: &mut RenderContext, vars: Option<Value>) {
+    let (Some(Value::Object(vars)), Some(block)) = (vars, rc.block_mut()) else {
+        return;
+    };
+    let mut all = take_vars(block.get_local_var(VARS));
+    for (name, value) in vars {
+        block.set_local_var(&name, value.clone());
+        all.insert(name, value);
+    }
+    block.set_local_var(VARS, Value::Object(all));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CaptureHelper, SetHelper};
+    use crate::{SwitchHelper, SwitchSomeHelper};
+    use handlebars::Handlebars;
+
+    #[test]
+    fn test_vars_leave_switches() {
+        let tpl = "\
+            {{#switch a}}\
+                {{#case 1 context=inner}}\
+                    {{#switch_some b}}{{#some}}{{set x=1}}{{/some}}{{/switch_some}}\
+                    {{#switch b}}{{#default}}{{#capture \"y\"}}<{{b}}>{{/capture}}{{/default}}{{/switch}}\
+                {{/case}}\
+            {{/switch}}\
+            {{@x}}{{{@y}}}\
+            {{#each items}}{{#switch this}}{{#default}}{{set z=this}}{{/default}}{{/switch}}{{@z}}{{/each}}\
+            {{@z}}\
+        ";
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("switch", Box::new(SwitchHelper::new()));
+        handlebars.register_helper("switch_some", Box::new(SwitchSomeHelper));
+        handlebars.register_helper("set", Box::new(SetHelper));
+        handlebars.register_helper("capture", Box::new(CaptureHelper));
+
+        let data = json!({"a": 1, "inner": {"b": 2}, "items": ["p", "q"]});
+        assert_eq!(handlebars.render_template(tpl, &data).unwrap(), "1<2>pq");
+
+        for invalid in [
+            "{{set match=1}}",
+            "{{set vars=1}}",
+            "{{#capture 1}}{{/capture}}",
+        ] {
+            assert!(handlebars.render_template(invalid, &data).is_err());
+        }
+    }
+}